@@ -0,0 +1,191 @@
+use std::{collections::HashMap, sync::Arc};
+
+use wasm_pkg_common::{
+    Error,
+    config::{Config, RegistryConfig},
+    metadata::{
+        GIT_PROTOCOL, INDEX_PROTOCOL, LOCAL_PROTOCOL, OCI_LAYOUT_PROTOCOL, OCI_PROTOCOL,
+        RegistryMetadata,
+    },
+    registry::Registry,
+};
+
+use crate::{
+    Client, LoaderPublisher,
+    git::GitBackend,
+    index::IndexBackend,
+    local::LocalBackend,
+    oci::{OciBackend, OciLayoutBackend},
+};
+
+/// Creates the backend for a registry. Factories are registered with a [`ClientBuilder`] under the
+/// name of the backend type they create, which is matched against the default backend of a
+/// registry's config or the preferred protocol of its metadata.
+///
+/// Any `Fn(&Registry, &RegistryConfig, &RegistryMetadata)` closure returning a boxed backend is a
+/// factory.
+pub trait BackendFactory: Send + Sync {
+    /// Creates the backend for the given registry. Backend specific config can be read with
+    /// [`RegistryConfig::backend_config`] and [`RegistryMetadata::protocol_config`].
+    fn create(
+        &self,
+        registry: &Registry,
+        registry_config: &RegistryConfig,
+        registry_meta: &RegistryMetadata,
+    ) -> Result<Box<dyn LoaderPublisher>, Error>;
+}
+
+impl<F> BackendFactory for F
+where
+    F: Fn(&Registry, &RegistryConfig, &RegistryMetadata) -> Result<Box<dyn LoaderPublisher>, Error>
+        + Send
+        + Sync,
+{
+    fn create(
+        &self,
+        registry: &Registry,
+        registry_config: &RegistryConfig,
+        registry_meta: &RegistryMetadata,
+    ) -> Result<Box<dyn LoaderPublisher>, Error> {
+        self(registry, registry_config, registry_meta)
+    }
+}
+
+pub(crate) type BackendFactories = HashMap<String, Arc<dyn BackendFactory>>;
+
+/// A builder for a [`Client`] with additional backend types.
+///
+/// ```no_run
+/// # use async_trait::async_trait;
+/// # use wasm_pkg_client::{
+/// #     Client, Config, ContentStream, Error, LoaderPublisher, PackageLoader, PackagePublisher,
+/// #     PackageRef, PublishingSource, Registry, RegistryConfig, RegistryMetadata, Release,
+/// #     Version, VersionInfo,
+/// # };
+/// /// A backend without any packages
+/// struct EmptyBackend;
+///
+/// # #[async_trait]
+/// # impl PackageLoader for EmptyBackend {
+/// #     async fn list_all_versions(&self, _: &PackageRef) -> Result<Vec<VersionInfo>, Error> {
+/// #         Err(Error::PackageNotFound)
+/// #     }
+/// #     async fn get_release(&self, _: &PackageRef, _: &Version) -> Result<Release, Error> {
+/// #         Err(Error::PackageNotFound)
+/// #     }
+/// #     async fn stream_content_unvalidated(
+/// #         &self,
+/// #         _: &PackageRef,
+/// #         _: &Release,
+/// #     ) -> Result<ContentStream, Error> {
+/// #         Err(Error::PackageNotFound)
+/// #     }
+/// # }
+/// # #[async_trait]
+/// # impl PackagePublisher for EmptyBackend {
+/// #     async fn publish(
+/// #         &self,
+/// #         _: &PackageRef,
+/// #         _: &Version,
+/// #         _: PublishingSource,
+/// #         _: bool,
+/// #     ) -> Result<(), Error> {
+/// #         Err(Error::RegistryError(anyhow::anyhow!("the empty backend is read-only")))
+/// #     }
+/// # }
+/// fn empty_backend(
+///     _registry: &Registry,
+///     _registry_config: &RegistryConfig,
+///     _registry_meta: &RegistryMetadata,
+/// ) -> Result<Box<dyn LoaderPublisher>, Error> {
+///     Ok(Box::new(EmptyBackend))
+/// }
+///
+/// let client = Client::builder(Config::empty())
+///     .with_backend("empty", empty_backend)
+///     .build();
+/// ```
+pub struct ClientBuilder {
+    config: Config,
+    backends: BackendFactories,
+}
+
+impl ClientBuilder {
+    /// Returns a new builder with the given [`Config`] and the built-in `oci`, `oci-layout`,
+    /// `local`, `index` and `git` backends.
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            backends: Default::default(),
+        }
+        .with_backend(OCI_PROTOCOL, oci_backend)
+        .with_backend(OCI_LAYOUT_PROTOCOL, oci_layout_backend)
+        .with_backend(LOCAL_PROTOCOL, local_backend)
+        .with_backend(INDEX_PROTOCOL, index_backend)
+        .with_backend(GIT_PROTOCOL, git_backend)
+    }
+
+    /// Registers the factory for the given backend type, replacing any factory that was registered
+    /// for it before, including the built-in ones.
+    pub fn with_backend(
+        mut self,
+        backend_type: impl Into<String>,
+        factory: impl BackendFactory + 'static,
+    ) -> Self {
+        self.backends.insert(backend_type.into(), Arc::new(factory));
+        self
+    }
+
+    /// Builds the client.
+    pub fn build(self) -> Client {
+        Client {
+            config: Arc::new(self.config),
+            backends: Arc::new(self.backends),
+            sources: Default::default(),
+        }
+    }
+}
+
+fn oci_backend(
+    registry: &Registry,
+    registry_config: &RegistryConfig,
+    registry_meta: &RegistryMetadata,
+) -> Result<Box<dyn LoaderPublisher>, Error> {
+    Ok(Box::new(OciBackend::new(
+        registry,
+        registry_config,
+        registry_meta,
+    )?))
+}
+
+fn oci_layout_backend(
+    _registry: &Registry,
+    registry_config: &RegistryConfig,
+    _registry_meta: &RegistryMetadata,
+) -> Result<Box<dyn LoaderPublisher>, Error> {
+    Ok(Box::new(OciLayoutBackend::new(registry_config)?))
+}
+
+fn local_backend(
+    _registry: &Registry,
+    registry_config: &RegistryConfig,
+    _registry_meta: &RegistryMetadata,
+) -> Result<Box<dyn LoaderPublisher>, Error> {
+    Ok(Box::new(LocalBackend::new(registry_config.clone())?))
+}
+
+fn index_backend(
+    _registry: &Registry,
+    registry_config: &RegistryConfig,
+    registry_meta: &RegistryMetadata,
+) -> Result<Box<dyn LoaderPublisher>, Error> {
+    Ok(Box::new(IndexBackend::new(registry_config, registry_meta)?))
+}
+
+fn git_backend(
+    _registry: &Registry,
+    registry_config: &RegistryConfig,
+    registry_meta: &RegistryMetadata,
+) -> Result<Box<dyn LoaderPublisher>, Error> {
+    Ok(Box::new(GitBackend::new(registry_config, registry_meta)?))
+}
@@ -1,21 +1,33 @@
 //! A `Cache` implementation for a filesystem
 
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
 
 use anyhow::Context;
 use etcetera::BaseStrategy;
 use futures_util::{StreamExt, TryStreamExt};
-use tokio_util::io::{ReaderStream, StreamReader};
+use tokio::io::AsyncWriteExt;
+use tokio_util::io::ReaderStream;
 use wasm_pkg_common::{
     Error,
     digest::ContentDigest,
     package::{PackageRef, Version},
 };
 
-use crate::{ContentStream, Release};
+use crate::{ContentStream, Release, VersionInfo, local::sha256_from_file};
 
-use super::Cache;
+use super::{Cache, CachedVersions, FetchedRelease};
 
+/// The file that maps the release info files of the cache to the digest of the data they refer to.
+const INDEX_FILE: &str = "index.json";
+/// The file that is locked while the index is updated.
+const INDEX_LOCK_FILE: &str = ".index.lock";
+
+/// A [`Cache`] that stores data in a directory. Data is written to a temporary file and only moved
+/// into place once its digest was verified. Data is validated again while it is read, and data
+/// whose digest doesn't match (e.g. because it was modified on disk) makes the returned stream fail
+/// and is evicted, so corrupted data is never read successfully.
 #[derive(Clone)]
 pub struct FileCache {
     root: PathBuf,
@@ -38,6 +50,20 @@ impl FileCache {
         })
     }
 
+    /// Opens an existing cache directory without creating it, e.g. a cache that is mounted
+    /// read-only.
+    pub async fn open(root: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let metadata = tokio::fs::metadata(&root)
+            .await
+            .context("Unable to open cache directory")?;
+        if !metadata.is_dir() {
+            anyhow::bail!("{} is not a directory", root.as_ref().display());
+        }
+        Ok(Self {
+            root: root.as_ref().to_path_buf(),
+        })
+    }
+
     /// Returns a cache setup to use the global default cache path if it can be determined,
     /// otherwise this will error
     pub async fn global_cache() -> anyhow::Result<Self> {
@@ -50,19 +76,325 @@ impl FileCache {
             .ok()
             .map(|strat| strat.cache_dir().join("wasm-pkg"))
     }
+
+    /// Creates a temporary file in the cache directory that can be renamed into place once it was
+    /// completely written. The file is removed if it is dropped before that.
+    fn temp_file(&self) -> Result<(tokio::fs::File, tempfile::TempPath), Error> {
+        let (file, path) = tempfile::Builder::new()
+            .prefix(".tmp-")
+            .tempfile_in(&self.root)
+            .map_err(|e| Error::CacheError(anyhow::anyhow!("Unable to create file for cache {e}")))?
+            .into_parts();
+        Ok((tokio::fs::File::from_std(file), path))
+    }
+
+    /// Moves a completely written temporary file into place.
+    fn persist(temp_path: tempfile::TempPath, path: &Path) -> Result<(), Error> {
+        temp_path
+            .persist(path)
+            .map_err(|e| Error::CacheError(anyhow::anyhow!("Error writing to disk: {e}")))
+    }
+
+    /// Serializes the value to JSON and atomically writes it to the given path.
+    async fn write_json(&self, path: &Path, value: &impl serde::Serialize) -> Result<(), Error> {
+        let data = serde_json::to_vec(value).map_err(|e| {
+            Error::CacheError(anyhow::anyhow!("Error serializing data to disk: {e}"))
+        })?;
+        let (mut file, temp_path) = self.temp_file()?;
+        file.write_all(&data)
+            .await
+            .map_err(|e| Error::CacheError(anyhow::anyhow!("Error writing to disk: {e}")))?;
+        file.sync_all()
+            .await
+            .map_err(|e| Error::CacheError(anyhow::anyhow!("Error writing to disk: {e}")))?;
+        Self::persist(temp_path, path)
+    }
+
+    /// Reads and deserializes the JSON at the given path. Returns `None` if the file doesn't exist.
+    async fn read_json<T: serde::de::DeserializeOwned>(
+        &self,
+        path: &Path,
+    ) -> Result<Option<T>, Error> {
+        let data = match tokio::fs::read(path).await {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                return Err(Error::CacheError(anyhow::anyhow!(
+                    "Error reading from disk: {e}"
+                )));
+            }
+        };
+        serde_json::from_slice(&data).map(Some).map_err(|e| {
+            Error::CacheError(anyhow::anyhow!("Error deserializing data from disk: {e}"))
+        })
+    }
+}
+
+/// A release whose info is stored in a [`FileCache`].
+#[derive(Clone, Debug)]
+pub struct CachedRelease {
+    pub package: PackageRef,
+    pub version: Version,
+}
+
+/// Data stored in a [`FileCache`] along with the releases that refer to it.
+#[derive(Clone, Debug)]
+pub struct CachedData {
+    pub digest: ContentDigest,
+    /// The size of the data in bytes.
+    pub size: u64,
+    /// When the data was last read from or written to the cache.
+    pub accessed: SystemTime,
+    pub releases: Vec<CachedRelease>,
+}
+
+impl FileCache {
+    /// Returns the directory the cache stores data in.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Returns an index of all the data in the cache and the releases that refer to it, sorted by
+    /// digest. Release info that refers to data that isn't in the cache is not included.
+    ///
+    /// Release info files that are missing from the persisted index (e.g. written by older
+    /// versions) are read and added to it.
+    pub async fn index(&self) -> anyhow::Result<Vec<CachedData>> {
+        // A read-only cache can't be locked, in which case the index is only read
+        let lock = self.lock_index().await;
+        let mut persisted = self.read_index().await;
+        let mut indexed = ReleaseIndex::default();
+        let mut changed = false;
+        let mut releases: HashMap<String, Vec<CachedRelease>> = HashMap::new();
+        let mut data = Vec::new();
+        let mut entries = tokio::fs::read_dir(&self.root)
+            .await
+            .with_context(|| format!("Unable to read cache directory {}", self.root.display()))?;
+        while let Some(entry) = entries.next_entry().await? {
+            let file_name = entry.file_name();
+            let Some(file_name) = file_name.to_str() else {
+                continue;
+            };
+            if let Ok(digest) = file_name.parse::<ContentDigest>() {
+                let metadata = entry.metadata().await?;
+                data.push(CachedData {
+                    digest,
+                    size: metadata.len(),
+                    accessed: metadata.accessed().or_else(|_| metadata.modified())?,
+                    releases: Vec::new(),
+                });
+            } else if let Some((package, version)) = parse_release_file_name(file_name) {
+                let digest = match persisted.releases.remove(file_name) {
+                    Some(digest) => digest,
+                    None => {
+                        let path = entry.path();
+                        let info: ReleaseInfoOwned = match tokio::fs::read(&path)
+                            .await
+                            .map_err(anyhow::Error::from)
+                            .and_then(|bytes| Ok(serde_json::from_slice(&bytes)?))
+                        {
+                            Ok(info) => info,
+                            Err(e) => {
+                                tracing::warn!(path = %path.display(), "Skipping invalid release info: {e}");
+                                continue;
+                            }
+                        };
+                        changed = true;
+                        info.content_digest
+                    }
+                };
+                releases
+                    .entry(digest.to_string())
+                    .or_default()
+                    .push(CachedRelease { package, version });
+                indexed.releases.insert(file_name.to_string(), digest);
+            }
+        }
+        // Entries left over refer to release info files that were removed
+        if lock.is_ok() && (changed || !persisted.releases.is_empty()) {
+            self.write_json(&self.root.join(INDEX_FILE), &indexed)
+                .await?;
+        }
+        drop(lock);
+        for entry in data.iter_mut() {
+            if let Some(refs) = releases.remove(&entry.digest.to_string()) {
+                entry.releases = refs;
+                entry
+                    .releases
+                    .sort_by(|a, b| (&a.package, &a.version).cmp(&(&b.package, &b.version)));
+            }
+        }
+        data.sort_by_key(|entry| entry.digest.to_string());
+        Ok(data)
+    }
+
+    /// Checks the digest of all the data in the cache, removing any data that doesn't match its
+    /// digest. Returns the removed data.
+    pub async fn verify(&self) -> anyhow::Result<Vec<CachedData>> {
+        let mut corrupted = Vec::new();
+        for entry in self.index().await? {
+            let path = self.root.join(entry.digest.to_string());
+            let actual = sha256_from_file(&path)
+                .await
+                .with_context(|| format!("Unable to read {}", path.display()))?;
+            if actual != entry.digest {
+                self.remove(&entry).await?;
+                corrupted.push(entry);
+            }
+        }
+        Ok(corrupted)
+    }
+
+    /// Removes the data and the info of all releases that refer to it from the cache.
+    pub async fn remove(&self, data: &CachedData) -> anyhow::Result<()> {
+        for release in data.releases.iter() {
+            remove_if_exists(&self.release_path(&release.package, &release.version)).await?;
+        }
+        self.update_index(|index| {
+            for release in data.releases.iter() {
+                index
+                    .releases
+                    .remove(&release_file_name(&release.package, &release.version));
+            }
+        })
+        .await?;
+        remove_if_exists(&self.root.join(data.digest.to_string())).await
+    }
+
+    /// Removes the least recently used data until the cache is at most `max_size` bytes and removes
+    /// all data that wasn't used for longer than `older_than`. Returns the removed data.
+    pub async fn prune(
+        &self,
+        max_size: Option<u64>,
+        older_than: Option<Duration>,
+    ) -> anyhow::Result<Vec<CachedData>> {
+        let mut data = self.index().await?;
+        // Most recently used first, so the least recently used data can be popped off the end
+        data.sort_by_key(|entry| std::cmp::Reverse(entry.accessed));
+        let cutoff = older_than.and_then(|older_than| SystemTime::now().checked_sub(older_than));
+        let mut size: u64 = data.iter().map(|entry| entry.size).sum();
+        let mut removed = Vec::new();
+        while let Some(entry) = data.last() {
+            let expired = cutoff.is_some_and(|cutoff| entry.accessed < cutoff);
+            if !expired && max_size.is_none_or(|max_size| size <= max_size) {
+                break;
+            }
+            let entry = data.pop().unwrap();
+            self.remove(&entry).await?;
+            size -= entry.size;
+            removed.push(entry);
+        }
+        Ok(removed)
+    }
+
+    /// Removes everything from the cache.
+    pub async fn clean(&self) -> anyhow::Result<()> {
+        let mut entries = tokio::fs::read_dir(&self.root)
+            .await
+            .with_context(|| format!("Unable to read cache directory {}", self.root.display()))?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if entry.file_type().await?.is_dir() {
+                tokio::fs::remove_dir_all(&path).await
+            } else {
+                tokio::fs::remove_file(&path).await
+            }
+            .with_context(|| format!("Unable to remove {}", path.display()))?;
+        }
+        Ok(())
+    }
+
+    fn release_path(&self, package: &PackageRef, version: &Version) -> PathBuf {
+        self.root.join(release_file_name(package, version))
+    }
+
+    /// Acquires an exclusive lock on the index, which is released when the returned file is
+    /// dropped.
+    async fn lock_index(&self) -> Result<std::fs::File, Error> {
+        let path = self.root.join(INDEX_LOCK_FILE);
+        tokio::task::spawn_blocking(move || {
+            let file = std::fs::OpenOptions::new()
+                .create(true)
+                .truncate(false)
+                .write(true)
+                .open(&path)?;
+            file.lock()?;
+            Ok(file)
+        })
+        .await
+        .map_err(|e| Error::CacheError(e.into()))?
+        .map_err(|e: std::io::Error| {
+            Error::CacheError(anyhow::anyhow!("Unable to lock the cache index: {e}"))
+        })
+    }
+
+    /// Reads the persisted index. An index that can't be read is treated as empty, as it is
+    /// rebuilt from the release info files by [`FileCache::index`].
+    async fn read_index(&self) -> ReleaseIndex {
+        match self.read_json(&self.root.join(INDEX_FILE)).await {
+            Ok(index) => index.unwrap_or_default(),
+            Err(e) => {
+                tracing::warn!("Ignoring invalid cache index: {e}");
+                ReleaseIndex::default()
+            }
+        }
+    }
+
+    /// Updates the persisted index while holding its lock, as other processes may share the cache.
+    async fn update_index(&self, update: impl FnOnce(&mut ReleaseIndex)) -> Result<(), Error> {
+        let _lock = self.lock_index().await?;
+        let mut index = self.read_index().await;
+        update(&mut index);
+        self.write_json(&self.root.join(INDEX_FILE), &index).await
+    }
+
+    fn versions_path(&self, package: &PackageRef) -> PathBuf {
+        self.root.join(format!("{package}.versions.json"))
+    }
+}
+
+fn release_file_name(package: &PackageRef, version: &Version) -> String {
+    format!("{package}-{version}.json")
+}
+
+/// Parses the package and version from the name of a release info file. The words of a package
+/// name can't start with a digit, so the version starts after the first `-` that is followed by one.
+fn parse_release_file_name(file_name: &str) -> Option<(PackageRef, Version)> {
+    let stem = file_name.strip_suffix(".json")?;
+    let (idx, _) = stem
+        .match_indices('-')
+        .find(|(idx, _)| stem[idx + 1..].starts_with(|c: char| c.is_ascii_digit()))?;
+    Some((stem[..idx].parse().ok()?, stem[idx + 1..].parse().ok()?))
+}
+
+async fn remove_if_exists(path: &Path) -> anyhow::Result<()> {
+    match tokio::fs::remove_file(path).await {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(e).with_context(|| format!("Unable to remove {}", path.display()))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Maps the names of release info files to the digest of the data they refer to.
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct ReleaseIndex {
+    releases: BTreeMap<String, ContentDigest>,
 }
 
 #[derive(serde::Serialize)]
 struct ReleaseInfoBorrowed<'a> {
     version: &'a Version,
     content_digest: &'a ContentDigest,
+    fetched_at: SystemTime,
 }
 
-impl<'a> From<&'a Release> for ReleaseInfoBorrowed<'a> {
-    fn from(release: &'a Release) -> Self {
+impl<'a> From<&'a FetchedRelease> for ReleaseInfoBorrowed<'a> {
+    fn from(fetched: &'a FetchedRelease) -> Self {
         Self {
-            version: &release.version,
-            content_digest: &release.content_digest,
+            version: &fetched.release.version,
+            content_digest: &fetched.release.content_digest,
+            fetched_at: fetched.fetched_at,
         }
     }
 }
@@ -71,60 +403,96 @@ impl<'a> From<&'a Release> for ReleaseInfoBorrowed<'a> {
 struct ReleaseInfoOwned {
     version: Version,
     content_digest: ContentDigest,
+    /// Missing for releases cached by older versions, which are treated as fetched long ago
+    #[serde(default = "unix_epoch")]
+    fetched_at: SystemTime,
+}
+
+fn unix_epoch() -> SystemTime {
+    SystemTime::UNIX_EPOCH
 }
 
-impl From<ReleaseInfoOwned> for Release {
+impl From<ReleaseInfoOwned> for FetchedRelease {
     fn from(info: ReleaseInfoOwned) -> Self {
         Self {
-            version: info.version,
-            content_digest: info.content_digest,
+            release: Release {
+                version: info.version,
+                content_digest: info.content_digest,
+            },
+            fetched_at: info.fetched_at,
         }
     }
 }
 
+#[derive(serde::Serialize, serde::Deserialize)]
+struct VersionsInfo {
+    listed_at: SystemTime,
+    versions: Vec<VersionInfoEntry>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct VersionInfoEntry {
+    version: Version,
+    yanked: bool,
+}
+
 impl Cache for FileCache {
     async fn put_data(&self, digest: ContentDigest, data: ContentStream) -> Result<(), Error> {
         let path = self.root.join(digest.to_string());
-        let mut file = tokio::fs::File::create(&path).await.map_err(|e| {
-            Error::CacheError(anyhow::anyhow!("Unable to create file for cache {e}"))
-        })?;
-        let mut buf = StreamReader::new(data.map_err(std::io::Error::other));
-        tokio::io::copy(&mut buf, &mut file)
+        let (mut file, temp_path) = self.temp_file()?;
+        let mut data = digest.validating_stream(data).boxed();
+        while let Some(bytes) = data.try_next().await? {
+            file.write_all(&bytes)
+                .await
+                .map_err(|e| Error::CacheError(e.into()))?;
+        }
+        file.sync_all()
             .await
-            .map_err(|e| Error::CacheError(e.into()))
-            .map(|_| ())
+            .map_err(|e| Error::CacheError(e.into()))?;
+        Self::persist(temp_path, &path)
     }
 
     async fn get_data(&self, digest: &ContentDigest) -> Result<Option<ContentStream>, Error> {
         let path = self.root.join(digest.to_string());
-        let exists = tokio::fs::try_exists(&path)
-            .await
-            .map_err(|e| Error::CacheError(e.into()))?;
-        if !exists {
-            return Ok(None);
+        let file = match tokio::fs::File::open(&path).await {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(Error::CacheError(e.into())),
+        };
+        // Track when the data was last used for pruning, as access times aren't always updated by
+        // the filesystem
+        let file = file.into_std().await;
+        let times = std::fs::FileTimes::new().set_accessed(SystemTime::now());
+        if let Err(e) = file.set_times(times) {
+            tracing::debug!("Unable to update the access time of cached data: {e}");
         }
-        let file = tokio::fs::File::open(path)
-            .await
-            .map_err(|e| Error::CacheError(e.into()))?;
+        let file = tokio::fs::File::from_std(file);
 
-        Ok(Some(
-            ReaderStream::new(file).map_err(Error::IoError).boxed(),
-        ))
+        // The data is validated while it is read, so it is only read once
+        let stream = digest
+            .validating_stream(ReaderStream::new(file).map_err(Error::IoError))
+            .or_else(move |err| {
+                let path = path.clone();
+                async move {
+                    if let Error::InvalidContent(_) = &err {
+                        tracing::warn!(
+                            path = %path.display(),
+                            "Evicting cached data that doesn't match its digest: {err}"
+                        );
+                        // The cache may be read-only, in which case the data is only ignored
+                        if let Err(e) = tokio::fs::remove_file(&path).await {
+                            tracing::warn!(path = %path.display(), "Unable to evict cached data: {e}");
+                        }
+                    }
+                    Err(err)
+                }
+            });
+        Ok(Some(stream.boxed()))
     }
 
     async fn put_release(&self, package: &PackageRef, release: &Release) -> Result<(), Error> {
-        let path = self
-            .root
-            .join(format!("{}-{}.json", package, release.version));
-        tokio::fs::write(
-            path,
-            serde_json::to_string(&ReleaseInfoBorrowed::from(release)).map_err(|e| {
-                Error::CacheError(anyhow::anyhow!("Error serializing data to disk: {e}"))
-            })?,
-        )
-        .await
-        .map(|_| ())
-        .map_err(|e| Error::CacheError(anyhow::anyhow!("Error writing to disk: {e}")))
+        self.put_release_fetched(package, &FetchedRelease::new(release.clone()))
+            .await
     }
 
     async fn get_release(
@@ -132,19 +500,279 @@ impl Cache for FileCache {
         package: &PackageRef,
         version: &Version,
     ) -> Result<Option<Release>, Error> {
-        let path = self.root.join(format!("{}-{}.json", package, version));
-        let exists = tokio::fs::try_exists(&path).await.map_err(|e| {
-            Error::CacheError(anyhow::anyhow!("Error checking if file exists: {e}"))
-        })?;
-        if !exists {
-            return Ok(None);
+        Ok(self
+            .get_release_fetched(package, version)
+            .await?
+            .map(|fetched| fetched.release))
+    }
+
+    async fn put_release_fetched(
+        &self,
+        package: &PackageRef,
+        release: &FetchedRelease,
+    ) -> Result<(), Error> {
+        let version = &release.release.version;
+        self.write_json(
+            &self.release_path(package, version),
+            &ReleaseInfoBorrowed::from(release),
+        )
+        .await?;
+        self.update_index(|index| {
+            index.releases.insert(
+                release_file_name(package, version),
+                release.release.content_digest.clone(),
+            );
+        })
+        .await
+    }
+
+    async fn get_release_fetched(
+        &self,
+        package: &PackageRef,
+        version: &Version,
+    ) -> Result<Option<FetchedRelease>, Error> {
+        let release: Option<ReleaseInfoOwned> =
+            self.read_json(&self.release_path(package, version)).await?;
+        Ok(release.map(Into::into))
+    }
+
+    async fn put_versions(
+        &self,
+        package: &PackageRef,
+        versions: &CachedVersions,
+    ) -> Result<(), Error> {
+        let info = VersionsInfo {
+            listed_at: versions.listed_at,
+            versions: versions
+                .versions
+                .iter()
+                .map(|info| VersionInfoEntry {
+                    version: info.version.clone(),
+                    yanked: info.yanked,
+                })
+                .collect(),
+        };
+        self.write_json(&self.versions_path(package), &info).await
+    }
+
+    async fn get_versions(&self, package: &PackageRef) -> Result<Option<CachedVersions>, Error> {
+        let info: Option<VersionsInfo> = self.read_json(&self.versions_path(package)).await?;
+        Ok(info.map(|info| CachedVersions {
+            listed_at: info.listed_at,
+            versions: info
+                .versions
+                .into_iter()
+                .map(|entry| VersionInfo {
+                    version: entry.version,
+                    yanked: entry.yanked,
+                })
+                .collect(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use futures_util::{StreamExt, TryStreamExt, stream};
+    use sha2::{Digest, Sha256};
+    use wasm_pkg_common::{Error, digest::ContentDigest};
+
+    use super::{Cache, FileCache, parse_release_file_name};
+    use crate::{ContentStream, Release};
+
+    fn content_stream(chunks: &[&'static [u8]]) -> ContentStream {
+        let chunks: Vec<_> = chunks
+            .iter()
+            .map(|chunk| Ok(Bytes::from_static(chunk)))
+            .collect();
+        stream::iter(chunks).boxed()
+    }
+
+    fn digest(data: &[u8]) -> ContentDigest {
+        Sha256::new_with_prefix(data).into()
+    }
+
+    async fn read_data(cache: &FileCache, digest: &ContentDigest) -> Option<Vec<u8>> {
+        let stream = cache.get_data(digest).await.unwrap()?;
+        let chunks: Vec<Bytes> = stream.try_collect().await.unwrap();
+        Some(chunks.concat())
+    }
+
+    /// Returns the names of the files in the cache directory.
+    async fn cache_files(cache: &FileCache) -> Vec<String> {
+        let mut entries = tokio::fs::read_dir(&cache.root).await.unwrap();
+        let mut names = Vec::new();
+        while let Some(entry) = entries.next_entry().await.unwrap() {
+            names.push(entry.file_name().to_string_lossy().into_owned());
         }
-        let data = tokio::fs::read(path)
+        names
+    }
+
+    #[tokio::test]
+    async fn put_data_stores_verified_data() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = FileCache::new(dir.path()).await.unwrap();
+        let digest = digest(b"hello world");
+
+        cache
+            .put_data(digest.clone(), content_stream(&[b"hello ", b"world"]))
             .await
-            .map_err(|e| Error::CacheError(anyhow::anyhow!("Error reading from disk: {e}")))?;
-        let release: ReleaseInfoOwned = serde_json::from_slice(&data).map_err(|e| {
-            Error::CacheError(anyhow::anyhow!("Error deserializing data from disk: {e}"))
-        })?;
-        Ok(Some(release.into()))
+            .unwrap();
+        assert_eq!(
+            read_data(&cache, &digest).await.as_deref(),
+            Some(&b"hello world"[..])
+        );
+        assert_eq!(cache_files(&cache).await, vec![digest.to_string()]);
+    }
+
+    #[tokio::test]
+    async fn put_data_discards_invalid_data() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = FileCache::new(dir.path()).await.unwrap();
+        let digest = digest(b"hello world");
+
+        let err = cache
+            .put_data(digest.clone(), content_stream(&[b"hello ", b"there"]))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidContent(_)), "{err:?}");
+
+        // An interrupted download
+        let interrupted = stream::iter([
+            Ok(Bytes::from_static(b"hello ")),
+            Err(Error::IoError(std::io::ErrorKind::ConnectionReset.into())),
+        ])
+        .boxed();
+        cache
+            .put_data(digest.clone(), interrupted)
+            .await
+            .unwrap_err();
+
+        assert!(read_data(&cache, &digest).await.is_none());
+        assert!(
+            cache_files(&cache).await.is_empty(),
+            "no partial files should be left in the cache"
+        );
+    }
+
+    #[tokio::test]
+    async fn get_data_evicts_corrupted_data() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = FileCache::new(dir.path()).await.unwrap();
+        let digest = digest(b"hello world");
+
+        cache
+            .put_data(digest.clone(), content_stream(&[b"hello world"]))
+            .await
+            .unwrap();
+        let path = dir.path().join(digest.to_string());
+        tokio::fs::write(&path, b"hello").await.unwrap();
+
+        let stream = cache.get_data(&digest).await.unwrap().unwrap();
+        let res: Result<Vec<Bytes>, _> = stream.try_collect().await;
+        assert!(
+            matches!(res, Err(Error::InvalidContent(_))),
+            "reading corrupted data should fail"
+        );
+        assert!(!path.exists(), "corrupted data should be removed");
+        assert!(read_data(&cache, &digest).await.is_none());
+    }
+
+    #[test]
+    fn release_file_names_are_parsed() {
+        let (package, version) = parse_release_file_name("my-ns:my-pkg-1.0.0-rc.1.json").unwrap();
+        assert_eq!(package.to_string(), "my-ns:my-pkg");
+        assert_eq!(version.to_string(), "1.0.0-rc.1");
+        assert!(parse_release_file_name("sha256:abc").is_none());
+    }
+
+    #[tokio::test]
+    async fn index_is_persisted_and_repaired() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = FileCache::new(dir.path()).await.unwrap();
+        let package = "example:foo".parse().unwrap();
+        let content_digest = digest(b"data");
+        cache
+            .put_data(content_digest.clone(), content_stream(&[b"data"]))
+            .await
+            .unwrap();
+        let release = Release {
+            version: "0.1.0".parse().unwrap(),
+            content_digest: content_digest.clone(),
+        };
+        cache.put_release(&package, &release).await.unwrap();
+
+        // The index is used instead of reading the release info files
+        let release_path = dir.path().join("example:foo-0.1.0.json");
+        tokio::fs::write(&release_path, b"not json").await.unwrap();
+        let index = cache.index().await.unwrap();
+        assert_eq!(index.len(), 1);
+        assert_eq!(index[0].releases[0].version.to_string(), "0.1.0");
+
+        // Release info files missing from the index are added to it
+        cache.put_release(&package, &release).await.unwrap();
+        tokio::fs::remove_file(dir.path().join(super::INDEX_FILE))
+            .await
+            .unwrap();
+        let index = cache.index().await.unwrap();
+        assert_eq!(index[0].releases.len(), 1);
+        let persisted = cache.read_index().await;
+        assert_eq!(
+            persisted.releases.get("example:foo-0.1.0.json"),
+            Some(&content_digest)
+        );
+
+        // And entries of removed release info files are dropped
+        tokio::fs::remove_file(&release_path).await.unwrap();
+        assert!(cache.index().await.unwrap()[0].releases.is_empty());
+        assert!(cache.read_index().await.releases.is_empty());
+    }
+
+    #[tokio::test]
+    async fn prune_removes_least_recently_used_data() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = FileCache::new(dir.path()).await.unwrap();
+        let package = "example:foo".parse().unwrap();
+        for (version, content) in [("0.1.0", b"old"), ("0.2.0", b"new")] {
+            let content_digest = digest(content);
+            cache
+                .put_data(content_digest.clone(), content_stream(&[content]))
+                .await
+                .unwrap();
+            let release = Release {
+                version: version.parse().unwrap(),
+                content_digest,
+            };
+            cache.put_release(&package, &release).await.unwrap();
+            // Make sure the access times differ
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        let index = cache.index().await.unwrap();
+        assert_eq!(index.len(), 2);
+        assert!(
+            index
+                .iter()
+                .all(|data| data.releases.len() == 1 && data.size == 3)
+        );
+
+        let removed = cache.prune(Some(3), None).await.unwrap();
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].releases[0].version.to_string(), "0.1.0");
+        assert!(
+            cache
+                .get_release(&package, &"0.1.0".parse().unwrap())
+                .await
+                .unwrap()
+                .is_none(),
+            "the release info of removed data should be removed too"
+        );
+        assert_eq!(
+            read_data(&cache, &digest(b"new")).await.as_deref(),
+            Some(&b"new"[..])
+        );
+
+        cache.clean().await.unwrap();
+        assert!(cache_files(&cache).await.is_empty());
     }
 }
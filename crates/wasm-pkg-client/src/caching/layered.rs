@@ -0,0 +1,170 @@
+//! A `Cache` implementation that layers multiple caches
+
+use wasm_pkg_common::{
+    Error,
+    digest::ContentDigest,
+    package::{PackageRef, Version},
+};
+
+use crate::{ContentStream, Release};
+
+use super::{Cache, CachedVersions, FetchedRelease};
+
+/// A [`Cache`] that reads from multiple layers in order, but only writes to the first (top) layer.
+/// The other layers are only read from, e.g. a pre-populated cache that is shared between jobs and
+/// mounted read-only.
+#[derive(Clone)]
+pub struct LayeredCache<T> {
+    top: T,
+    readonly: Vec<T>,
+}
+
+impl<T> LayeredCache<T> {
+    /// Creates a new layered cache that writes to `top` and falls back to the `readonly` layers in
+    /// the given order when reading.
+    pub fn new(top: T, readonly: impl IntoIterator<Item = T>) -> Self {
+        Self {
+            top,
+            readonly: readonly.into_iter().collect(),
+        }
+    }
+
+    /// Returns the layer that is written to.
+    pub fn top(&self) -> &T {
+        &self.top
+    }
+
+    /// Returns all layers in the order they are read from.
+    pub fn layers(&self) -> impl Iterator<Item = &T> {
+        std::iter::once(&self.top).chain(&self.readonly)
+    }
+}
+
+impl<T: Cache + Sync> Cache for LayeredCache<T> {
+    async fn put_data(&self, digest: ContentDigest, data: ContentStream) -> Result<(), Error> {
+        self.top.put_data(digest, data).await
+    }
+
+    async fn get_data(&self, digest: &ContentDigest) -> Result<Option<ContentStream>, Error> {
+        for layer in self.layers() {
+            if let Some(data) = layer.get_data(digest).await? {
+                return Ok(Some(data));
+            }
+        }
+        Ok(None)
+    }
+
+    async fn put_release(&self, package: &PackageRef, release: &Release) -> Result<(), Error> {
+        self.top.put_release(package, release).await
+    }
+
+    async fn get_release(
+        &self,
+        package: &PackageRef,
+        version: &Version,
+    ) -> Result<Option<Release>, Error> {
+        Ok(self
+            .get_release_fetched(package, version)
+            .await?
+            .map(|fetched| fetched.release))
+    }
+
+    async fn put_release_fetched(
+        &self,
+        package: &PackageRef,
+        release: &FetchedRelease,
+    ) -> Result<(), Error> {
+        self.top.put_release_fetched(package, release).await
+    }
+
+    async fn get_release_fetched(
+        &self,
+        package: &PackageRef,
+        version: &Version,
+    ) -> Result<Option<FetchedRelease>, Error> {
+        for layer in self.layers() {
+            if let Some(release) = layer.get_release_fetched(package, version).await? {
+                return Ok(Some(release));
+            }
+        }
+        Ok(None)
+    }
+
+    async fn put_versions(
+        &self,
+        package: &PackageRef,
+        versions: &CachedVersions,
+    ) -> Result<(), Error> {
+        self.top.put_versions(package, versions).await
+    }
+
+    /// Returns the most recently listed versions of all layers.
+    async fn get_versions(&self, package: &PackageRef) -> Result<Option<CachedVersions>, Error> {
+        let mut latest: Option<CachedVersions> = None;
+        for layer in self.layers() {
+            if let Some(versions) = layer.get_versions(package).await?
+                && latest
+                    .as_ref()
+                    .is_none_or(|latest| versions.listed_at > latest.listed_at)
+            {
+                latest = Some(versions);
+            }
+        }
+        Ok(latest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use bytes::Bytes;
+    use futures_util::{StreamExt, TryStreamExt, stream};
+    use sha2::{Digest, Sha256};
+    use wasm_pkg_common::digest::ContentDigest;
+
+    use super::{Cache, CachedVersions, LayeredCache};
+    use crate::caching::FileCache;
+
+    const DATA: &[u8] = b"layered";
+
+    fn digest() -> ContentDigest {
+        Sha256::new_with_prefix(DATA).into()
+    }
+
+    async fn put(cache: &FileCache) {
+        let stream = stream::iter([Ok(Bytes::from_static(DATA))]).boxed();
+        cache.put_data(digest(), stream).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn reads_fall_through_and_writes_go_to_top() {
+        let top_dir = tempfile::tempdir().unwrap();
+        let lower_dir = tempfile::tempdir().unwrap();
+        let top = FileCache::new(top_dir.path()).await.unwrap();
+        let lower = FileCache::new(lower_dir.path()).await.unwrap();
+        put(&lower).await;
+        let cache = LayeredCache::new(top.clone(), [lower.clone()]);
+
+        let stream = cache.get_data(&digest()).await.unwrap().unwrap();
+        let chunks: Vec<Bytes> = stream.try_collect().await.unwrap();
+        assert_eq!(chunks.concat(), DATA);
+        assert!(top.get_data(&digest()).await.unwrap().is_none());
+
+        let package = "test:pkg".parse().unwrap();
+        let old = CachedVersions {
+            versions: Vec::new(),
+            listed_at: SystemTime::now() - Duration::from_secs(60),
+        };
+        lower.put_versions(&package, &old).await.unwrap();
+        cache
+            .put_versions(&package, &CachedVersions::new(Vec::new()))
+            .await
+            .unwrap();
+        assert!(top.get_versions(&package).await.unwrap().is_some());
+        let latest = cache.get_versions(&package).await.unwrap().unwrap();
+        assert!(latest.listed_at > old.listed_at);
+        let lower_versions = lower.get_versions(&package).await.unwrap().unwrap();
+        assert!(lower_versions.listed_at < latest.listed_at);
+    }
+}
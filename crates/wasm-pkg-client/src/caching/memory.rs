@@ -0,0 +1,242 @@
+//! A `Cache` implementation that keeps everything in memory
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use bytes::Bytes;
+use futures_util::{StreamExt, TryStreamExt, stream};
+use wasm_pkg_common::{
+    Error,
+    digest::ContentDigest,
+    package::{PackageRef, Version},
+};
+
+use crate::{ContentStream, Release};
+
+use super::{Cache, CachedVersions, FetchedRelease};
+
+/// A [`Cache`] that keeps everything in memory, e.g. for tools embedding the client that shouldn't
+/// touch disk, or for tests. Clones share the same storage.
+///
+/// With a byte budget, the least recently used data is evicted once the total size of the cached
+/// data exceeds it. Data that is larger than the whole budget is not cached at all, so a
+/// [`CachingClient`](super::CachingClient) streams it from the registry instead. Releases and
+/// version listings are small and don't count against the budget.
+#[derive(Clone, Default)]
+pub struct InMemoryCache {
+    inner: Arc<Mutex<Inner>>,
+    max_bytes: Option<u64>,
+}
+
+#[derive(Default)]
+struct Inner {
+    /// Data keyed by the string form of its digest
+    data: HashMap<String, Entry>,
+    releases: HashMap<(PackageRef, Version), FetchedRelease>,
+    versions: HashMap<PackageRef, CachedVersions>,
+    size: u64,
+    /// Incremented on every access, used to find the least recently used data
+    clock: u64,
+}
+
+struct Entry {
+    bytes: Bytes,
+    last_used: u64,
+}
+
+impl InMemoryCache {
+    /// Creates an in-memory cache without a limit on its size.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an in-memory cache that holds at most `max_bytes` of data.
+    pub fn with_max_bytes(max_bytes: u64) -> Self {
+        Self {
+            inner: Default::default(),
+            max_bytes: Some(max_bytes),
+        }
+    }
+
+    /// Returns the total size of the cached data in bytes.
+    pub fn size(&self) -> u64 {
+        self.lock().size
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        // The cache is never left in an inconsistent state, so it's fine to ignore poisoning
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Inner {
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    /// Evicts the least recently used data until `additional` bytes fit into `max_bytes`.
+    fn make_room(&mut self, additional: u64, max_bytes: u64) {
+        while self.size + additional > max_bytes {
+            let Some(key) = self
+                .data
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone())
+            else {
+                return;
+            };
+            if let Some(entry) = self.data.remove(&key) {
+                self.size -= entry.bytes.len() as u64;
+            }
+        }
+    }
+}
+
+impl Cache for InMemoryCache {
+    async fn put_data(&self, digest: ContentDigest, data: ContentStream) -> Result<(), Error> {
+        let chunks: Vec<Bytes> = digest.validating_stream(data).try_collect().await?;
+        let bytes = Bytes::from(chunks.concat());
+        let len = bytes.len() as u64;
+        if self.max_bytes.is_some_and(|max_bytes| len > max_bytes) {
+            tracing::debug!(%digest, len, "Not caching data that is larger than the cache budget");
+            return Ok(());
+        }
+
+        let mut inner = self.lock();
+        if let Some(old) = inner.data.remove(&digest.to_string()) {
+            inner.size -= old.bytes.len() as u64;
+        }
+        if let Some(max_bytes) = self.max_bytes {
+            inner.make_room(len, max_bytes);
+        }
+        let last_used = inner.tick();
+        inner
+            .data
+            .insert(digest.to_string(), Entry { bytes, last_used });
+        inner.size += len;
+        Ok(())
+    }
+
+    async fn get_data(&self, digest: &ContentDigest) -> Result<Option<ContentStream>, Error> {
+        let mut inner = self.lock();
+        let last_used = inner.tick();
+        Ok(inner.data.get_mut(&digest.to_string()).map(|entry| {
+            entry.last_used = last_used;
+            stream::once(std::future::ready(Ok(entry.bytes.clone()))).boxed()
+        }))
+    }
+
+    async fn put_release(&self, package: &PackageRef, release: &Release) -> Result<(), Error> {
+        self.put_release_fetched(package, &FetchedRelease::new(release.clone()))
+            .await
+    }
+
+    async fn get_release(
+        &self,
+        package: &PackageRef,
+        version: &Version,
+    ) -> Result<Option<Release>, Error> {
+        Ok(self
+            .get_release_fetched(package, version)
+            .await?
+            .map(|fetched| fetched.release))
+    }
+
+    async fn put_release_fetched(
+        &self,
+        package: &PackageRef,
+        release: &FetchedRelease,
+    ) -> Result<(), Error> {
+        self.lock().releases.insert(
+            (package.clone(), release.release.version.clone()),
+            release.clone(),
+        );
+        Ok(())
+    }
+
+    async fn get_release_fetched(
+        &self,
+        package: &PackageRef,
+        version: &Version,
+    ) -> Result<Option<FetchedRelease>, Error> {
+        Ok(self
+            .lock()
+            .releases
+            .get(&(package.clone(), version.clone()))
+            .cloned())
+    }
+
+    async fn put_versions(
+        &self,
+        package: &PackageRef,
+        versions: &CachedVersions,
+    ) -> Result<(), Error> {
+        self.lock()
+            .versions
+            .insert(package.clone(), versions.clone());
+        Ok(())
+    }
+
+    async fn get_versions(&self, package: &PackageRef) -> Result<Option<CachedVersions>, Error> {
+        Ok(self.lock().versions.get(package).cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use futures_util::{StreamExt, TryStreamExt, stream};
+    use sha2::{Digest, Sha256};
+    use wasm_pkg_common::digest::ContentDigest;
+
+    use super::{Cache, InMemoryCache};
+
+    fn digest(data: &[u8]) -> ContentDigest {
+        Sha256::new_with_prefix(data).into()
+    }
+
+    async fn put(cache: &InMemoryCache, data: &'static [u8]) -> Result<(), wasm_pkg_common::Error> {
+        let stream = stream::iter([Ok(Bytes::from_static(data))]).boxed();
+        cache.put_data(digest(data), stream).await
+    }
+
+    async fn contains(cache: &InMemoryCache, data: &[u8]) -> bool {
+        match cache.get_data(&digest(data)).await.unwrap() {
+            Some(stream) => {
+                let chunks: Vec<Bytes> = stream.try_collect().await.unwrap();
+                assert_eq!(chunks.concat(), data);
+                true
+            }
+            None => false,
+        }
+    }
+
+    #[tokio::test]
+    async fn put_data_discards_invalid_data() {
+        let cache = InMemoryCache::new();
+        let stream = stream::iter([Ok(Bytes::from_static(b"bad"))]).boxed();
+        assert!(cache.put_data(digest(b"good"), stream).await.is_err());
+        assert!(!contains(&cache, b"good").await);
+        assert_eq!(cache.size(), 0);
+    }
+
+    #[tokio::test]
+    async fn budget_evicts_least_recently_used_data() {
+        let cache = InMemoryCache::with_max_bytes(8);
+        put(&cache, b"aaaa").await.unwrap();
+        put(&cache, b"bbbb").await.unwrap();
+        // Reading `aaaa` makes `bbbb` the least recently used data
+        assert!(contains(&cache, b"aaaa").await);
+        put(&cache, b"cccc").await.unwrap();
+        assert!(contains(&cache, b"aaaa").await);
+        assert!(!contains(&cache, b"bbbb").await);
+        assert!(contains(&cache, b"cccc").await);
+        assert_eq!(cache.size(), 8);
+
+        // Data larger than the budget is skipped without evicting anything
+        put(&cache, b"too large").await.unwrap();
+        assert!(!contains(&cache, b"too large").await);
+        assert_eq!(cache.size(), 8);
+    }
+}
@@ -1,5 +1,6 @@
 use std::future::Future;
 use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 
 use wasm_pkg_common::{
     Error,
@@ -7,11 +8,15 @@ use wasm_pkg_common::{
     package::{PackageRef, Version},
 };
 
-use crate::{Client, ContentStream, Release, VersionInfo};
+use crate::{Client, Config, ContentStream, Release, VersionInfo};
 
 mod file;
+mod layered;
+mod memory;
 
-pub use file::FileCache;
+pub use file::{CachedData, CachedRelease, FileCache};
+pub use layered::LayeredCache;
+pub use memory::InMemoryCache;
 
 /// A trait for a cache of data.
 pub trait Cache {
@@ -41,14 +46,163 @@ pub trait Cache {
         package: &PackageRef,
         version: &Version,
     ) -> impl Future<Output = Result<Option<Release>, Error>> + Send;
+
+    /// Puts the release data into the cache, along with the time it was fetched at.
+    ///
+    /// The default implementation only puts the release, see [`Cache::get_release_fetched`].
+    fn put_release_fetched(
+        &self,
+        package: &PackageRef,
+        release: &FetchedRelease,
+    ) -> impl Future<Output = Result<(), Error>> + Send {
+        self.put_release(package, &release.release)
+    }
+
+    /// Gets the release data from the cache along with the time it was fetched at. Returns None if
+    /// the data is not in the cache.
+    ///
+    /// The default implementation doesn't know when the release was fetched, so it is treated as
+    /// fetched long ago and revalidated by any [`RevalidationPolicy`] other than `Never`.
+    fn get_release_fetched(
+        &self,
+        package: &PackageRef,
+        version: &Version,
+    ) -> impl Future<Output = Result<Option<FetchedRelease>, Error>> + Send {
+        let release = self.get_release(package, version);
+        async move {
+            Ok(release.await?.map(|release| FetchedRelease {
+                release,
+                fetched_at: SystemTime::UNIX_EPOCH,
+            }))
+        }
+    }
+
+    /// Puts the list of versions of the given package into the cache, along with the time they
+    /// were listed at.
+    ///
+    /// The default implementation doesn't cache the versions, so that caches written before
+    /// versions were cached keep working. Such caches always list versions from the registry and
+    /// can't list them in offline mode.
+    fn put_versions(
+        &self,
+        package: &PackageRef,
+        versions: &CachedVersions,
+    ) -> impl Future<Output = Result<(), Error>> + Send {
+        let _ = (package, versions);
+        async { Ok(()) }
+    }
+
+    /// Gets the list of versions of the given package from the cache. Returns None if the versions
+    /// are not in the cache.
+    ///
+    /// The default implementation never returns any versions, see [`Cache::put_versions`].
+    fn get_versions(
+        &self,
+        package: &PackageRef,
+    ) -> impl Future<Output = Result<Option<CachedVersions>, Error>> + Send {
+        let _ = package;
+        async { Ok(None) }
+    }
+}
+
+/// A release along with the time it was fetched from the registry at.
+#[derive(Clone, Debug)]
+pub struct FetchedRelease {
+    pub release: Release,
+    pub fetched_at: SystemTime,
+}
+
+impl FetchedRelease {
+    /// Creates a release that was just fetched.
+    pub fn new(release: Release) -> Self {
+        Self {
+            release,
+            fetched_at: SystemTime::now(),
+        }
+    }
+}
+
+/// When cached releases are checked against the registry. Registries like OCI registries allow
+/// republishing a version (tag) with different content, which would otherwise go unnoticed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RevalidationPolicy {
+    /// Always use cached releases.
+    #[default]
+    Never,
+    /// Check cached releases that were fetched longer than the given duration ago.
+    After(Duration),
+    /// Check cached releases every time they are used.
+    Always,
+}
+
+impl RevalidationPolicy {
+    fn needs_revalidation(&self, fetched: &FetchedRelease) -> bool {
+        match self {
+            Self::Never => false,
+            Self::After(ttl) => fetched
+                .fetched_at
+                .elapsed()
+                .is_ok_and(|elapsed| elapsed >= *ttl),
+            Self::Always => true,
+        }
+    }
+}
+
+/// What to do when the digest of a revalidated release changed in the registry.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DigestMismatchAction {
+    /// Log a warning and use the current release of the registry.
+    #[default]
+    Warn,
+    /// Fail with [`Error::DigestChanged`].
+    Error,
+}
+
+/// A list of the versions of a package along with the time they were listed at.
+#[derive(Clone, Debug)]
+pub struct CachedVersions {
+    pub versions: Vec<VersionInfo>,
+    pub listed_at: SystemTime,
+}
+
+impl CachedVersions {
+    /// Creates a list of versions that were just listed.
+    pub fn new(versions: Vec<VersionInfo>) -> Self {
+        Self {
+            versions,
+            listed_at: SystemTime::now(),
+        }
+    }
+
+    /// Returns whether the versions were listed less than `ttl` ago.
+    pub fn is_fresh(&self, ttl: Duration) -> bool {
+        self.listed_at.elapsed().is_ok_and(|elapsed| elapsed < ttl)
+    }
 }
 
 /// A client that caches response data using the given cache implementation. Can be used without an
 /// underlying client to be used as a read-only cache.
-#[derive(Clone)]
 pub struct CachingClient<T> {
     client: Option<Client>,
     cache: Arc<T>,
+    offline: bool,
+    versions_ttl: Duration,
+    revalidation: RevalidationPolicy,
+    on_digest_mismatch: DigestMismatchAction,
+}
+
+// Not derived, as the cache is shared and doesn't need to be `Clone`
+impl<T> Clone for CachingClient<T> {
+    fn clone(&self) -> Self {
+        Self {
+            client: self.client.clone(),
+            cache: self.cache.clone(),
+            offline: self.offline,
+            versions_ttl: self.versions_ttl,
+            revalidation: self.revalidation,
+            on_digest_mismatch: self.on_digest_mismatch,
+        }
+    }
 }
 
 impl<T: Cache> CachingClient<T> {
@@ -59,39 +213,129 @@ impl<T: Cache> CachingClient<T> {
         Self {
             client,
             cache: Arc::new(cache),
+            offline: false,
+            versions_ttl: Duration::ZERO,
+            revalidation: RevalidationPolicy::Never,
+            on_digest_mismatch: DigestMismatchAction::Warn,
         }
     }
 
+    /// Creates a new caching client in offline mode. Like a client created without an underlying
+    /// client, it will only be able to return things that are already in the cache, but the config
+    /// of the given client is still available, e.g. for resolving the registry of a package.
+    pub fn new_offline(client: Client, cache: T) -> Self {
+        Self::new(Some(client), cache).into_offline()
+    }
+
+    /// Puts the client into offline mode. See [`CachingClient::new_offline`].
+    pub fn into_offline(mut self) -> Self {
+        self.offline = true;
+        self
+    }
+
+    /// Sets how long listed versions of a package are used before they are listed again. By default,
+    /// versions are always listed again, unless the client is in read-only mode.
+    pub fn with_versions_ttl(mut self, ttl: Duration) -> Self {
+        self.versions_ttl = ttl;
+        self
+    }
+
+    /// Sets when cached releases are checked against the registry and what to do if their digest
+    /// changed. By default, cached releases are never checked. Releases can't be checked in
+    /// read-only mode.
+    pub fn with_revalidation(
+        mut self,
+        policy: RevalidationPolicy,
+        on_mismatch: DigestMismatchAction,
+    ) -> Self {
+        self.revalidation = policy;
+        self.on_digest_mismatch = on_mismatch;
+        self
+    }
+
     /// Returns whether or not the client is in read-only mode.
     pub fn is_readonly(&self) -> bool {
-        self.client.is_none()
+        self.offline || self.client.is_none()
+    }
+
+    /// Returns the config of the underlying client, even if the client is in offline mode. Returns
+    /// `None` if no client was provided.
+    pub fn config(&self) -> Option<&Config> {
+        self.client.as_ref().map(Client::config)
     }
 
-    /// Returns a list of all package [`VersionInfo`]s available for the given package. This will
-    /// always fail if no client was provided.
+    /// Returns a list of all package [`VersionInfo`]s available for the given package. Versions
+    /// that were listed within the [TTL](Self::with_versions_ttl) are returned from the cache. In
+    /// read-only mode, cached versions are returned no matter how long ago they were listed.
     pub async fn list_all_versions(&self, package: &PackageRef) -> Result<Vec<VersionInfo>, Error> {
+        let cached = self.cache.get_versions(package).await?;
+        if let Some(cached) = cached
+            .as_ref()
+            .filter(|cached| cached.is_fresh(self.versions_ttl))
+        {
+            return Ok(cached.versions.clone());
+        }
+        if self.is_readonly() {
+            return cached.map(|cached| cached.versions).ok_or_else(|| {
+                Error::CacheError(anyhow::anyhow!(
+                    "the versions of {package} are not in the cache and can't be listed in offline mode"
+                ))
+            });
+        }
+
         let client = self.client()?;
-        client.list_all_versions(package).await
+        let versions = client.list_all_versions(package).await?;
+        self.cache
+            .put_versions(package, &CachedVersions::new(versions.clone()))
+            .await?;
+        Ok(versions)
     }
 
-    /// Returns a [`Release`] for the given package version.
+    /// Returns a [`Release`] for the given package version. Cached releases are checked against
+    /// the registry according to the [revalidation policy](Self::with_revalidation).
     pub async fn get_release(
         &self,
         package: &PackageRef,
         version: &Version,
     ) -> Result<Release, Error> {
-        if let Some(data) = self.cache.get_release(package, version).await? {
-            return Ok(data);
+        let cached = self.cache.get_release_fetched(package, version).await?;
+        match cached {
+            Some(cached)
+                if self.is_readonly() || !self.revalidation.needs_revalidation(&cached) =>
+            {
+                return Ok(cached.release);
+            }
+            None if self.is_readonly() => {
+                return Err(Error::CacheError(anyhow::anyhow!(
+                    "release {package}@{version} is not in the cache and can't be fetched in offline mode"
+                )));
+            }
+            _ => {}
         }
 
         let client = self.client()?;
         let release = client.get_release(package, version).await?;
-        self.cache.put_release(package, &release).await?;
+        if let Some(cached) = cached.filter(|c| c.release.content_digest != release.content_digest)
+        {
+            let err = Error::DigestChanged(format!(
+                "{package}@{version} was cached as {cached} but is {current} in the registry",
+                cached = cached.release.content_digest,
+                current = release.content_digest,
+            ));
+            match self.on_digest_mismatch {
+                DigestMismatchAction::Warn => tracing::warn!("{err}, using the current release"),
+                DigestMismatchAction::Error => return Err(err),
+            }
+        }
+        self.cache
+            .put_release_fetched(package, &FetchedRelease::new(release.clone()))
+            .await?;
         Ok(release)
     }
 
     /// Returns a [`ContentStream`] of content chunks. If the data is in the cache, it will be returned,
-    /// otherwise it will be fetched from an upstream registry and then cached. This is the same as
+    /// otherwise it will be fetched from an upstream registry and then cached. Data the cache doesn't
+    /// keep is streamed from the registry again. This is the same as
     /// [`Client::stream_content`] but named differently to avoid confusion when trying to use this
     /// as a normal [`Client`].
     pub async fn get_content(
@@ -102,6 +346,13 @@ impl<T: Cache> CachingClient<T> {
         if let Some(data) = self.cache.get_data(&release.content_digest).await? {
             return Ok(data);
         }
+        if self.is_readonly() {
+            return Err(Error::CacheError(anyhow::anyhow!(
+                "content of {package}@{version} ({digest}) is not in the cache and can't be fetched in offline mode",
+                version = release.version,
+                digest = release.content_digest,
+            )));
+        }
 
         let client = self.client()?;
         let stream = client.stream_content(package, release).await?;
@@ -109,14 +360,13 @@ impl<T: Cache> CachingClient<T> {
             .put_data(release.content_digest.clone(), stream)
             .await?;
 
-        self.cache
-            .get_data(&release.content_digest)
-            .await?
-            .ok_or_else(|| {
-                Error::CacheError(anyhow::anyhow!(
-                    "Cached data was deleted after putting the data in cache"
-                ))
-            })
+        if let Some(data) = self.cache.get_data(&release.content_digest).await? {
+            return Ok(data);
+        }
+        // The cache may refuse or immediately evict data, e.g. data that is larger than the budget
+        // of an `InMemoryCache`, in which case the content is streamed from the registry again
+        tracing::debug!(%package, version = %release.version, "Content was not kept by the cache");
+        client.stream_content(package, release).await
     }
 
     /// Returns a reference to the underlying client. Returns an error if the client is in read-only
@@ -126,6 +376,7 @@ impl<T: Cache> CachingClient<T> {
     pub fn client(&self) -> Result<&Client, Error> {
         self.client
             .as_ref()
+            .filter(|_| !self.offline)
             .ok_or_else(|| Error::CacheError(anyhow::anyhow!("Client is in read only mode")))
     }
 
@@ -133,6 +384,251 @@ impl<T: Cache> CachingClient<T> {
     /// client is in read-only mode.
     pub fn into_client(self) -> Result<Client, Error> {
         self.client
+            .filter(|_| !self.offline)
             .ok_or_else(|| Error::CacheError(anyhow::anyhow!("Client is in read only mode")))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use wasm_pkg_common::{
+        Error,
+        package::{PackageRef, Version},
+    };
+
+    use bytes::Bytes;
+    use futures_util::TryStreamExt;
+    use sha2::{Digest, Sha256};
+    use wasm_pkg_common::digest::ContentDigest;
+
+    use super::{
+        Cache, CachedVersions, CachingClient, DigestMismatchAction, FetchedRelease, FileCache,
+        InMemoryCache, RevalidationPolicy,
+    };
+    use crate::{Client, Config, ContentStream, Release, VersionInfo};
+
+    fn versions(versions: &[&str]) -> Vec<VersionInfo> {
+        versions
+            .iter()
+            .map(|version| VersionInfo {
+                version: version.parse().unwrap(),
+                yanked: false,
+            })
+            .collect()
+    }
+
+    /// Returns a client for a local registry in the given directory.
+    fn local_registry_client(dir: &std::path::Path) -> Client {
+        let config = Config::from_toml(&format!(
+            r#"
+default_registry = "local"
+
+[registry."local".local]
+root = '{}'
+"#,
+            dir.display()
+        ))
+        .unwrap();
+        Client::new(config)
+    }
+
+    #[tokio::test]
+    async fn versions_are_cached_within_ttl() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = FileCache::new(dir.path().join("cache")).await.unwrap();
+        let package: PackageRef = "example:foo".parse().unwrap();
+        cache
+            .put_versions(&package, &CachedVersions::new(versions(&["0.1.0"])))
+            .await
+            .unwrap();
+
+        let client = CachingClient::new(Some(local_registry_client(dir.path())), cache);
+        let err = client.list_all_versions(&package).await.unwrap_err();
+        assert!(
+            matches!(err, Error::PackageNotFound),
+            "versions should be listed again without a TTL: {err:?}"
+        );
+
+        let client = client.with_versions_ttl(Duration::from_secs(60 * 60));
+        assert_eq!(
+            client.list_all_versions(&package).await.unwrap(),
+            versions(&["0.1.0"])
+        );
+    }
+
+    #[tokio::test]
+    async fn stale_versions_are_used_offline() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = FileCache::new(dir.path()).await.unwrap();
+        let package: PackageRef = "example:foo".parse().unwrap();
+        let stale = CachedVersions {
+            versions: versions(&["0.1.0", "0.2.0"]),
+            listed_at: SystemTime::UNIX_EPOCH,
+        };
+        cache.put_versions(&package, &stale).await.unwrap();
+
+        let client = CachingClient::new(None, cache);
+        assert_eq!(
+            client.list_all_versions(&package).await.unwrap(),
+            versions(&["0.1.0", "0.2.0"])
+        );
+        client
+            .list_all_versions(&"example:bar".parse().unwrap())
+            .await
+            .expect_err("versions that aren't cached can't be listed offline");
+    }
+
+    /// A cache written against the original `Cache` trait, which only had the data and release
+    /// methods
+    struct BaselineCache(FileCache);
+
+    impl Cache for BaselineCache {
+        async fn put_data(&self, digest: ContentDigest, data: ContentStream) -> Result<(), Error> {
+            self.0.put_data(digest, data).await
+        }
+
+        async fn get_data(&self, digest: &ContentDigest) -> Result<Option<ContentStream>, Error> {
+            self.0.get_data(digest).await
+        }
+
+        async fn put_release(&self, package: &PackageRef, release: &Release) -> Result<(), Error> {
+            self.0.put_release(package, release).await
+        }
+
+        async fn get_release(
+            &self,
+            package: &PackageRef,
+            version: &Version,
+        ) -> Result<Option<Release>, Error> {
+            self.0.get_release(package, version).await
+        }
+    }
+
+    #[tokio::test]
+    async fn baseline_caches_use_the_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = BaselineCache(FileCache::new(dir.path().join("cache")).await.unwrap());
+        let package: PackageRef = "example:foo".parse().unwrap();
+        cache
+            .put_versions(&package, &CachedVersions::new(versions(&["0.1.0"])))
+            .await
+            .unwrap();
+        assert!(cache.get_versions(&package).await.unwrap().is_none());
+
+        let version: Version = "0.1.0".parse().unwrap();
+        let cached: ContentDigest = Sha256::new_with_prefix(b"original").into();
+        let release = Release {
+            version: version.clone(),
+            content_digest: cached.clone(),
+        };
+        cache
+            .put_release_fetched(&package, &FetchedRelease::new(release))
+            .await
+            .unwrap();
+        let fetched = cache
+            .get_release_fetched(&package, &version)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(fetched.release.content_digest, cached);
+        assert_eq!(
+            fetched.fetched_at,
+            SystemTime::UNIX_EPOCH,
+            "the fetch time isn't stored by default"
+        );
+
+        let client = CachingClient::new(None, cache);
+        client
+            .list_all_versions(&package)
+            .await
+            .expect_err("versions can't be listed offline without caching them");
+        assert_eq!(
+            client
+                .get_release(&package, &version)
+                .await
+                .unwrap()
+                .content_digest,
+            cached
+        );
+    }
+
+    #[tokio::test]
+    async fn content_larger_than_the_cache_budget_is_streamed() {
+        let dir = tempfile::tempdir().unwrap();
+        let package_dir = dir.path().join("example").join("foo");
+        tokio::fs::create_dir_all(&package_dir).await.unwrap();
+        tokio::fs::write(package_dir.join("0.1.0.wasm"), b"larger than the budget")
+            .await
+            .unwrap();
+        let cache = InMemoryCache::with_max_bytes(4);
+        let client = CachingClient::new(Some(local_registry_client(dir.path())), cache.clone());
+        let package: PackageRef = "example:foo".parse().unwrap();
+
+        let release = client
+            .get_release(&package, &"0.1.0".parse().unwrap())
+            .await
+            .unwrap();
+        let content: Vec<Bytes> = client
+            .get_content(&package, &release)
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(content.concat(), b"larger than the budget");
+        assert_eq!(cache.size(), 0, "the content shouldn't be cached");
+    }
+
+    #[tokio::test]
+    async fn cached_releases_are_revalidated() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = FileCache::new(dir.path().join("cache")).await.unwrap();
+        let package: PackageRef = "example:foo".parse().unwrap();
+        let version = "0.1.0".parse().unwrap();
+        // The release was republished with different content after it was cached
+        let package_dir = dir.path().join("example").join("foo");
+        tokio::fs::create_dir_all(&package_dir).await.unwrap();
+        tokio::fs::write(package_dir.join("0.1.0.wasm"), b"republished")
+            .await
+            .unwrap();
+        let current: ContentDigest = Sha256::new_with_prefix(b"republished").into();
+        let cached: ContentDigest = Sha256::new_with_prefix(b"original").into();
+        let release = Release {
+            version: "0.1.0".parse().unwrap(),
+            content_digest: cached.clone(),
+        };
+        cache.put_release(&package, &release).await.unwrap();
+
+        let client = CachingClient::new(Some(local_registry_client(dir.path())), cache);
+        let release = client.get_release(&package, &version).await.unwrap();
+        assert_eq!(
+            release.content_digest, cached,
+            "cached releases shouldn't be revalidated by default"
+        );
+        let client = client.with_revalidation(
+            RevalidationPolicy::After(Duration::from_secs(60 * 60)),
+            DigestMismatchAction::Error,
+        );
+        let release = client.get_release(&package, &version).await.unwrap();
+        assert_eq!(release.content_digest, cached);
+
+        let client =
+            client.with_revalidation(RevalidationPolicy::Always, DigestMismatchAction::Error);
+        let err = client.get_release(&package, &version).await.unwrap_err();
+        assert!(matches!(err, Error::DigestChanged(_)), "{err:?}");
+
+        let client =
+            client.with_revalidation(RevalidationPolicy::Always, DigestMismatchAction::Warn);
+        let release = client.get_release(&package, &version).await.unwrap();
+        assert_eq!(release.content_digest, current);
+        let client =
+            client.with_revalidation(RevalidationPolicy::Never, DigestMismatchAction::Warn);
+        let release = client.get_release(&package, &version).await.unwrap();
+        assert_eq!(
+            release.content_digest, current,
+            "the current release should replace the cached one"
+        );
+    }
+}
@@ -0,0 +1,317 @@
+//! Read-only package backend for git repositories, using the `git` command line tool.
+//!
+//! Each release of a package is a tag of the repository: `<namespace>-<name>/v<version>`, e.g.
+//! `wasi-http/v0.2.1`. The tagged commit contains either a `wit` directory at the root of the
+//! repository, which is built into a WIT package when the release is fetched, or a single `.wasm`
+//! file at the root of the repository. The `path` config can point at a different WIT directory or
+//! `.wasm` file in the repository instead.
+//!
+//! The repository is given by the `url` of the `git` registry config or of the `git` protocol
+//! config of the registry metadata. In the registry config it can be anything `git clone` accepts,
+//! including the path of a local (bare) repository, while registry metadata can only use `https`
+//! urls.
+//!
+//! The digest of a release that is built from a WIT directory is the digest of the WIT package
+//! that [`wit_component::encode`] produces for it. A different version of `wit-component` may
+//! encode the same WIT differently, which changes the digest of the release, so lock files that
+//! refer to such releases may have to be updated after upgrading.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::Mutex,
+};
+
+use anyhow::{Context, anyhow};
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures_util::{StreamExt, stream};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use wasm_pkg_common::{
+    Error,
+    config::RegistryConfig,
+    metadata::{GIT_PROTOCOL, RegistryMetadata},
+    package::{PackageRef, Version},
+};
+
+use crate::{
+    ContentStream, PublishingSource,
+    loader::PackageLoader,
+    publisher::PackagePublisher,
+    release::{Release, VersionInfo},
+};
+
+/// Config of the `git` backend, which can be given in the registry config or metadata.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GitConfig {
+    /// The URL or path of the repository.
+    pub url: String,
+    /// The path of the WIT directory or `.wasm` file in the repository. Defaults to the `wit`
+    /// directory, or the only `.wasm` file, at the root of the repository.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+}
+
+pub(crate) struct GitBackend {
+    config: GitConfig,
+    /// Content of the releases that were already fetched, as WIT packages are built on fetch
+    content: Mutex<HashMap<(PackageRef, Version), Bytes>>,
+}
+
+impl GitBackend {
+    pub(crate) fn new(
+        registry_config: &RegistryConfig,
+        registry_meta: &RegistryMetadata,
+    ) -> Result<Self, Error> {
+        let config = match registry_config.backend_config::<GitConfig>(GIT_PROTOCOL)? {
+            Some(config) => config,
+            None => {
+                let config = registry_meta
+                    .protocol_config::<GitConfig>(GIT_PROTOCOL)?
+                    .ok_or_else(|| {
+                        Error::InvalidConfig(anyhow!("'git' backend requires a repository url"))
+                    })?;
+                // Registry metadata cannot point at the filesystem or make git run other
+                // transports, such as `ext::`, just like it cannot force a filesystem backend
+                if !config.url.starts_with("https://") {
+                    return Err(Error::InvalidRegistryMetadata(anyhow!(
+                        "registry metadata with a non-https git url not allowed"
+                    )));
+                }
+                config
+            }
+        };
+        // After `--`, git takes a url starting with `-` as a path, which is never what was meant.
+        // The user and host of ssh urls are passed on to ssh though, which would take them as
+        // options, e.g. `ssh://-oProxyCommand=...`
+        let is_option_like = |part: &str| part.starts_with('-');
+        if is_option_like(&config.url)
+            || ssh_destination(&config.url).is_some_and(|dest| dest.split('@').any(is_option_like))
+        {
+            return Err(Error::InvalidConfig(anyhow!(
+                "invalid git repository url {:?}",
+                config.url
+            )));
+        }
+        Ok(Self {
+            config,
+            content: Default::default(),
+        })
+    }
+
+    fn tag_prefix(package: &PackageRef) -> String {
+        format!("{}-{}/v", package.namespace(), package.name())
+    }
+
+    /// Loads the content of the release, building it from the tagged commit if it wasn't loaded
+    /// before.
+    async fn load_content(&self, package: &PackageRef, version: &Version) -> Result<Bytes, Error> {
+        let key = (package.clone(), version.clone());
+        if let Some(content) = self
+            .content
+            .lock()
+            .expect("content cache lock poisoned")
+            .get(&key)
+        {
+            return Ok(content.clone());
+        }
+
+        let checkout = tempfile::tempdir()?;
+        let tag = format!("{}{version}", Self::tag_prefix(package));
+        // `--no-local` makes `--depth` work for local repositories too
+        git(&[
+            "clone",
+            "--quiet",
+            "--no-local",
+            "--depth",
+            "1",
+            "--branch",
+            &tag,
+            "--",
+            &self.config.url,
+            &checkout.path().to_string_lossy(),
+        ])
+        .await
+        .map_err(|e| match e {
+            Error::RegistryError(e) => Error::RegistryError(
+                e.context(format!("failed to check out {tag} of {}", self.config.url)),
+            ),
+            e => e,
+        })?;
+
+        let path = match &self.config.path {
+            Some(path) => checkout.path().join(path),
+            None => default_path(checkout.path()).await?,
+        };
+        let content = if path.is_dir() {
+            let package = package.clone();
+            tokio::task::spawn_blocking(move || build_wit_package(&path, &package))
+                .await
+                .map_err(|e| Error::RegistryError(e.into()))??
+        } else {
+            tokio::fs::read(&path)
+                .await
+                .with_context(|| format!("failed to read {} of {tag}", path.display()))
+                .map_err(Error::RegistryError)?
+        };
+        let content = Bytes::from(content);
+        self.content
+            .lock()
+            .expect("content cache lock poisoned")
+            .insert(key, content.clone());
+        Ok(content)
+    }
+}
+
+/// Returns the `[user@]host[:port]` of ssh urls, both `ssh://` urls and the scp-like
+/// `[user@]host:path`, which git passes on to ssh.
+fn ssh_destination(url: &str) -> Option<&str> {
+    if let Some((scheme, rest)) = url.split_once("://") {
+        return matches!(scheme, "ssh" | "git+ssh" | "ssh+git")
+            .then(|| rest.split('/').next().unwrap_or_default());
+    }
+    // The scp-like syntax has a colon before the first slash
+    let (destination, _) = url.split_once(':')?;
+    (!destination.contains('/')).then_some(destination)
+}
+
+/// Runs git with the given args, returning its stdout.
+async fn git(args: &[&str]) -> Result<String, Error> {
+    let output = tokio::process::Command::new("git")
+        .args(args)
+        // Never wait for credentials on a terminal
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .map_err(|e| Error::RegistryError(anyhow::Error::new(e).context("failed to run git")))?;
+    if !output.status.success() {
+        return Err(Error::RegistryError(anyhow!(
+            "git {} failed: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    String::from_utf8(output.stdout).map_err(|e| Error::RegistryError(e.into()))
+}
+
+/// Returns the `wit` directory at the root of the checkout, or the only `.wasm` file there.
+async fn default_path(checkout: &Path) -> Result<PathBuf, Error> {
+    let wit_dir = checkout.join("wit");
+    if wit_dir.is_dir() {
+        return Ok(wit_dir);
+    }
+    let mut wasm_files = Vec::new();
+    let mut entries = tokio::fs::read_dir(checkout).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if path.extension() == Some("wasm".as_ref()) && path.is_file() {
+            wasm_files.push(path);
+        }
+    }
+    match <[PathBuf; 1]>::try_from(wasm_files) {
+        Ok([path]) => Ok(path),
+        Err(files) => Err(Error::InvalidPackage(anyhow!(
+            "expected a `wit` directory or a single `.wasm` file in the repository, found {} \
+             `.wasm` files",
+            files.len()
+        ))),
+    }
+}
+
+/// Builds the WIT package in the given directory, making sure it is the expected package.
+fn build_wit_package(dir: &Path, package: &PackageRef) -> Result<Vec<u8>, Error> {
+    let mut resolve = wit_parser::Resolve::default();
+    let (pkg_id, _) = resolve
+        .push_dir(dir)
+        .map_err(|e| Error::InvalidPackage(e.context(format!("{}", dir.display()))))?;
+    let name = &resolve.packages[pkg_id].name;
+    if name.namespace != package.namespace().as_ref() || name.name != package.name().as_ref() {
+        return Err(Error::InvalidPackage(anyhow!(
+            "the WIT directory contains package {name}, expected {package}"
+        )));
+    }
+    wit_component::encode(&resolve, pkg_id).map_err(Error::InvalidPackage)
+}
+
+#[async_trait]
+impl PackageLoader for GitBackend {
+    async fn list_all_versions(&self, package: &PackageRef) -> Result<Vec<VersionInfo>, Error> {
+        let prefix = Self::tag_prefix(package);
+        let output = git(&[
+            "ls-remote",
+            "--tags",
+            "--refs",
+            "--",
+            &self.config.url,
+            &format!("refs/tags/{prefix}*"),
+        ])
+        .await?;
+        let versions: Vec<_> = output
+            .lines()
+            .filter_map(|line| line.split_once('\t'))
+            .filter_map(|(_, reference)| {
+                reference.strip_prefix("refs/tags/")?.strip_prefix(&prefix)
+            })
+            .filter_map(|version| match Version::parse(version) {
+                Ok(version) => Some(VersionInfo {
+                    version,
+                    yanked: false,
+                }),
+                Err(err) => {
+                    tracing::warn!(?version, error = ?err, "Ignoring invalid version tag");
+                    None
+                }
+            })
+            .collect();
+        if versions.is_empty() {
+            return Err(Error::PackageNotFound);
+        }
+        Ok(versions)
+    }
+
+    async fn get_release(&self, package: &PackageRef, version: &Version) -> Result<Release, Error> {
+        let content = self.load_content(package, version).await?;
+        Ok(Release {
+            version: version.clone(),
+            content_digest: Sha256::new_with_prefix(&content).into(),
+        })
+    }
+
+    async fn stream_content_unvalidated(
+        &self,
+        package: &PackageRef,
+        release: &Release,
+    ) -> Result<ContentStream, Error> {
+        let content = self.load_content(package, &release.version).await?;
+        Ok(stream::once(async { Ok(content) }).boxed())
+    }
+}
+
+#[async_trait]
+impl PackagePublisher for GitBackend {
+    async fn publish(
+        &self,
+        _package: &PackageRef,
+        _version: &Version,
+        _data: PublishingSource,
+        _dry_run: bool,
+    ) -> Result<(), Error> {
+        Err(Error::RegistryError(anyhow!(
+            "the 'git' backend is read-only, tag a release in the repository instead"
+        )))
+    }
+
+    async fn set_yanked(
+        &self,
+        _package: &PackageRef,
+        _version: &Version,
+        _yanked: bool,
+    ) -> Result<(), Error> {
+        Err(Error::RegistryError(anyhow!(
+            "the 'git' backend is read-only"
+        )))
+    }
+}
@@ -0,0 +1,266 @@
+//! Read-only package backend for a static index, which can be served by any static file host.
+//!
+//! Each package has a JSON index listing its releases:
+//! `<url>/<namespace>/<name>.json`
+//!
+//! ```json
+//! {"versions": [{"version": "1.0.0", "digest": "sha256:...", "yanked": false}]}
+//! ```
+//!
+//! The content of releases is stored by digest: `<url>/blobs/sha256/<hex>`
+//!
+//! The base URL is given by the `url` of the `index` protocol config of the registry metadata, or
+//! of the registry config. Besides `http` and `https`, `file` URLs are supported in the registry
+//! config, e.g. to use an index on a local or mounted filesystem. Registry metadata can't use
+//! `file` URLs. The registry config also accepts the `accept_invalid_certificates` and
+//! `extra_root_certificates` options of the `oci` backend.
+
+use std::path::PathBuf;
+
+use anyhow::{Context, anyhow};
+use async_trait::async_trait;
+use futures_util::{StreamExt, TryStreamExt};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use tokio_util::io::ReaderStream;
+use url::Url;
+use wasm_pkg_common::{
+    Error,
+    config::RegistryConfig,
+    digest::ContentDigest,
+    metadata::{INDEX_PROTOCOL, RegistryMetadata},
+    package::{PackageRef, Version},
+};
+
+use crate::{
+    ContentStream, PublishingSource,
+    loader::PackageLoader,
+    oci::{TlsConfigToml, client::ClientConfig, http_client},
+    publisher::PackagePublisher,
+    release::{Release, VersionInfo},
+};
+
+/// Config of the `index` backend, which can be given in the registry metadata or config.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct IndexConfig {
+    /// The base URL of the index.
+    pub url: String,
+}
+
+/// The index of a package, stored at `<url>/<namespace>/<name>.json`.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct PackageIndex {
+    pub versions: Vec<IndexEntry>,
+}
+
+/// A release of a package in its [`PackageIndex`].
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct IndexEntry {
+    pub version: Version,
+    pub digest: ContentDigest,
+    #[serde(default)]
+    pub yanked: bool,
+}
+
+pub(crate) struct IndexBackend {
+    base_url: Url,
+    client: reqwest::Client,
+}
+
+impl IndexBackend {
+    pub(crate) fn new(
+        registry_config: &RegistryConfig,
+        registry_meta: &RegistryMetadata,
+    ) -> Result<Self, Error> {
+        let (config, from_metadata) =
+            match registry_config.backend_config::<IndexConfig>(INDEX_PROTOCOL)? {
+                Some(config) => (config, false),
+                None => (
+                    registry_meta
+                        .protocol_config::<IndexConfig>(INDEX_PROTOCOL)?
+                        .ok_or_else(|| {
+                            Error::InvalidConfig(anyhow!("'index' backend requires a base url"))
+                        })?,
+                    true,
+                ),
+            };
+        let mut base_url: Url = config
+            .url
+            .parse()
+            .with_context(|| format!("invalid index url {:?}", config.url))
+            .map_err(Error::InvalidConfig)?;
+        // Make sure paths are joined onto the base URL instead of replacing its last segment
+        if !base_url.path().ends_with('/') {
+            base_url.set_path(&format!("{}/", base_url.path()));
+        }
+        match base_url.scheme() {
+            "http" | "https" => {}
+            // Registry metadata cannot point at the filesystem, just like it cannot force a
+            // filesystem backend
+            "file" if from_metadata => {
+                return Err(Error::InvalidRegistryMetadata(anyhow!(
+                    "registry metadata with a 'file' index url not allowed"
+                )));
+            }
+            "file" => {}
+            scheme => {
+                return Err(Error::InvalidConfig(anyhow!(
+                    "unsupported index url scheme {scheme:?}"
+                )));
+            }
+        }
+        // TLS options can only be given in the registry config, like `file` urls
+        let client_config = ClientConfig {
+            user_agent: concat!("wasm-pkg-client/", env!("CARGO_PKG_VERSION")),
+            ..registry_config
+                .backend_config::<TlsConfigToml>(INDEX_PROTOCOL)?
+                .unwrap_or_default()
+                .into_client_config()?
+        };
+        Ok(Self {
+            base_url,
+            client: http_client(&client_config)?,
+        })
+    }
+
+    fn url(&self, path: &str) -> Result<Url, Error> {
+        self.base_url
+            .join(path)
+            .map_err(|e| Error::RegistryError(e.into()))
+    }
+
+    fn index_url(&self, package: &PackageRef) -> Result<Url, Error> {
+        self.url(&format!("{}/{}.json", package.namespace(), package.name()))
+    }
+
+    fn blob_url(&self, digest: &ContentDigest) -> Result<Url, Error> {
+        match digest {
+            ContentDigest::Sha256 { hex } => self.url(&format!("blobs/sha256/{hex}")),
+        }
+    }
+
+    /// Fetches the content at the given URL. Returns `None` if it doesn't exist.
+    async fn fetch(&self, url: &Url) -> Result<Option<ContentStream>, Error> {
+        tracing::debug!(%url, "Fetching from index");
+        if url.scheme() == "file" {
+            let file = match tokio::fs::File::open(file_path(url)?).await {
+                Ok(file) => file,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+                Err(e) => {
+                    return Err(Error::RegistryError(
+                        anyhow::Error::new(e).context(format!("url: {url}")),
+                    ));
+                }
+            };
+            return Ok(Some(ReaderStream::new(file).map_err(Into::into).boxed()));
+        }
+
+        let resp = self
+            .client
+            .get(url.clone())
+            .send()
+            .await
+            .map_err(|e| Error::RegistryError(e.into()))?;
+        if resp.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let resp = resp
+            .error_for_status()
+            .map_err(|e| Error::RegistryError(e.into()))?;
+        Ok(Some(
+            resp.bytes_stream()
+                .map_err(|e| Error::RegistryError(e.into()))
+                .boxed(),
+        ))
+    }
+
+    async fn package_index(&self, package: &PackageRef) -> Result<PackageIndex, Error> {
+        let url = self.index_url(package)?;
+        let Some(stream) = self.fetch(&url).await? else {
+            return Err(Error::PackageNotFound);
+        };
+        let data: Vec<u8> = stream
+            .try_fold(Vec::new(), |mut data, bytes| async move {
+                data.extend_from_slice(&bytes);
+                Ok(data)
+            })
+            .await?;
+        serde_json::from_slice(&data)
+            .with_context(|| format!("invalid package index at {url}"))
+            .map_err(Error::RegistryError)
+    }
+}
+
+fn file_path(url: &Url) -> Result<PathBuf, Error> {
+    url.to_file_path()
+        .map_err(|_| Error::InvalidConfig(anyhow!("invalid file url {url}")))
+}
+
+#[async_trait]
+impl PackageLoader for IndexBackend {
+    async fn list_all_versions(&self, package: &PackageRef) -> Result<Vec<VersionInfo>, Error> {
+        let index = self.package_index(package).await?;
+        Ok(index
+            .versions
+            .into_iter()
+            .map(|entry| VersionInfo {
+                version: entry.version,
+                yanked: entry.yanked,
+            })
+            .collect())
+    }
+
+    async fn get_release(&self, package: &PackageRef, version: &Version) -> Result<Release, Error> {
+        let index = self.package_index(package).await?;
+        let entry = index
+            .versions
+            .into_iter()
+            .find(|entry| &entry.version == version)
+            .ok_or_else(|| Error::VersionNotFound(version.clone()))?;
+        Ok(Release {
+            version: entry.version,
+            content_digest: entry.digest,
+        })
+    }
+
+    async fn stream_content_unvalidated(
+        &self,
+        _package: &PackageRef,
+        release: &Release,
+    ) -> Result<ContentStream, Error> {
+        let url = self.blob_url(&release.content_digest)?;
+        self.fetch(&url).await?.ok_or_else(|| {
+            Error::RegistryError(anyhow!(
+                "content {} of version {} not found at {url}",
+                release.content_digest,
+                release.version
+            ))
+        })
+    }
+}
+
+#[async_trait]
+impl PackagePublisher for IndexBackend {
+    async fn publish(
+        &self,
+        _package: &PackageRef,
+        _version: &Version,
+        _data: PublishingSource,
+        _dry_run: bool,
+    ) -> Result<(), Error> {
+        Err(Error::RegistryError(anyhow!(
+            "the 'index' backend is read-only"
+        )))
+    }
+
+    async fn set_yanked(
+        &self,
+        _package: &PackageRef,
+        _version: &Version,
+        _yanked: bool,
+    ) -> Result<(), Error> {
+        Err(Error::RegistryError(anyhow!(
+            "the 'index' backend is read-only"
+        )))
+    }
+}
@@ -26,8 +26,11 @@
 //! # Ok(()) }
 //! ```
 
+mod builder;
 pub mod caching;
 pub mod decoded_component;
+pub mod git;
+pub mod index;
 mod loader;
 pub mod local;
 pub mod metadata;
@@ -42,21 +45,24 @@ use bytes::Bytes;
 use decoded_component::DecodedComponent;
 use futures_util::Stream;
 use tokio::sync::RwLock;
-use wasm_pkg_common::metadata::{LOCAL_PROTOCOL, OCI_PROTOCOL};
+use wasm_pkg_common::metadata::{LOCAL_PROTOCOL, OCI_LAYOUT_PROTOCOL, OCI_PROTOCOL};
 pub use wasm_pkg_common::{
     Error,
-    config::{Config, CustomConfig, RegistryMapping},
+    config::{Config, CustomConfig, RegistryConfig, RegistryMapping},
     digest::ContentDigest,
     metadata::RegistryMetadata,
     package::{PackageRef, Version, VersionReq},
     registry::Registry,
 };
 
+use crate::builder::BackendFactories;
 use crate::loader::VersionSort;
-use crate::local::LocalBackend;
 use crate::metadata::RegistryMetadataExt;
-use crate::oci::OciBackend;
-pub use crate::{loader::PackageLoader, publisher::PackagePublisher};
+pub use crate::{
+    builder::{BackendFactory, ClientBuilder},
+    loader::PackageLoader,
+    publisher::PackagePublisher,
+};
 
 pub use release::{Release, VersionInfo};
 
@@ -71,12 +77,13 @@ pub trait ReaderSeeker: tokio::io::AsyncRead + tokio::io::AsyncSeek {}
 
 impl<T> ReaderSeeker for T where T: tokio::io::AsyncRead + tokio::io::AsyncSeek {}
 
-trait LoaderPublisher: PackageLoader + PackagePublisher {}
+/// A backend that can both load and publish packages, as created by a [`BackendFactory`].
+pub trait LoaderPublisher: PackageLoader + PackagePublisher {}
 
 impl<T> LoaderPublisher for T where T: PackageLoader + PackagePublisher {}
 
 type RegistrySources = HashMap<Registry, Arc<InnerClient>>;
-type InnerClient = Box<dyn LoaderPublisher + Sync>;
+type InnerClient = Box<dyn LoaderPublisher>;
 
 /// Additional options for publishing a package.
 #[derive(Clone, Debug, Default)]
@@ -96,16 +103,20 @@ pub struct PublishOpts {
 #[derive(Clone)]
 pub struct Client {
     config: Arc<Config>,
+    backends: Arc<BackendFactories>,
     sources: Arc<RwLock<RegistrySources>>,
 }
 
 impl Client {
-    /// Returns a new client with the given [`Config`].
+    /// Returns a new client with the given [`Config`] and the built-in backends.
     pub fn new(config: Config) -> Self {
-        Self {
-            config: Arc::new(config),
-            sources: Default::default(),
-        }
+        ClientBuilder::new(config).build()
+    }
+
+    /// Returns a [`ClientBuilder`] with the given [`Config`], which can register additional
+    /// backend types.
+    pub fn builder(config: Config) -> ClientBuilder {
+        ClientBuilder::new(config)
     }
 
     /// Returns a reference to the configuration this client was initialized with.
@@ -121,8 +132,21 @@ impl Client {
 
     /// Returns a list of all package [`Version`]s available for the given package.
     pub async fn list_all_versions(&self, package: &PackageRef) -> Result<Vec<VersionInfo>, Error> {
-        let source = self.resolve_source(package, None).await?;
-        source.list_all_versions(package).await
+        self.list_all_versions_from(package, None).await
+    }
+
+    /// Returns a list of all package [`Version`]s available for the given package. The registry can
+    /// be overridden with `registry`, e.g. to list the versions of the registry a package was
+    /// locked to.
+    pub async fn list_all_versions_from(
+        &self,
+        package: &PackageRef,
+        registry: Option<Registry>,
+    ) -> Result<Vec<VersionInfo>, Error> {
+        self.read_with_fallback(package, registry, |source| async move {
+            source.list_all_versions(package).await
+        })
+        .await
     }
 
     /// Returns a [`Release`] for the given package version.
@@ -131,9 +155,11 @@ impl Client {
         package: &PackageRef,
         version: &Version,
     ) -> Result<Release, Error> {
-        // FIXME: the `None` below means we ignore workspace overrides for this call
-        let source = self.resolve_source(package, None).await?;
-        source.get_release(package, version).await
+        // FIXME: this ignores workspace overrides of the registry
+        self.read_with_fallback(package, None, |source| async move {
+            source.get_release(package, version).await
+        })
+        .await
     }
 
     /// Returns a [`ContentStream`] of content chunks. Contents are validated
@@ -143,8 +169,10 @@ impl Client {
         package: &'a PackageRef,
         release: &'a Release,
     ) -> Result<ContentStream, Error> {
-        let source = self.resolve_source(package, None).await?;
-        source.stream_content(package, release).await
+        self.read_with_fallback(package, None, |source| async move {
+            source.stream_content(package, release).await
+        })
+        .await
     }
 
     /// Publishes the given file as a package release. The package name and version will be read
@@ -242,6 +270,30 @@ impl Client {
             .map(|_| (package, version))
     }
 
+    /// Marks the given package release as yanked. Yanked releases are no longer selected when
+    /// resolving version requirements, but can still be fetched by their exact version (e.g. when
+    /// pinned by a lock file). The registry can be overridden with `registry`.
+    pub async fn yank(
+        &self,
+        package: &PackageRef,
+        version: &Version,
+        registry: Option<Registry>,
+    ) -> Result<(), Error> {
+        let source = self.resolve_source(package, registry).await?;
+        source.set_yanked(package, version, true).await
+    }
+
+    /// Reverts a previous [`Client::yank`] of the given package release.
+    pub async fn unyank(
+        &self,
+        package: &PackageRef,
+        version: &Version,
+        registry: Option<Registry>,
+    ) -> Result<(), Error> {
+        let source = self.resolve_source(package, registry).await?;
+        source.set_yanked(package, version, false).await
+    }
+
     fn resolve_registry(
         &self,
         package: &PackageRef,
@@ -256,27 +308,76 @@ impl Client {
             .ok_or_else(|| Error::NoRegistryForNamespace(package.namespace().clone()))
     }
 
-    async fn resolve_source(
+    /// Runs the given read against the registry of the given package, then against each of the
+    /// registry's mirrors in order while they are unavailable. Other errors, e.g. a package that
+    /// doesn't exist, are returned as is. If all of them are unavailable, the error of the registry
+    /// itself is returned.
+    async fn read_with_fallback<T, F, Fut>(
         &self,
         package: &PackageRef,
         registry_override: Option<Registry>,
-    ) -> Result<Arc<InnerClient>, Error> {
+        read: F,
+    ) -> Result<T, Error>
+    where
+        F: Fn(Arc<InnerClient>) -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
         let is_override = registry_override.is_some();
         let registry = self.resolve_registry(package, registry_override)?;
         tracing::debug!(?registry, "resolved registry");
-
-        if let Some(source) = self.sources.read().await.get(&registry) {
-            return Ok(source.clone());
-        }
-
-        let registry_config = self
+        let mirrors = self
             .config
             .registry_config(&registry)
-            .cloned()
+            .map(|config| config.mirrors().to_vec())
             .unwrap_or_default();
+        let metadata = self.mapping_metadata(package, &registry, is_override);
+        if mirrors.is_empty() {
+            return read(self.registry_source(&registry, metadata).await?).await;
+        }
 
-        let maybe_metadata = self
-            .config
+        let mut registry_err = None;
+        let mut sources = std::iter::once((registry, metadata))
+            .chain(mirrors.into_iter().map(|mirror| (mirror, None)))
+            .peekable();
+        while let Some((registry, metadata)) = sources.next() {
+            let result = match self.registry_source(&registry, metadata).await {
+                Ok(source) => read(source).await,
+                Err(err) => Err(err),
+            };
+            match result {
+                Ok(value) => return Ok(value),
+                Err(err) if !is_unavailable(&err) => return Err(err),
+                Err(err) => {
+                    if sources.peek().is_some() {
+                        tracing::warn!(%registry, error = %err, "Registry unavailable, trying next mirror");
+                    }
+                    registry_err.get_or_insert(err);
+                }
+            }
+        }
+        Err(registry_err.expect("the registry itself is always tried"))
+    }
+
+    async fn resolve_source(
+        &self,
+        package: &PackageRef,
+        registry_override: Option<Registry>,
+    ) -> Result<Arc<InnerClient>, Error> {
+        let is_override = registry_override.is_some();
+        let registry = self.resolve_registry(package, registry_override)?;
+        tracing::debug!(?registry, "resolved registry");
+        let metadata = self.mapping_metadata(package, &registry, is_override);
+        self.registry_source(&registry, metadata).await
+    }
+
+    /// Returns the metadata given by the mapping of the package to the registry, if any.
+    fn mapping_metadata(
+        &self,
+        package: &PackageRef,
+        registry: &Registry,
+        is_override: bool,
+    ) -> Option<RegistryMetadata> {
+        self.config
             .package_registry_override(package)
             .and_then(|mapping| match mapping {
                 RegistryMapping::Custom(custom) => Some(custom.metadata.clone()),
@@ -291,7 +392,7 @@ impl Client {
                         // registry
                         match (meta, is_override) {
                             (RegistryMapping::Custom(custom), true)
-                                if custom.registry == registry =>
+                                if &custom.registry == registry =>
                             {
                                 Some(custom.metadata.clone())
                             }
@@ -301,15 +402,36 @@ impl Client {
                             _ => None,
                         }
                     })
-            });
+            })
+    }
+
+    /// Returns the source of the given registry, creating it with the given metadata, or the
+    /// metadata fetched from the registry, if it doesn't exist yet.
+    async fn registry_source(
+        &self,
+        registry: &Registry,
+        metadata: Option<RegistryMetadata>,
+    ) -> Result<Arc<InnerClient>, Error> {
+        if let Some(source) = self.sources.read().await.get(registry) {
+            return Ok(source.clone());
+        }
+
+        let registry_config = self
+            .config
+            .registry_config(registry)
+            .cloned()
+            .unwrap_or_default();
 
-        let registry_meta = if let Some(meta) = maybe_metadata {
+        let registry_meta = if let Some(meta) = metadata {
             meta
-        } else if registry_config.default_backend() == LOCAL_PROTOCOL.into() {
-            // Skip fetching metadata for "local" source
+        } else if matches!(
+            registry_config.default_backend(),
+            Some(LOCAL_PROTOCOL | OCI_LAYOUT_PROTOCOL)
+        ) {
+            // Skip fetching metadata for "local" and "oci-layout" sources
             RegistryMetadata::default()
         } else {
-            RegistryMetadata::fetch_or_default(&registry).await
+            RegistryMetadata::fetch_or_default(registry).await
         };
 
         // Resolve backend type
@@ -319,10 +441,11 @@ impl Client {
             None => {
                 // If the registry metadata indicates a preferred protocol, use it
                 let preferred_protocol = registry_meta.preferred_protocol();
-                // ...except registry metadata cannot force a local backend
-                if preferred_protocol == Some(LOCAL_PROTOCOL) {
+                // ...except registry metadata cannot force a filesystem backend
+                if let Some(protocol @ (LOCAL_PROTOCOL | OCI_LAYOUT_PROTOCOL)) = preferred_protocol
+                {
                     return Err(Error::InvalidRegistryMetadata(anyhow!(
-                        "registry metadata with 'local' protocol not allowed"
+                        "registry metadata with '{protocol}' protocol not allowed"
                     )));
                 }
                 preferred_protocol
@@ -332,19 +455,10 @@ impl Client {
         .unwrap_or(OCI_PROTOCOL);
         tracing::debug!(?backend_type, "Resolved backend type");
 
-        let source: InnerClient = match backend_type {
-            LOCAL_PROTOCOL => Box::new(LocalBackend::new(registry_config)?),
-            OCI_PROTOCOL => Box::new(OciBackend::new(
-                &registry,
-                &registry_config,
-                &registry_meta,
-            )?),
-            other => {
-                return Err(Error::InvalidConfig(anyhow!(
-                    "unknown backend type {other:?}"
-                )));
-            }
-        };
+        let factory = self.backends.get(backend_type).ok_or_else(|| {
+            Error::InvalidConfig(anyhow!("unknown backend type {backend_type:?}"))
+        })?;
+        let source = factory.create(registry, &registry_config, &registry_meta)?;
         let source = Arc::new(source);
         self.sources
             .write()
@@ -355,6 +469,12 @@ impl Client {
     }
 }
 
+/// Returns whether the error means that a registry couldn't be reached or failed to respond, in
+/// which case its mirrors are tried.
+fn is_unavailable(err: &Error) -> bool {
+    matches!(err, Error::RegistryError(_) | Error::IoError(_))
+}
+
 // Fetch every prior release in the same semver compatibility series as
 // `version`, sorted in descending order.
 //
@@ -362,7 +482,7 @@ impl Client {
 //   0.Y.z (Y >= 1) -> 0.Y.*     (in 0.x, minor bumps are breaking)
 //   0.0.Z          -> 0.0.Z     (every patch is its own series)
 async fn fetch_semver_series(
-    source: &(dyn LoaderPublisher + Sync),
+    source: &dyn LoaderPublisher,
     package: &PackageRef,
     version: &Version,
 ) -> Result<Vec<VersionInfo>, Error> {
@@ -382,7 +502,7 @@ async fn fetch_semver_series(
 }
 
 async fn fetch_and_resolve_package(
-    source: &(dyn LoaderPublisher + Sync),
+    source: &dyn LoaderPublisher,
     package: &PackageRef,
     version: Version,
 ) -> Result<decoded_component::DecodedComponent, Error> {
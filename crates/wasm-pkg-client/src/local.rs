@@ -1,6 +1,8 @@
 //! Local filesystem-based package backend.
 //!
 //! Each package release is a file: `<root-dir>/<namespace>/<name>/<version>.wasm`
+//!
+//! Yanked releases are marked with an empty sidecar file: `<root-dir>/<namespace>/<name>/<version>.yanked`
 
 use std::{
     io,
@@ -82,6 +84,10 @@ impl LocalBackend {
     fn version_path(&self, package: &PackageRef, version: &Version) -> PathBuf {
         self.package_dir(package).join(format!("{version}.wasm"))
     }
+
+    fn yanked_path(&self, package: &PackageRef, version: &Version) -> PathBuf {
+        self.package_dir(package).join(format!("{version}.yanked"))
+    }
 }
 
 #[async_trait]
@@ -112,10 +118,10 @@ impl PackageLoader for LocalBackend {
                 tracing::warn!("invalid package file name at {path:?}");
                 continue;
             };
-            versions.push(VersionInfo {
-                version,
-                yanked: false,
-            });
+            let yanked = tokio::fs::try_exists(path.with_extension("yanked"))
+                .await
+                .map_err(|e| registry_path_context(e, &path))?;
+            versions.push(VersionInfo { version, yanked });
         }
         Ok(versions)
     }
@@ -123,9 +129,13 @@ impl PackageLoader for LocalBackend {
     async fn get_release(&self, package: &PackageRef, version: &Version) -> Result<Release, Error> {
         let path = self.version_path(package, version);
         tracing::debug!(path = %path.display(), "Reading content from path");
-        let content_digest = sha256_from_file(&path)
-            .await
-            .map_err(|e| registry_path_context(e, &path))?;
+        let content_digest = sha256_from_file(&path).await.map_err(|e| {
+            if e.kind() == io::ErrorKind::NotFound {
+                Error::VersionNotFound(version.clone())
+            } else {
+                registry_path_context(e, &path)
+            }
+        })?;
         Ok(Release {
             version: version.clone(),
             content_digest,
@@ -173,9 +183,36 @@ impl PackagePublisher for LocalBackend {
             .map_err(Error::IoError)
             .map(|_| ())
     }
+
+    async fn set_yanked(
+        &self,
+        package: &PackageRef,
+        version: &Version,
+        yanked: bool,
+    ) -> Result<(), Error> {
+        let path = self.version_path(package, version);
+        if !tokio::fs::try_exists(&path)
+            .await
+            .map_err(|e| registry_path_context(e, &path))?
+        {
+            return Err(Error::VersionNotFound(version.clone()));
+        }
+        let marker = self.yanked_path(package, version);
+        let res = if yanked {
+            tokio::fs::write(&marker, b"").await
+        } else {
+            match tokio::fs::remove_file(&marker).await {
+                Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+                res => res,
+            }
+        };
+        res.map_err(|e| registry_path_context(e, &marker))
+    }
 }
 
-async fn sha256_from_file(path: impl AsRef<Path>) -> Result<ContentDigest, std::io::Error> {
+pub(crate) async fn sha256_from_file(
+    path: impl AsRef<Path>,
+) -> Result<ContentDigest, std::io::Error> {
     use tokio::io::AsyncReadExt;
     let mut file = tokio::fs::File::open(path).await?;
     let mut hasher = Sha256::new();
@@ -110,6 +110,32 @@ impl From<OciRegistryConfig> for OciRegistryConfigToml {
     }
 }
 
+/// The TLS options of the `oci` backend config, which the other HTTP based backends accept in their
+/// config as well.
+#[derive(Default, Deserialize)]
+pub(crate) struct TlsConfigToml {
+    #[serde(default)]
+    accept_invalid_certificates: bool,
+    #[serde(default)]
+    extra_root_certificates: Vec<TomlCertificate>,
+}
+
+impl TlsConfigToml {
+    /// Returns a [`ClientConfig`] with these TLS options.
+    pub(crate) fn into_client_config(self) -> Result<ClientConfig, Error> {
+        Ok(ClientConfig {
+            accept_invalid_certificates: self.accept_invalid_certificates,
+            extra_root_certificates: self
+                .extra_root_certificates
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(Error::InvalidConfig)?,
+            ..Default::default()
+        })
+    }
+}
+
 #[derive(Deserialize, Serialize)]
 #[serde(untagged)]
 #[serde(deny_unknown_fields)]
@@ -0,0 +1,419 @@
+//! OCI image layout package backend.
+//!
+//! Each package is stored in its own [OCI image layout][1] directory:
+//! `<root-dir>/<namespace>/<name>/` with an `oci-layout` file, an `index.json` and the blobs in
+//! `blobs/sha256/<hex>`. Releases are tagged with their version through the
+//! `org.opencontainers.image.ref.name` annotation of their `index.json` entry, and use the same
+//! manifests as [`OciBackend`](super::OciBackend) pushes to registries, so a release can be copied
+//! to and from a registry with tools like `oras` or `skopeo`, e.g.
+//! `oras cp --from-oci-layout <root-dir>/wasi/http:0.2.1 ghcr.io/example/wasi/http:0.2.1`.
+//!
+//! Files are written atomically, and the `index.json` is only updated while holding a lock on an
+//! `index.json.lock` file next to it, so several processes can publish to the same layout.
+//!
+//! [1]: https://github.com/opencontainers/image-spec/blob/main/image-layout.md
+
+use std::{
+    collections::BTreeSet,
+    io,
+    path::{Path, PathBuf},
+};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use futures_util::{StreamExt, TryStreamExt};
+use oci_client::{
+    annotations::ORG_OPENCONTAINERS_IMAGE_REF_NAME,
+    manifest::{
+        ImageIndexEntry, OCI_IMAGE_INDEX_MEDIA_TYPE, OCI_IMAGE_MEDIA_TYPE, OciImageIndex,
+        OciImageManifest,
+    },
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::io::AsyncReadExt;
+use tokio_util::io::ReaderStream;
+use wasm_pkg_common::{
+    Error,
+    config::RegistryConfig,
+    digest::ContentDigest,
+    metadata::OCI_LAYOUT_PROTOCOL,
+    package::{PackageRef, Version},
+};
+
+use crate::{
+    ContentStream, PublishingSource,
+    loader::PackageLoader,
+    publisher::PackagePublisher,
+    release::{Release, VersionInfo},
+};
+
+use super::{
+    YANKED_ANNOTATION, YANKED_TAG,
+    publisher::{wasm_artifact, yanked_manifest},
+};
+
+/// Content of the `oci-layout` file of every layout directory.
+const OCI_LAYOUT_FILE: &str = r#"{"imageLayoutVersion":"1.0.0"}"#;
+
+/// Name of the file that is locked while the `index.json` of a layout directory is updated.
+const INDEX_LOCK_FILE: &str = "index.json.lock";
+
+/// Config of the `oci-layout` backend.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct OciLayoutConfig {
+    /// The directory containing the OCI image layout directories of the packages.
+    pub root: PathBuf,
+}
+
+pub(crate) struct OciLayoutBackend {
+    root: PathBuf,
+}
+
+fn layout_path_context(err: io::Error, path: &Path) -> Error {
+    let err = anyhow::Error::new(err).context(format!("path: {}", path.display()));
+    Error::RegistryError(err)
+}
+
+impl OciLayoutBackend {
+    pub(crate) fn new(registry_config: &RegistryConfig) -> Result<Self, Error> {
+        let config = registry_config
+            .backend_config::<OciLayoutConfig>(OCI_LAYOUT_PROTOCOL)?
+            .ok_or_else(|| {
+                Error::InvalidConfig(anyhow!("'oci-layout' backend requires configuration"))
+            })?;
+        Ok(Self { root: config.root })
+    }
+
+    fn layout_dir(&self, package: &PackageRef) -> PathBuf {
+        self.root
+            .join(package.namespace().as_ref())
+            .join(package.name().as_ref())
+    }
+
+    fn blob_path(layout_dir: &Path, digest: &ContentDigest) -> PathBuf {
+        match digest {
+            ContentDigest::Sha256 { hex } => layout_dir.join("blobs").join("sha256").join(hex),
+        }
+    }
+
+    /// Reads the `index.json` of the package. Returns `None` if the package has no layout.
+    async fn read_index(&self, package: &PackageRef) -> Result<Option<OciImageIndex>, Error> {
+        let path = self.layout_dir(package).join("index.json");
+        let data = match tokio::fs::read(&path).await {
+            Ok(data) => data,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(layout_path_context(e, &path)),
+        };
+        serde_json::from_slice(&data)
+            .map(Some)
+            .map_err(|e| layout_path_context(e.into(), &path))
+    }
+
+    /// Returns the `index.json` entry with the given tag.
+    async fn find_tag(
+        &self,
+        package: &PackageRef,
+        tag: &str,
+    ) -> Result<Option<ImageIndexEntry>, Error> {
+        let Some(index) = self.read_index(package).await? else {
+            return Ok(None);
+        };
+        Ok(index
+            .manifests
+            .into_iter()
+            .find(|entry| entry_tag(entry) == Some(tag)))
+    }
+
+    async fn read_manifest(
+        &self,
+        package: &PackageRef,
+        entry: &ImageIndexEntry,
+    ) -> Result<OciImageManifest, Error> {
+        let digest: ContentDigest = entry.digest.parse()?;
+        let path = Self::blob_path(&self.layout_dir(package), &digest);
+        let data = tokio::fs::read(&path)
+            .await
+            .map_err(|e| layout_path_context(e, &path))?;
+        serde_json::from_slice(&data).map_err(|e| layout_path_context(e.into(), &path))
+    }
+
+    /// Returns the versions of the package recorded in its [`YANKED_TAG`] manifest, if any.
+    async fn yanked_versions(&self, package: &PackageRef) -> Result<BTreeSet<Version>, Error> {
+        let Some(entry) = self.find_tag(package, YANKED_TAG).await? else {
+            return Ok(BTreeSet::new());
+        };
+        let manifest = self.read_manifest(package, &entry).await?;
+        let Some(list) = manifest
+            .annotations
+            .as_ref()
+            .and_then(|annotations| annotations.get(YANKED_ANNOTATION))
+        else {
+            return Ok(BTreeSet::new());
+        };
+        list.split(',')
+            .filter(|ver| !ver.is_empty())
+            .map(|ver| Version::parse(ver).map_err(Into::into))
+            .collect()
+    }
+
+    /// Writes the given data as a blob of the package layout, returning its digest. Blobs are
+    /// written atomically so that readers never see partial blobs.
+    async fn write_blob(layout_dir: &Path, data: &[u8]) -> Result<ContentDigest, Error> {
+        let digest: ContentDigest = Sha256::new_with_prefix(data).into();
+        let path = Self::blob_path(layout_dir, &digest);
+        if tokio::fs::try_exists(&path)
+            .await
+            .map_err(|e| layout_path_context(e, &path))?
+        {
+            return Ok(digest);
+        }
+        let blobs_dir = path.parent().expect("blob paths have a parent");
+        tokio::fs::create_dir_all(blobs_dir)
+            .await
+            .map_err(|e| layout_path_context(e, blobs_dir))?;
+        write_atomic(&path, data).await?;
+        Ok(digest)
+    }
+
+    /// Writes the manifest with its layers and config, and tags it in the `index.json`, replacing
+    /// any manifest that had the tag before.
+    async fn write_tagged_manifest(
+        &self,
+        package: &PackageRef,
+        tag: &str,
+        manifest: &OciImageManifest,
+        blobs: &[&[u8]],
+    ) -> Result<(), Error> {
+        let layout_dir = self.layout_dir(package);
+        let (digest, size) = Self::write_manifest(&layout_dir, manifest, blobs).await?;
+        // Other processes may publish or yank releases of the package at the same time, so the
+        // index is read and replaced while holding the lock
+        let _lock = lock_layout(&layout_dir).await?;
+        self.tag_manifest(package, tag, manifest, &digest, size)
+            .await
+    }
+
+    /// Writes the manifest with its layers and config as blobs, returning the digest and size of
+    /// the manifest.
+    async fn write_manifest(
+        layout_dir: &Path,
+        manifest: &OciImageManifest,
+        blobs: &[&[u8]],
+    ) -> Result<(ContentDigest, usize), Error> {
+        for data in blobs {
+            Self::write_blob(layout_dir, data).await?;
+        }
+        let manifest_data =
+            serde_json::to_vec(manifest).map_err(|e| Error::RegistryError(e.into()))?;
+        let digest = Self::write_blob(layout_dir, &manifest_data).await?;
+        Ok((digest, manifest_data.len()))
+    }
+
+    /// Tags the written manifest in the `index.json`. The caller must hold the layout lock.
+    async fn tag_manifest(
+        &self,
+        package: &PackageRef,
+        tag: &str,
+        manifest: &OciImageManifest,
+        digest: &ContentDigest,
+        size: usize,
+    ) -> Result<(), Error> {
+        let layout_dir = self.layout_dir(package);
+        let mut index = self
+            .read_index(package)
+            .await?
+            .unwrap_or_else(|| OciImageIndex {
+                schema_version: 2,
+                media_type: Some(OCI_IMAGE_INDEX_MEDIA_TYPE.to_string()),
+                manifests: Vec::new(),
+                artifact_type: None,
+                annotations: None,
+            });
+        index
+            .manifests
+            .retain(|entry| entry_tag(entry) != Some(tag));
+        index.manifests.push(ImageIndexEntry {
+            media_type: manifest
+                .media_type
+                .clone()
+                .unwrap_or_else(|| OCI_IMAGE_MEDIA_TYPE.to_string()),
+            digest: digest.to_string(),
+            size: size as i64,
+            platform: None,
+            annotations: Some(
+                [(
+                    ORG_OPENCONTAINERS_IMAGE_REF_NAME.to_string(),
+                    tag.to_string(),
+                )]
+                .into(),
+            ),
+            artifact_type: manifest.artifact_type.clone(),
+        });
+
+        write_atomic(&layout_dir.join("oci-layout"), OCI_LAYOUT_FILE.as_bytes()).await?;
+        let index_data = serde_json::to_vec(&index).map_err(|e| Error::RegistryError(e.into()))?;
+        write_atomic(&layout_dir.join("index.json"), &index_data).await
+    }
+}
+
+/// Writes the data to a temporary file next to the given path and moves it into place, so that
+/// readers either see the old or the new content.
+async fn write_atomic(path: &Path, data: &[u8]) -> Result<(), Error> {
+    let dir = path.parent().expect("layout paths have a parent");
+    let temp = tempfile::NamedTempFile::new_in(dir).map_err(|e| layout_path_context(e, dir))?;
+    tokio::fs::write(temp.path(), data)
+        .await
+        .map_err(|e| layout_path_context(e, temp.path()))?;
+    temp.persist(path)
+        .map_err(|e| layout_path_context(e.error, path))?;
+    Ok(())
+}
+
+/// Acquires an exclusive lock on the `index.json.lock` file of the layout directory, which is
+/// released when the returned file is dropped.
+async fn lock_layout(layout_dir: &Path) -> Result<std::fs::File, Error> {
+    let path = layout_dir.join(INDEX_LOCK_FILE);
+    tokio::task::spawn_blocking(move || {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)
+            .map_err(|e| layout_path_context(e, &path))?;
+        file.lock().map_err(|e| layout_path_context(e, &path))?;
+        Ok(file)
+    })
+    .await
+    .map_err(|e| Error::RegistryError(e.into()))?
+}
+
+/// Returns the tag of the `index.json` entry.
+fn entry_tag(entry: &ImageIndexEntry) -> Option<&str> {
+    entry
+        .annotations
+        .as_ref()?
+        .get(ORG_OPENCONTAINERS_IMAGE_REF_NAME)
+        .map(String::as_str)
+}
+
+#[async_trait]
+impl PackageLoader for OciLayoutBackend {
+    async fn list_all_versions(&self, package: &PackageRef) -> Result<Vec<VersionInfo>, Error> {
+        let Some(index) = self.read_index(package).await? else {
+            return Err(Error::PackageNotFound);
+        };
+        let yanked = self.yanked_versions(package).await?;
+        Ok(index
+            .manifests
+            .iter()
+            .filter_map(entry_tag)
+            .filter(|tag| *tag != YANKED_TAG)
+            .filter_map(|tag| match Version::parse(tag) {
+                Ok(version) => Some(VersionInfo {
+                    yanked: yanked.contains(&version),
+                    version,
+                }),
+                Err(err) => {
+                    tracing::warn!(?tag, error = ?err, "Ignoring invalid version tag");
+                    None
+                }
+            })
+            .collect())
+    }
+
+    async fn get_release(&self, package: &PackageRef, version: &Version) -> Result<Release, Error> {
+        let entry = self
+            .find_tag(package, &version.to_string())
+            .await?
+            .ok_or_else(|| Error::VersionNotFound(version.clone()))?;
+        let manifest = self.read_manifest(package, &entry).await?;
+        let content_digest = manifest
+            .layers
+            .into_iter()
+            .next()
+            .ok_or_else(|| Error::InvalidPackageManifest("Manifest had no layers".to_string()))?
+            .digest
+            .parse()?;
+        Ok(Release {
+            version: version.clone(),
+            content_digest,
+        })
+    }
+
+    async fn stream_content_unvalidated(
+        &self,
+        package: &PackageRef,
+        release: &Release,
+    ) -> Result<ContentStream, Error> {
+        let path = Self::blob_path(&self.layout_dir(package), &release.content_digest);
+        tracing::debug!("Streaming content from {path:?}");
+        let file = tokio::fs::File::open(&path)
+            .await
+            .map_err(|e| layout_path_context(e, &path))?;
+        Ok(ReaderStream::new(file).map_err(Into::into).boxed())
+    }
+}
+
+#[async_trait]
+impl PackagePublisher for OciLayoutBackend {
+    async fn publish(
+        &self,
+        package: &PackageRef,
+        version: &Version,
+        mut data: PublishingSource,
+        dry_run: bool,
+    ) -> Result<(), Error> {
+        let mut buf = Vec::new();
+        data.read_to_end(&mut buf).await?;
+        let (manifest, layers, config) = wasm_artifact(package, version, buf)?;
+        if dry_run {
+            return Ok(());
+        }
+        tracing::info!(
+            "publishing to {}:{version}",
+            self.layout_dir(package).display()
+        );
+        let blobs: Vec<&[u8]> = layers
+            .iter()
+            .map(|layer| layer.data.as_ref())
+            .chain([config.data.as_ref()])
+            .collect();
+        self.write_tagged_manifest(package, &version.to_string(), &manifest, &blobs)
+            .await
+    }
+
+    async fn set_yanked(
+        &self,
+        package: &PackageRef,
+        version: &Version,
+        yanked: bool,
+    ) -> Result<(), Error> {
+        // Make sure we never record a version that doesn't exist
+        if self
+            .find_tag(package, &version.to_string())
+            .await?
+            .is_none()
+        {
+            return Err(Error::VersionNotFound(version.clone()));
+        }
+        // The yanked list is read and replaced while holding the lock, so that concurrent yanks
+        // don't drop each other's versions
+        let layout_dir = self.layout_dir(package);
+        let _lock = lock_layout(&layout_dir).await?;
+        let mut versions = self.yanked_versions(package).await?;
+        let changed = if yanked {
+            versions.insert(version.clone())
+        } else {
+            versions.remove(version)
+        };
+        if !changed {
+            return Ok(());
+        }
+        let (manifest, config) = yanked_manifest(&versions);
+        let (digest, size) =
+            Self::write_manifest(&layout_dir, &manifest, &[config.data.as_ref()]).await?;
+        self.tag_manifest(package, YANKED_TAG, &manifest, &digest, size)
+            .await
+    }
+}
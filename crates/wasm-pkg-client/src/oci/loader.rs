@@ -1,6 +1,20 @@
+use std::collections::HashSet;
+
+use anyhow::{Context, anyhow};
 use async_trait::async_trait;
 use futures_util::{StreamExt, TryStreamExt};
-use oci_client::{RegistryOperation, manifest::OciDescriptor};
+use oci_client::{
+    Reference, RegistryOperation,
+    errors::{OciDistributionError, OciEnvelope},
+    manifest::OciDescriptor,
+    secrets::RegistryAuth,
+};
+use reqwest::{
+    StatusCode,
+    header::{HeaderMap, LINK},
+};
+use serde::Deserialize;
+use url::Url;
 use wasm_pkg_common::{
     Error,
     package::{PackageRef, Version},
@@ -12,7 +26,153 @@ use crate::{
     release::{Release, VersionInfo},
 };
 
-use super::{OciBackend, oci_registry_error};
+use super::{OciBackend, YANKED_TAG, oci_registry_error};
+
+impl OciBackend {
+    /// Lists every tag of the given repository, following the `Link` header of each response until
+    /// the registry stops returning one. Registries commonly cap the number of tags returned per
+    /// request, so a single request may only return the first page. Links to another origin are
+    /// refused, as the requests carry the credentials of the registry.
+    async fn list_all_tags(
+        &self,
+        reference: &Reference,
+        auth: &RegistryAuth,
+    ) -> Result<Vec<String>, Error> {
+        // NOTE: oci-client doesn't expose the headers of the tag listing response, so the pages are
+        // requested directly, reusing the token oci-client negotiated for the repository
+        let token = self
+            .client
+            .auth(reference, auth, RegistryOperation::Pull)
+            .await
+            .map_err(oci_registry_error)?;
+        let mut url = Url::parse(&format!(
+            "{}://{}/v2/{}/tags/list",
+            self.scheme(),
+            reference.resolve_registry(),
+            reference.repository()
+        ))
+        .map_err(|err| Error::RegistryError(err.into()))?;
+
+        let mut tags = Vec::new();
+        let mut seen = HashSet::new();
+        loop {
+            let request = self.http.get(url.clone());
+            let request = match (&token, auth) {
+                (Some(token), _) => request.bearer_auth(token),
+                (None, RegistryAuth::Basic(username, password)) => {
+                    request.basic_auth(username, Some(password))
+                }
+                _ => request,
+            };
+            let resp = request
+                .send()
+                .await
+                .map_err(|err| Error::RegistryError(err.into()))?;
+            let status = resp.status();
+            let next = next_link(resp.headers());
+            let body = resp
+                .bytes()
+                .await
+                .map_err(|err| Error::RegistryError(err.into()))?;
+            if !status.is_success() {
+                return Err(tag_list_error(status, &body, &url));
+            }
+            let page: TagList = serde_json::from_slice(&body)
+                .with_context(|| format!("invalid tag list returned by {url}"))
+                .map_err(Error::RegistryError)?;
+            tracing::trace!(tags = ?page.tags, ?next, "List tags response");
+
+            let Some(next) = next else {
+                tags.extend(page.tags.into_iter().filter(|tag| seen.insert(tag.clone())));
+                break;
+            };
+            // A registry that ignores the continuation would otherwise keep returning the same
+            // page, so refuse to return a partial list instead of stopping early
+            let before = tags.len();
+            tags.extend(page.tags.into_iter().filter(|tag| seen.insert(tag.clone())));
+            if tags.len() == before {
+                return Err(Error::RegistryError(anyhow!(
+                    "registry returned a page of tags for {} without any new tags, refusing to \
+                     return a partial list",
+                    reference.repository()
+                )));
+            }
+            let next_url = url
+                .join(&next)
+                .with_context(|| format!("invalid Link header {next:?} returned by {url}"))
+                .map_err(Error::RegistryError)?;
+            // The credentials of the registry are sent along, so never follow it anywhere else
+            if next_url.origin() != url.origin() {
+                return Err(Error::RegistryError(anyhow!(
+                    "registry returned a Link header to another origin: {next_url}"
+                )));
+            }
+            url = next_url;
+        }
+        Ok(tags)
+    }
+}
+
+#[derive(Deserialize)]
+struct TagList {
+    #[serde(default, deserialize_with = "null_as_empty")]
+    tags: Vec<String>,
+}
+
+/// Some registries return `"tags": null` for repositories without tags
+fn null_as_empty<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<String>, D::Error> {
+    Ok(Option::deserialize(deserializer)?.unwrap_or_default())
+}
+
+/// Returns the target of the `rel="next"` entry of a `Link` header, if any.
+fn next_link(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get_all(LINK)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .find_map(|link| {
+            let mut parts = link.split(';');
+            let target = parts.next()?.trim().strip_prefix('<')?.strip_suffix('>')?;
+            parts
+                .any(|param| {
+                    matches!(
+                        param.trim().split_once('='),
+                        Some(("rel", rel)) if rel.trim_matches('"') == "next"
+                    )
+                })
+                .then(|| target.to_string())
+        })
+}
+
+/// Converts an unsuccessful tag listing response to an error the same way oci-client does, so a
+/// missing repository is still reported as [`Error::PackageNotFound`].
+fn tag_list_error(status: StatusCode, body: &[u8], url: &Url) -> Error {
+    let err = match status {
+        StatusCode::UNAUTHORIZED => OciDistributionError::UnauthorizedError {
+            url: url.to_string(),
+        },
+        s if s.is_client_error() => match serde_json::from_slice::<OciEnvelope>(body) {
+            Ok(envelope) => OciDistributionError::RegistryError {
+                envelope,
+                url: url.to_string(),
+            },
+            Err(_) => OciDistributionError::ServerError {
+                code: s.as_u16(),
+                url: url.to_string(),
+                message: String::from_utf8_lossy(body).to_string(),
+            },
+        },
+        s => OciDistributionError::ServerError {
+            code: s.as_u16(),
+            url: url.to_string(),
+            message: String::from_utf8_lossy(body).to_string(),
+        },
+    };
+    oci_registry_error(err)
+}
 
 #[async_trait]
 impl PackageLoader for OciBackend {
@@ -21,22 +181,23 @@ impl PackageLoader for OciBackend {
 
         tracing::debug!(?reference, "Listing tags for OCI reference");
         let auth = self.auth(&reference, RegistryOperation::Pull).await?;
-        let resp = self
-            .client
-            .list_tags(&reference, &auth, None, None)
-            .await
-            .map_err(oci_registry_error)?;
-        tracing::trace!(response = ?resp, "List tags response");
+        let tags = self.list_all_tags(&reference, &auth).await?;
+
+        let yanked = if tags.iter().any(|tag| tag == YANKED_TAG) {
+            self.yanked_versions(package, &auth).await?
+        } else {
+            Default::default()
+        };
 
         // Return only tags that parse as valid semver versions.
-        let versions = resp
-            .tags
+        let versions = tags
             .iter()
             .flat_map(|tag| match Version::parse(tag) {
                 Ok(version) => Some(VersionInfo {
+                    yanked: yanked.contains(&version),
                     version,
-                    yanked: false,
                 }),
+                Err(_) if tag == YANKED_TAG => None,
                 Err(err) => {
                     // Signature tags all start with a SHA and shouldn't generate a warning
                     if tag.starts_with("sha256-") {
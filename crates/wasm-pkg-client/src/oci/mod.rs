@@ -5,12 +5,19 @@
 //! [1]: https://tag-runtime.cncf.io/wgs/wasm/deliverables/wasm-oci-artifact/
 
 mod config;
+mod layout;
 mod loader;
 mod publisher;
 
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::{Arc, Mutex},
+};
+
 use docker_credential::{CredentialRetrievalError, DockerCredential};
 use oci_client::{
     Reference, RegistryOperation,
+    client::{ClientConfig, ClientProtocol},
     errors::{OciDistributionError, OciError, OciErrorCode},
     secrets::RegistryAuth,
 };
@@ -28,7 +35,20 @@ use wasm_pkg_common::{
 /// Re-exported for convenience.
 pub use oci_client::client;
 
+pub(crate) use config::TlsConfigToml;
 pub use config::{BasicCredentials, OciRegistryConfig};
+pub(crate) use layout::OciLayoutBackend;
+pub use layout::OciLayoutConfig;
+
+/// Tag of the companion manifest that records which versions of a package are yanked.
+const YANKED_TAG: &str = "yanked";
+/// Annotation on the [`YANKED_TAG`] manifest holding a comma-separated list of yanked versions.
+const YANKED_ANNOTATION: &str = "dev.wasm-pkg.yanked";
+/// Artifact type of the [`YANKED_TAG`] manifest.
+const YANKED_ARTIFACT_TYPE: &str = "application/vnd.wasm-pkg.yanked.v1";
+/// How many times an update of the [`YANKED_TAG`] manifest is retried when it races with another
+/// update.
+const YANK_ATTEMPTS: usize = 3;
 
 #[derive(Default, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -37,12 +57,22 @@ struct OciRegistryMetadata {
     namespace_prefix: Option<String>,
 }
 
+/// Preflighted auth, keyed by repository and operation since registry tokens are usually scoped to
+/// both.
+type AuthCache = BTreeMap<(String, RegistryOperation), Arc<OnceCell<RegistryAuth>>>;
+
 pub(crate) struct OciBackend {
     client: oci_wasm::WasmClient,
+    /// Plain HTTP client for requests whose response headers oci-client doesn't expose, set up
+    /// with the same TLS settings as `client`
+    http: reqwest::Client,
+    protocol: ClientProtocol,
     oci_registry: String,
     namespace_prefix: Option<String>,
     credentials: Option<BasicCredentials>,
-    registry_auth: OnceCell<RegistryAuth>,
+    registry_auth: Mutex<AuthCache>,
+    /// Serializes updates of [`YANKED_TAG`] manifests made through this backend
+    yank_lock: tokio::sync::Mutex<()>,
 }
 
 impl OciBackend {
@@ -55,6 +85,8 @@ impl OciBackend {
             client_config,
             credentials,
         } = registry_config.try_into()?;
+        let http = http_client(&client_config)?;
+        let protocol = client_config.protocol.clone();
         let client = oci_client::Client::new(client_config);
         let client = oci_wasm::WasmClient::new(client);
 
@@ -65,19 +97,47 @@ impl OciBackend {
 
         Ok(Self {
             client,
+            http,
+            protocol,
             oci_registry,
             namespace_prefix: oci_meta.namespace_prefix,
             credentials,
-            registry_auth: OnceCell::new(),
+            registry_auth: Default::default(),
+            yank_lock: Default::default(),
         })
     }
 
+    /// Returns the URL scheme used to talk to the registry.
+    fn scheme(&self) -> &'static str {
+        match &self.protocol {
+            ClientProtocol::Http => "http",
+            ClientProtocol::HttpsExcept(exceptions) if exceptions.contains(&self.oci_registry) => {
+                "http"
+            }
+            _ => "https",
+        }
+    }
+
+    /// Returns the auth cache entry for the repository of the given reference and operation.
+    fn auth_cell(
+        &self,
+        reference: &Reference,
+        operation: RegistryOperation,
+    ) -> Arc<OnceCell<RegistryAuth>> {
+        self.registry_auth
+            .lock()
+            .expect("auth cache lock poisoned")
+            .entry((reference.repository().to_owned(), operation))
+            .or_default()
+            .clone()
+    }
+
     pub(crate) async fn auth(
         &self,
         reference: &Reference,
         operation: RegistryOperation,
     ) -> Result<RegistryAuth, Error> {
-        self.registry_auth
+        self.auth_cell(reference, operation)
             .get_or_try_init(|| async {
                 let mut auth = self.get_credentials()?;
                 // Preflight auth to check for validity; this isn't wasted
@@ -148,6 +208,17 @@ impl OciBackend {
         &self,
         package: &PackageRef,
         version: Option<&Version>,
+    ) -> Reference {
+        let tag = version
+            .map(|ver| ver.to_string())
+            .unwrap_or_else(|| "latest".into());
+        self.make_tagged_reference(package, tag)
+    }
+
+    pub(crate) fn make_tagged_reference(
+        &self,
+        package: &PackageRef,
+        tag: impl Into<String>,
     ) -> Reference {
         let repository = format!(
             "{}{}/{}",
@@ -155,13 +226,113 @@ impl OciBackend {
             package.namespace(),
             package.name()
         );
-        let tag = version
-            .map(|ver| ver.to_string())
-            .unwrap_or_else(|| "latest".into());
-        Reference::with_tag(self.oci_registry.clone(), repository, tag)
+        Reference::with_tag(self.oci_registry.clone(), repository, tag.into())
+    }
+
+    /// Returns the versions of the package recorded in its [`YANKED_TAG`] manifest, if any.
+    pub(crate) async fn yanked_versions(
+        &self,
+        package: &PackageRef,
+        auth: &RegistryAuth,
+    ) -> Result<BTreeSet<Version>, Error> {
+        self.yanked_state(package, auth)
+            .await
+            .map(|(versions, _)| versions)
+    }
+
+    /// Returns the versions of the package recorded in its [`YANKED_TAG`] manifest along with the
+    /// digest of the manifest, if it exists.
+    pub(crate) async fn yanked_state(
+        &self,
+        package: &PackageRef,
+        auth: &RegistryAuth,
+    ) -> Result<(BTreeSet<Version>, Option<String>), Error> {
+        let reference = self.make_tagged_reference(package, YANKED_TAG);
+        let (manifest, digest) = match self.client.pull_image_manifest(&reference, auth).await {
+            Ok(pulled) => pulled,
+            Err(OciDistributionError::ImageManifestNotFoundError(_)) => {
+                return Ok((BTreeSet::new(), None));
+            }
+            Err(err) => return Err(oci_registry_error(err)),
+        };
+        let Some(list) = manifest
+            .annotations
+            .as_ref()
+            .and_then(|annotations| annotations.get(YANKED_ANNOTATION))
+        else {
+            return Ok((BTreeSet::new(), Some(digest)));
+        };
+        let versions = list
+            .split(',')
+            .filter(|ver| !ver.is_empty())
+            .map(|ver| Version::parse(ver).map_err(Into::into))
+            .collect::<Result<_, Error>>()?;
+        Ok((versions, Some(digest)))
+    }
+
+    /// Returns the digest of the package's [`YANKED_TAG`] manifest, if it exists.
+    async fn yanked_digest(
+        &self,
+        package: &PackageRef,
+        auth: &RegistryAuth,
+    ) -> Result<Option<String>, Error> {
+        let reference = self.make_tagged_reference(package, YANKED_TAG);
+        match self.client.fetch_manifest_digest(&reference, auth).await {
+            Ok(digest) => Ok(Some(digest)),
+            Err(OciDistributionError::ImageManifestNotFoundError(_)) => Ok(None),
+            Err(err) => match oci_registry_error(err) {
+                Error::PackageNotFound => Ok(None),
+                err => Err(err),
+            },
+        }
     }
 }
 
+/// Builds an HTTP client with the same settings oci-client uses for its own client, for the
+/// requests oci-client doesn't make itself.
+pub(crate) fn http_client(config: &ClientConfig) -> Result<reqwest::Client, Error> {
+    let certificates = |certs: &[oci_client::client::Certificate]| {
+        certs
+            .iter()
+            .map(|cert| {
+                reqwest::Certificate::try_from(cert).map_err(|err| Error::InvalidConfig(err.into()))
+            })
+            .collect::<Result<Vec<_>, _>>()
+    };
+    let mut builder = reqwest::Client::builder()
+        .danger_accept_invalid_certs(config.accept_invalid_certificates)
+        .user_agent(config.user_agent);
+    if !config.tls_certs_only.is_empty() {
+        builder = builder.tls_certs_only(certificates(&config.tls_certs_only)?);
+    }
+    for cert in certificates(&config.extra_root_certificates)? {
+        builder = builder.add_root_certificate(cert);
+    }
+    if let Some(timeout) = config.read_timeout {
+        builder = builder.read_timeout(timeout);
+    }
+    if let Some(timeout) = config.connect_timeout {
+        builder = builder.connect_timeout(timeout);
+    }
+    let no_proxy = || {
+        config
+            .no_proxy
+            .as_deref()
+            .and_then(reqwest::NoProxy::from_string)
+    };
+    if let Some(proxy) = &config.https_proxy {
+        let proxy = reqwest::Proxy::https(proxy).map_err(|err| Error::InvalidConfig(err.into()))?;
+        builder = builder.proxy(proxy.no_proxy(no_proxy()));
+    }
+    if let Some(proxy) = &config.http_proxy {
+        let proxy = reqwest::Proxy::http(proxy).map_err(|err| Error::InvalidConfig(err.into()))?;
+        builder = builder.proxy(proxy.no_proxy(no_proxy()));
+    }
+    builder
+        .build()
+        .map_err(|err| Error::InvalidConfig(err.into()))
+}
+
 pub(crate) fn oci_registry_error(err: OciDistributionError) -> Error {
     match err {
         // Technically this could be a missing version too, but there really isn't a way to find out
@@ -239,6 +410,38 @@ fn get_docker_credential(registry: &str) -> Result<Option<RegistryAuth>, Error>
 mod tests {
     use super::*;
 
+    #[test]
+    fn auth_is_cached_per_repository_and_operation() {
+        let registry: Registry = "example.com".parse().unwrap();
+        let backend = OciBackend::new(
+            &registry,
+            &RegistryConfig::default(),
+            &RegistryMetadata::default(),
+        )
+        .unwrap();
+        let foo = backend.make_reference(&"ns:foo".parse().unwrap(), None);
+        let foo_versioned =
+            backend.make_reference(&"ns:foo".parse().unwrap(), Some(&"1.0.0".parse().unwrap()));
+        let bar = backend.make_reference(&"ns:bar".parse().unwrap(), None);
+
+        let foo_pull = backend.auth_cell(&foo, RegistryOperation::Pull);
+        assert!(
+            Arc::ptr_eq(
+                &foo_pull,
+                &backend.auth_cell(&foo_versioned, RegistryOperation::Pull)
+            ),
+            "tags of the same repository should share auth"
+        );
+        assert!(!Arc::ptr_eq(
+            &foo_pull,
+            &backend.auth_cell(&foo, RegistryOperation::Push)
+        ));
+        assert!(!Arc::ptr_eq(
+            &foo_pull,
+            &backend.auth_cell(&bar, RegistryOperation::Pull)
+        ));
+    }
+
     #[test]
     fn auth_env_var_sanitizes_registry_name() {
         assert_eq!(
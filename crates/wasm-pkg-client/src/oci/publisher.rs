@@ -1,4 +1,4 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 use oci_client::{
     Reference, RegistryOperation,
@@ -8,13 +8,101 @@ use oci_client::{
         ORG_OPENCONTAINERS_IMAGE_TITLE, ORG_OPENCONTAINERS_IMAGE_URL,
         ORG_OPENCONTAINERS_IMAGE_VERSION,
     },
+    client::{Config, ImageLayer},
+    manifest::OciImageManifest,
 };
+use oci_wasm::ToConfig;
 use tokio::io::AsyncReadExt;
 
 use crate::publisher::PackagePublisher;
 use crate::{PackageRef, PublishingSource, Version};
 
-use super::OciBackend;
+use super::{
+    OciBackend, YANK_ATTEMPTS, YANKED_ANNOTATION, YANKED_ARTIFACT_TYPE, YANKED_TAG,
+    oci_registry_error,
+};
+
+/// Media type of the empty JSON descriptor (`{}`) used as the config of artifact manifests.
+const EMPTY_CONFIG_MEDIA_TYPE: &str = "application/vnd.oci.empty.v1+json";
+
+/// Builds the manifest, layers and config of the Wasm OCI artifact for the given component, with
+/// the same annotations for every OCI backend.
+pub(crate) fn wasm_artifact(
+    package: &PackageRef,
+    version: &Version,
+    buf: Vec<u8>,
+) -> Result<(OciImageManifest, Vec<ImageLayer>, Config), crate::Error> {
+    let payload = wasm_metadata::Payload::from_binary(&buf).map_err(|e| {
+        crate::Error::InvalidComponent(anyhow::anyhow!("Unable to parse WASM: {e}"))
+    })?;
+    let meta = payload.metadata();
+    let (config, mut layer) = oci_wasm::WasmConfig::from_raw_component(buf, None)
+        .map_err(crate::Error::InvalidComponent)?;
+    // Set the layer title so OCI tools can name the file on disk
+    layer.annotations = Some(BTreeMap::from_iter([(
+        ORG_OPENCONTAINERS_IMAGE_TITLE.to_string(),
+        format!("{}.wasm", package.name()),
+    )]));
+    let mut annotations = BTreeMap::from_iter([(
+        ORG_OPENCONTAINERS_IMAGE_VERSION.to_string(),
+        version.to_string(),
+    )]);
+    if let Some(desc) = &meta.description {
+        annotations.insert(
+            ORG_OPENCONTAINERS_IMAGE_DESCRIPTION.to_string(),
+            desc.to_string(),
+        );
+    }
+    if let Some(licenses) = &meta.licenses {
+        annotations.insert(
+            ORG_OPENCONTAINERS_IMAGE_LICENSES.to_string(),
+            licenses.to_string(),
+        );
+    }
+    if let Some(source) = &meta.source {
+        annotations.insert(
+            ORG_OPENCONTAINERS_IMAGE_SOURCE.to_string(),
+            source.to_string(),
+        );
+    }
+    if let Some(homepage) = &meta.homepage {
+        annotations.insert(
+            ORG_OPENCONTAINERS_IMAGE_URL.to_string(),
+            homepage.to_string(),
+        );
+    }
+    if let Some(authors) = &meta.authors {
+        annotations.insert(
+            ORG_OPENCONTAINERS_IMAGE_AUTHORS.to_string(),
+            authors.to_string(),
+        );
+    }
+
+    let layers = vec![layer];
+    let config = config.to_config().map_err(crate::Error::InvalidComponent)?;
+    // The same manifest `oci_wasm::WasmClient::push` would create
+    let mut manifest = OciImageManifest::build(&layers, &config, Some(annotations));
+    manifest.media_type = Some(oci_wasm::WASM_MANIFEST_MEDIA_TYPE.to_string());
+    Ok((manifest, layers, config))
+}
+
+/// Builds the [`YANKED_TAG`] manifest recording the given yanked versions, along with its (empty)
+/// config.
+pub(crate) fn yanked_manifest(versions: &BTreeSet<Version>) -> (OciImageManifest, Config) {
+    let list = versions
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(",");
+    let config = Config::new(b"{}".to_vec(), EMPTY_CONFIG_MEDIA_TYPE.to_string(), None);
+    let mut manifest = OciImageManifest::build(
+        &[],
+        &config,
+        Some(BTreeMap::from_iter([(YANKED_ANNOTATION.to_string(), list)])),
+    );
+    manifest.artifact_type = Some(YANKED_ARTIFACT_TYPE.to_string());
+    (manifest, config)
+}
 
 #[async_trait::async_trait]
 impl PackagePublisher for OciBackend {
@@ -30,60 +118,77 @@ impl PackagePublisher for OciBackend {
         // to remove this and use the stream directly.
         let mut buf = Vec::new();
         data.read_to_end(&mut buf).await?;
-        let payload = wasm_metadata::Payload::from_binary(&buf).map_err(|e| {
-            crate::Error::InvalidComponent(anyhow::anyhow!("Unable to parse WASM: {e}"))
-        })?;
-        let meta = payload.metadata();
-        let (config, mut layer) = oci_wasm::WasmConfig::from_raw_component(buf, None)
-            .map_err(crate::Error::InvalidComponent)?;
-        // Set the layer title so OCI tools can name the file on disk
-        layer.annotations = Some(BTreeMap::from_iter([(
-            ORG_OPENCONTAINERS_IMAGE_TITLE.to_string(),
-            format!("{}.wasm", package.name()),
-        )]));
-        let mut annotations = BTreeMap::from_iter([(
-            ORG_OPENCONTAINERS_IMAGE_VERSION.to_string(),
-            version.to_string(),
-        )]);
-        if let Some(desc) = &meta.description {
-            annotations.insert(
-                ORG_OPENCONTAINERS_IMAGE_DESCRIPTION.to_string(),
-                desc.to_string(),
-            );
-        }
-        if let Some(licenses) = &meta.licenses {
-            annotations.insert(
-                ORG_OPENCONTAINERS_IMAGE_LICENSES.to_string(),
-                licenses.to_string(),
-            );
-        }
-        if let Some(source) = &meta.source {
-            annotations.insert(
-                ORG_OPENCONTAINERS_IMAGE_SOURCE.to_string(),
-                source.to_string(),
-            );
-        }
-        if let Some(homepage) = &meta.homepage {
-            annotations.insert(
-                ORG_OPENCONTAINERS_IMAGE_URL.to_string(),
-                homepage.to_string(),
-            );
-        }
-        if let Some(authors) = &meta.authors {
-            annotations.insert(
-                ORG_OPENCONTAINERS_IMAGE_AUTHORS.to_string(),
-                authors.to_string(),
-            );
-        }
+        let (manifest, layers, config) = wasm_artifact(package, version, buf)?;
 
         let reference: Reference = self.make_reference(package, Some(version));
         let auth = self.auth(&reference, RegistryOperation::Push).await?;
         if !dry_run {
-            self.client
-                .push(&reference, &auth, layer, config, Some(annotations))
-                .await
-                .map_err(crate::Error::RegistryError)?;
+            oci_client::Client::push(
+                &self.client,
+                &reference,
+                &layers,
+                config,
+                &auth,
+                Some(manifest),
+            )
+            .await
+            .map_err(|e| crate::Error::RegistryError(e.into()))?;
         }
         Ok(())
     }
+
+    async fn set_yanked(
+        &self,
+        package: &PackageRef,
+        version: &Version,
+        yanked: bool,
+    ) -> Result<(), crate::Error> {
+        let reference = self.make_reference(package, Some(version));
+        let auth = self.auth(&reference, RegistryOperation::Push).await?;
+        // Make sure we never record a version that doesn't exist
+        self.client
+            .fetch_manifest_digest(&reference, &auth)
+            .await
+            .map_err(|err| match oci_registry_error(err) {
+                crate::Error::PackageNotFound => crate::Error::VersionNotFound(version.clone()),
+                err => err,
+            })?;
+
+        // The yanked versions are stored as an annotation on a companion manifest so that
+        // existing release manifests (and their digests) are never modified. Registries can't
+        // update a tag conditionally, so updates of the manifest by other clients are detected by
+        // checking its digest right before pushing and its contents right after, and the update is
+        // retried if one got in between. This narrows the window for lost updates, but can't close it.
+        let _guard = self.yank_lock.lock().await;
+        let reference = self.make_tagged_reference(package, YANKED_TAG);
+        for _ in 0..YANK_ATTEMPTS {
+            let (mut versions, digest) = self.yanked_state(package, &auth).await?;
+            let changed = if yanked {
+                versions.insert(version.clone())
+            } else {
+                versions.remove(version)
+            };
+            if !changed {
+                return Ok(());
+            }
+            if self.yanked_digest(package, &auth).await? != digest {
+                tracing::debug!(%package, "yanked versions changed concurrently, retrying");
+                continue;
+            }
+
+            let (manifest, config) = yanked_manifest(&versions);
+            oci_client::Client::push(&self.client, &reference, &[], config, &auth, Some(manifest))
+                .await
+                .map_err(oci_registry_error)?;
+            let (versions, _) = self.yanked_state(package, &auth).await?;
+            if versions.contains(version) == yanked {
+                return Ok(());
+            }
+            // Another update replaced ours without being based on it
+            tracing::debug!(%package, "yanked versions were replaced concurrently, retrying");
+        }
+        Err(crate::Error::RegistryError(anyhow::anyhow!(
+            "the yanked versions of {package} kept changing concurrently, try again"
+        )))
+    }
 }
@@ -12,4 +12,20 @@ pub trait PackagePublisher: Send + Sync {
         data: PublishingSource,
         dry_run: bool,
     ) -> Result<(), crate::Error>;
+
+    /// Marks an existing release as yanked (or un-yanks it if `yanked` is false). Yanked releases
+    /// are still available for download, but are reported as
+    /// [`VersionInfo::yanked`](crate::VersionInfo::yanked) when listing versions.
+    ///
+    /// The default implementation returns an error for publishers that don't support yanking.
+    async fn set_yanked(
+        &self,
+        package: &PackageRef,
+        _version: &Version,
+        _yanked: bool,
+    ) -> Result<(), crate::Error> {
+        Err(crate::Error::RegistryError(anyhow::anyhow!(
+            "yanking releases of {package} is not supported by this registry"
+        )))
+    }
 }
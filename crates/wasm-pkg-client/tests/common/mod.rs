@@ -0,0 +1,89 @@
+// NOTE: test "lib" code needs to be under ./tests/common/mod.rs otherwise there will
+// be false clippy positives since ./tests/common.rs will be treated as a runnable test target
+// Not every test target uses every helper
+#![allow(dead_code)]
+use std::path::Path;
+
+use wasm_pkg_client::{Client, Config};
+
+/// Returns a client for the given TOML config
+pub(crate) fn client(toml: &str) -> Client {
+    Client::new(Config::from_toml(toml).expect("test config should parse"))
+}
+
+/// Returns a client whose default registry uses the given backend with the given backend config
+fn backend_client(backend: &str, backend_config: &str) -> Client {
+    client(&format!(
+        r#"
+default_registry = "{backend}"
+
+[registry."{backend}"]
+type = "{backend}"
+
+[registry."{backend}".{backend}]
+{backend_config}
+"#
+    ))
+}
+
+/// Returns a client with a `local` registry at the given directory as the default registry
+pub(crate) fn local_client(root: &Path) -> Client {
+    backend_client("local", &format!("root = '{}'", root.display()))
+}
+
+/// Returns a client with an `oci-layout` registry at the given directory as the default registry
+pub(crate) fn oci_layout_client(root: &Path) -> Client {
+    backend_client("oci-layout", &format!("root = '{}'", root.display()))
+}
+
+/// Returns a client with a `git` registry for the given repository URL as the default registry
+pub(crate) fn git_client(url: &str) -> Client {
+    backend_client("git", &format!("url = '{url}'"))
+}
+
+/// Returns a client with an `index` registry at the given directory as the default registry
+pub(crate) fn index_client(root: &Path) -> Client {
+    let url = url::Url::from_directory_path(root).unwrap();
+    backend_client("index", &format!("url = '{url}'"))
+}
+
+/// Returns an HTTP URL that nothing listens on, like a registry that is down
+pub(crate) fn unreachable_url() -> String {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    format!("http://{}/", listener.local_addr().unwrap())
+}
+
+/// Encodes the given WIT as a WIT package
+pub(crate) fn wit_package(wit: &str) -> Vec<u8> {
+    let mut resolve = wit_parser::Resolve::new();
+    let pkg = resolve
+        .push_str("test.wit", wit)
+        .expect("test WIT should parse");
+    wit_component::encode(&resolve, pkg).expect("test WIT should encode")
+}
+
+/// Encodes a component that targets the only world of the given WIT
+pub(crate) fn component_bytes(wit: &str) -> Vec<u8> {
+    let mut resolve = wit_parser::Resolve::new();
+    let pkg = resolve
+        .push_str("test.wit", wit)
+        .expect("test WIT should parse");
+    let world = resolve
+        .select_world(&[pkg], None)
+        .expect("test WIT should have exactly one world");
+    let mut module =
+        wit_component::dummy_module(&resolve, world, wit_parser::ManglingAndAbi::Standard32);
+    wit_component::embed_component_metadata(
+        &mut module,
+        &resolve,
+        world,
+        wit_component::StringEncoding::UTF8,
+    )
+    .expect("component metadata should embed");
+    wit_component::ComponentEncoder::default()
+        .module(&module)
+        .expect("dummy module should be accepted")
+        .validate(true)
+        .encode()
+        .expect("dummy module should encode as a component")
+}
@@ -0,0 +1,122 @@
+use async_trait::async_trait;
+use wasm_pkg_client::{
+    Client, Config, ContentStream, Error, LoaderPublisher, PackageLoader, PackagePublisher,
+    PackageRef, PublishingSource, Registry, RegistryConfig, RegistryMetadata, Release, Version,
+    VersionInfo,
+};
+
+const CONFIG: &str = r#"
+default_registry = "custom.example.com"
+
+[registry."custom.example.com"]
+type = "custom"
+
+[registry."custom.example.com".custom]
+versions = ["1.0.0", "1.1.0"]
+"#;
+
+/// A backend that lists the versions given in its config
+struct StaticBackend {
+    versions: Vec<Version>,
+}
+
+#[derive(serde::Deserialize)]
+struct StaticConfig {
+    versions: Vec<Version>,
+}
+
+#[async_trait]
+impl PackageLoader for StaticBackend {
+    async fn list_all_versions(&self, _package: &PackageRef) -> Result<Vec<VersionInfo>, Error> {
+        Ok(self
+            .versions
+            .iter()
+            .map(|version| VersionInfo {
+                version: version.clone(),
+                yanked: false,
+            })
+            .collect())
+    }
+
+    async fn get_release(
+        &self,
+        _package: &PackageRef,
+        version: &Version,
+    ) -> Result<Release, Error> {
+        Err(Error::VersionNotFound(version.clone()))
+    }
+
+    async fn stream_content_unvalidated(
+        &self,
+        _package: &PackageRef,
+        release: &Release,
+    ) -> Result<ContentStream, Error> {
+        Err(Error::VersionNotFound(release.version.clone()))
+    }
+}
+
+#[async_trait]
+impl PackagePublisher for StaticBackend {
+    async fn publish(
+        &self,
+        _package: &PackageRef,
+        _version: &Version,
+        _data: PublishingSource,
+        _dry_run: bool,
+    ) -> Result<(), Error> {
+        Err(Error::RegistryError(anyhow::anyhow!(
+            "the static backend is read-only"
+        )))
+    }
+}
+
+fn static_backend(
+    registry: &Registry,
+    registry_config: &RegistryConfig,
+    _registry_meta: &RegistryMetadata,
+) -> Result<Box<dyn LoaderPublisher>, Error> {
+    assert_eq!(registry.to_string(), "custom.example.com");
+    let config: StaticConfig = registry_config
+        .backend_config("custom")?
+        .expect("the custom backend should be configured");
+    Ok(Box::new(StaticBackend {
+        versions: config.versions,
+    }))
+}
+
+#[tokio::test]
+async fn registered_backend_is_used() {
+    let config = Config::from_toml(CONFIG).unwrap();
+    let client = Client::builder(config)
+        .with_backend("custom", static_backend)
+        .build();
+
+    let versions = client
+        .list_all_versions(&"example:pkg".parse().unwrap())
+        .await
+        .expect("the custom backend should list versions");
+    let versions: Vec<_> = versions.iter().map(|v| v.version.to_string()).collect();
+    assert_eq!(versions, ["1.0.0", "1.1.0"]);
+
+    client
+        .yank(
+            &"example:pkg".parse().unwrap(),
+            &"1.0.0".parse().unwrap(),
+            None,
+        )
+        .await
+        .expect_err("the custom backend doesn't support yanking");
+}
+
+#[tokio::test]
+async fn unregistered_backend_is_rejected() {
+    let client = Client::new(Config::from_toml(CONFIG).unwrap());
+    let err = client
+        .list_all_versions(&"example:pkg".parse().unwrap())
+        .await
+        .expect_err("the custom backend isn't registered");
+    assert!(
+        err.to_string().contains("unknown backend type \"custom\""),
+        "unexpected error: {err}"
+    );
+}
@@ -110,6 +110,65 @@ async fn publish_with_semver_check_succeeds_for_new_package() {
         .expect("publish should succeed for a brand-new package (NameUnknown swallowed)");
 }
 
+#[cfg(feature = "docker-tests")]
+#[tokio::test]
+async fn yank_and_unyank() {
+    use testcontainers::{
+        GenericImage, ImageExt,
+        core::{IntoContainerPort, WaitFor},
+        runners::AsyncRunner,
+    };
+
+    let _container = GenericImage::new("registry", "2")
+        .with_wait_for(WaitFor::message_on_stderr("listening on [::]:5000"))
+        .with_mapped_port(5004, 5000.tcp())
+        .start()
+        .await
+        .expect("Failed to start test container");
+
+    let config = Config::from_toml(
+        r#"
+        default_registry = "localhost:5004"
+
+        [registry."localhost:5004"]
+        type = "oci"
+        [registry."localhost:5004".oci]
+        protocol = "http"
+    "#,
+    )
+    .unwrap();
+    let client = Client::new(config);
+
+    let (package, version) = client
+        .publish_release_file(FIXTURE_WASM, Default::default())
+        .await
+        .expect("Failed to publish file");
+    let release = client.get_release(&package, &version).await.unwrap();
+
+    client
+        .yank(&package, &version, None)
+        .await
+        .expect("Failed to yank release");
+    let versions = client.list_all_versions(&package).await.unwrap();
+    assert!(
+        matches!(&versions[..], [info] if info.version == version && info.yanked),
+        "release should be listed as yanked: {versions:?}"
+    );
+    // Yanked releases can still be fetched and keep their digest
+    let yanked_release = client.get_release(&package, &version).await.unwrap();
+    assert_eq!(yanked_release.content_digest, release.content_digest);
+
+    client
+        .unyank(&package, &version, None)
+        .await
+        .expect("Failed to unyank release");
+    let versions = client.list_all_versions(&package).await.unwrap();
+    assert!(
+        matches!(&versions[..], [info] if info.version == version && !info.yanked),
+        "release should no longer be yanked: {versions:?}"
+    );
+}
+
 #[cfg(feature = "docker-tests")]
 #[tokio::test]
 async fn publish_and_fetch_succeed_with_self_signed_registry() {
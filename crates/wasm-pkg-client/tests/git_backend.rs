@@ -0,0 +1,147 @@
+use std::path::Path;
+
+use futures_util::TryStreamExt;
+use sha2::{Digest, Sha256};
+use tempfile::TempDir;
+use wasm_pkg_client::{Client, Config, ContentDigest, Error};
+
+use crate::common::git_client;
+
+mod common;
+
+fn git(dir: &Path, args: &[&str]) {
+    let status = std::process::Command::new("git")
+        .current_dir(dir)
+        .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .status()
+        .expect("git should be installed");
+    assert!(status.success(), "git {args:?} failed");
+}
+
+/// Creates a bare repository with `example-foo/v1.0.0` tagging a WIT directory and
+/// `example-foo/v1.1.0` tagging a `.wasm` file, returning the path of the bare repository.
+fn create_repo(tmp: &Path) -> std::path::PathBuf {
+    let work = tmp.join("work");
+    let bare = tmp.join("repo.git");
+    std::fs::create_dir_all(work.join("wit")).unwrap();
+    git(tmp, &["init", "--quiet", "--bare", "repo.git"]);
+    git(&work, &["init", "--quiet"]);
+
+    std::fs::write(
+        work.join("wit/foo.wit"),
+        "package example:foo@1.0.0;\n\ninterface api {\n  get: func() -> u32;\n}\n",
+    )
+    .unwrap();
+    git(&work, &["add", "."]);
+    git(&work, &["commit", "--quiet", "-m", "v1.0.0"]);
+    git(&work, &["tag", "example-foo/v1.0.0"]);
+
+    git(&work, &["rm", "--quiet", "-r", "wit"]);
+    std::fs::write(work.join("foo.wasm"), b"prebuilt").unwrap();
+    git(&work, &["add", "."]);
+    git(&work, &["commit", "--quiet", "-m", "v1.1.0"]);
+    git(&work, &["tag", "example-foo/v1.1.0"]);
+    // Tags of other packages and tags that aren't versions are ignored
+    git(&work, &["tag", "example-bar/v2.0.0"]);
+    git(&work, &["tag", "example-foo/vnext"]);
+
+    git(
+        &work,
+        &["push", "--quiet", bare.to_str().unwrap(), "--tags"],
+    );
+    bare
+}
+
+#[tokio::test]
+async fn git_backend_loads_tagged_releases() {
+    let tmp = TempDir::new().unwrap();
+    let client = git_client(&create_repo(tmp.path()).display().to_string());
+    let package = "example:foo".parse().unwrap();
+
+    let mut versions: Vec<_> = client
+        .list_all_versions(&package)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|info| info.version.to_string())
+        .collect();
+    versions.sort();
+    assert_eq!(versions, ["1.0.0", "1.1.0"]);
+
+    // The WIT directory is built into a WIT package
+    let release = client
+        .get_release(&package, &"1.0.0".parse().unwrap())
+        .await
+        .unwrap();
+    let content: Vec<_> = client
+        .stream_content(&package, &release)
+        .await
+        .unwrap()
+        .try_collect()
+        .await
+        .unwrap();
+    let content = content.concat();
+    let decoded = wit_component::decode(&content).expect("content should be a WIT package");
+    let name = &decoded.resolve().packages[decoded.package()].name;
+    assert_eq!(name.to_string(), "example:foo@1.0.0");
+
+    // A `.wasm` file is used as is
+    let release = client
+        .get_release(&package, &"1.1.0".parse().unwrap())
+        .await
+        .unwrap();
+    let digest: ContentDigest = Sha256::new_with_prefix(b"prebuilt").into();
+    assert_eq!(release.content_digest, digest);
+
+    client
+        .get_release(&package, &"3.0.0".parse().unwrap())
+        .await
+        .expect_err("3.0.0 isn't tagged");
+    client
+        .list_all_versions(&"example:missing".parse().unwrap())
+        .await
+        .expect_err("example:missing has no tags");
+}
+
+#[tokio::test]
+async fn git_backend_rejects_option_like_urls() {
+    let tmp = TempDir::new().unwrap();
+    let marker = tmp.path().join("marker");
+    let command = format!("touch {}", marker.display());
+    for url in [
+        format!("--upload-pack={command}"),
+        format!("ssh://-oProxyCommand={command}/repo.git"),
+        format!("ssh://git@-oProxyCommand={command}/repo.git"),
+        format!("-oProxyCommand={command}:repo.git"),
+        "git@-oProxyCommand=true:repo.git".to_string(),
+    ] {
+        let err = git_client(&url)
+            .list_all_versions(&"example:foo".parse().unwrap())
+            .await
+            .expect_err("option-like urls and ssh hosts should be rejected");
+        assert!(matches!(err, Error::InvalidConfig(_)), "{url}: {err:?}");
+    }
+    assert!(!marker.exists(), "git shouldn't have run the command");
+}
+
+#[tokio::test]
+async fn git_metadata_only_allows_https_urls() {
+    let tmp = TempDir::new().unwrap();
+    let repo = create_repo(tmp.path());
+    let config = Config::from_toml(&format!(
+        r#"
+[namespace_registries]
+example = {{ registry = "git.example.com", metadata = {{ preferredProtocol = "git", "git" = {{ url = '{}' }} }} }}
+"#,
+        repo.display()
+    ))
+    .unwrap();
+    let client = Client::new(config);
+
+    let err = client
+        .list_all_versions(&"example:foo".parse().unwrap())
+        .await
+        .expect_err("registry metadata shouldn't be able to use local repositories");
+    assert!(matches!(err, Error::InvalidRegistryMetadata(_)), "{err:?}");
+}
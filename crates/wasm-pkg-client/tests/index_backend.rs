@@ -0,0 +1,160 @@
+use std::path::Path;
+
+use futures_util::TryStreamExt;
+use sha2::{Digest, Sha256};
+use wasm_pkg_client::{
+    Client, Config, ContentDigest, Error,
+    index::{IndexEntry, PackageIndex},
+};
+
+use crate::common::index_client;
+
+mod common;
+
+const CONTENT: &[u8] = b"index content";
+
+/// Writes an index with `example:foo` 1.0.0 (yanked) and 1.1.0 into the given directory
+async fn write_index(root: &Path) -> ContentDigest {
+    let digest: ContentDigest = Sha256::new_with_prefix(CONTENT).into();
+    let ContentDigest::Sha256 { hex } = &digest;
+    tokio::fs::create_dir_all(root.join("blobs/sha256"))
+        .await
+        .unwrap();
+    tokio::fs::write(root.join("blobs/sha256").join(hex), CONTENT)
+        .await
+        .unwrap();
+
+    let index = PackageIndex {
+        versions: ["1.0.0", "1.1.0"]
+            .into_iter()
+            .map(|version| IndexEntry {
+                version: version.parse().unwrap(),
+                digest: digest.clone(),
+                yanked: version == "1.0.0",
+            })
+            .collect(),
+    };
+    tokio::fs::create_dir_all(root.join("example"))
+        .await
+        .unwrap();
+    tokio::fs::write(
+        root.join("example/foo.json"),
+        serde_json::to_vec(&index).unwrap(),
+    )
+    .await
+    .unwrap();
+    digest
+}
+
+#[tokio::test]
+async fn index_backend_loads_packages() {
+    let root = tempfile::tempdir().unwrap();
+    let digest = write_index(root.path()).await;
+    let client = index_client(root.path());
+    let package = "example:foo".parse().unwrap();
+
+    let versions = client.list_all_versions(&package).await.unwrap();
+    let versions: Vec<_> = versions
+        .iter()
+        .map(|info| (info.version.to_string(), info.yanked))
+        .collect();
+    assert_eq!(
+        versions,
+        [("1.0.0".to_string(), true), ("1.1.0".to_string(), false)]
+    );
+
+    let release = client
+        .get_release(&package, &"1.1.0".parse().unwrap())
+        .await
+        .unwrap();
+    assert_eq!(release.content_digest, digest);
+    let content: Vec<_> = client
+        .stream_content(&package, &release)
+        .await
+        .unwrap()
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(content.concat(), CONTENT);
+
+    client
+        .get_release(&package, &"2.0.0".parse().unwrap())
+        .await
+        .expect_err("2.0.0 is not in the index");
+    client
+        .list_all_versions(&"example:missing".parse().unwrap())
+        .await
+        .expect_err("example:missing has no index");
+}
+
+#[tokio::test]
+async fn index_backend_validates_content() {
+    let root = tempfile::tempdir().unwrap();
+    let digest = write_index(root.path()).await;
+    let ContentDigest::Sha256 { hex } = &digest;
+    tokio::fs::write(root.path().join("blobs/sha256").join(hex), b"tampered")
+        .await
+        .unwrap();
+    let client = index_client(root.path());
+    let package = "example:foo".parse().unwrap();
+
+    let release = client
+        .get_release(&package, &"1.1.0".parse().unwrap())
+        .await
+        .unwrap();
+    let result: Result<Vec<_>, _> = client
+        .stream_content(&package, &release)
+        .await
+        .unwrap()
+        .try_collect()
+        .await;
+    result.expect_err("content that doesn't match the digest should be rejected");
+}
+
+#[tokio::test]
+async fn index_metadata_cannot_use_file_urls() {
+    let root = tempfile::tempdir().unwrap();
+    write_index(root.path()).await;
+    let url = url::Url::from_directory_path(root.path()).unwrap();
+    let config = Config::from_toml(&format!(
+        r#"
+[namespace_registries]
+example = {{ registry = "index.example.com", metadata = {{ preferredProtocol = "index", "index" = {{ url = "{url}" }} }} }}
+"#
+    ))
+    .unwrap();
+    let client = Client::new(config);
+
+    let err = client
+        .list_all_versions(&"example:foo".parse().unwrap())
+        .await
+        .expect_err("registry metadata shouldn't be able to read from the filesystem");
+    assert!(
+        matches!(err, Error::InvalidRegistryMetadata(_)),
+        "unexpected error: {err:?}"
+    );
+}
+
+#[tokio::test]
+async fn index_backend_rejects_invalid_tls_config() {
+    let config = Config::from_toml(
+        r#"
+default_registry = "index.example.com"
+
+[registry."index.example.com"]
+type = "index"
+
+[registry."index.example.com".index]
+url = "https://index.example.com/"
+extra_root_certificates = [{ encoding = "der", data = "not a certificate" }]
+"#,
+    )
+    .unwrap();
+    let client = Client::new(config);
+
+    let err = client
+        .list_all_versions(&"example:foo".parse().unwrap())
+        .await
+        .expect_err("an invalid root certificate should be rejected");
+    assert!(matches!(err, Error::InvalidConfig(_)), "{err:?}");
+}
@@ -0,0 +1,155 @@
+use std::{io::Cursor, path::Path};
+
+use futures_util::TryStreamExt;
+use sha2::{Digest, Sha256};
+use tempfile::TempDir;
+use wasm_pkg_client::{Client, ContentDigest, Error, PackageRef, PublishOpts, Release};
+
+use crate::common::{client, unreachable_url, wit_package};
+
+mod common;
+
+/// Returns a client with the given `primary` registry, mirrored by an `index` registry that is down
+/// and a `local` registry at `mirror`
+fn mirrored_client(primary: &str, mirror: &Path) -> Client {
+    client(&format!(
+        r#"
+default_registry = "primary"
+
+[registry."primary"]
+mirrors = ["down", "mirror"]
+{primary}
+
+[registry."down"]
+type = "index"
+
+[registry."down".index]
+url = '{}'
+
+[registry."mirror".local]
+root = '{}'
+"#,
+        unreachable_url(),
+        mirror.display(),
+    ))
+}
+
+/// Returns the config of a primary `index` registry that is down
+fn unreachable_primary() -> String {
+    format!(
+        r#"type = "index"
+
+[registry."primary".index]
+url = '{}'"#,
+        unreachable_url()
+    )
+}
+
+/// Returns the config of a primary `local` registry at the given directory
+fn local_primary(root: &Path) -> String {
+    format!(
+        r#"type = "local"
+
+[registry."primary".local]
+root = '{}'"#,
+        root.display()
+    )
+}
+
+async fn write_release(root: &Path, name: &str, version: &str, content: &[u8]) {
+    let dir = root.join("example").join(name);
+    tokio::fs::create_dir_all(&dir).await.unwrap();
+    tokio::fs::write(dir.join(format!("{version}.wasm")), content)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn reads_fall_back_to_mirrors_while_registries_are_down() {
+    let mirror = TempDir::new().unwrap();
+    write_release(mirror.path(), "foo", "1.0.0", b"mirrored").await;
+    let client = mirrored_client(&unreachable_primary(), mirror.path());
+    let package = "example:foo".parse().unwrap();
+    let version = "1.0.0".parse().unwrap();
+
+    let versions = client.list_all_versions(&package).await.unwrap();
+    assert_eq!(versions.len(), 1);
+    let release = client.get_release(&package, &version).await.unwrap();
+    let content: Vec<_> = client
+        .stream_content(&package, &release)
+        .await
+        .unwrap()
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(content.concat(), b"mirrored");
+
+    let err = client
+        .get_release(&package, &"2.0.0".parse().unwrap())
+        .await
+        .expect_err("2.0.0 is in none of the registries");
+    assert!(matches!(err, Error::VersionNotFound(_)), "{err:?}");
+}
+
+#[tokio::test]
+async fn missing_packages_do_not_fall_back_to_mirrors() {
+    let primary = TempDir::new().unwrap();
+    let mirror = TempDir::new().unwrap();
+    write_release(mirror.path(), "foo", "1.0.0", b"mirrored").await;
+    write_release(mirror.path(), "bar", "1.0.0", b"mirrored").await;
+    let client = mirrored_client(&local_primary(primary.path()), mirror.path());
+    let package: PackageRef = "example:foo".parse().unwrap();
+
+    // Publishing only goes to the primary registry
+    let opts = PublishOpts {
+        package: Some((package.clone(), "1.1.0".parse().unwrap())),
+        ..Default::default()
+    };
+    client
+        .publish_release_data(
+            Box::pin(Cursor::new(wit_package(
+                "package example:foo@1.0.0;\n\ninterface api {\n  get: func() -> u32;\n}\n",
+            ))),
+            opts,
+        )
+        .await
+        .unwrap();
+    assert!(primary.path().join("example/foo/1.1.0.wasm").exists());
+    assert!(!mirror.path().join("example/foo/1.1.0.wasm").exists());
+
+    let versions = client.list_all_versions(&package).await.unwrap();
+    assert_eq!(versions.len(), 1, "the primary registry has only 1.1.0");
+    assert_eq!(versions[0].version.to_string(), "1.1.0");
+    let err = client
+        .get_release(&package, &"1.0.0".parse().unwrap())
+        .await
+        .expect_err("1.0.0 isn't in the primary registry");
+    assert!(matches!(err, Error::VersionNotFound(_)), "{err:?}");
+    let err = client
+        .list_all_versions(&"example:bar".parse().unwrap())
+        .await
+        .expect_err("example:bar isn't in the primary registry");
+    assert!(matches!(err, Error::PackageNotFound), "{err:?}");
+}
+
+#[tokio::test]
+async fn mirror_content_is_validated() {
+    let mirror = TempDir::new().unwrap();
+    write_release(mirror.path(), "foo", "1.0.0", b"tampered").await;
+    let client = mirrored_client(&unreachable_primary(), mirror.path());
+    let package = "example:foo".parse().unwrap();
+
+    // A release that was resolved before the primary went down
+    let content_digest: ContentDigest = Sha256::new_with_prefix(b"original").into();
+    let release = Release {
+        version: "1.0.0".parse().unwrap(),
+        content_digest,
+    };
+    let result: Result<Vec<_>, _> = client
+        .stream_content(&package, &release)
+        .await
+        .unwrap()
+        .try_collect()
+        .await;
+    result.expect_err("mirrored content that doesn't match the digest should be rejected");
+}
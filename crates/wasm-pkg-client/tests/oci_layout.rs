@@ -0,0 +1,167 @@
+use std::{io::Cursor, path::Path};
+
+use futures_util::TryStreamExt;
+use oci_client::manifest::{OciImageIndex, OciImageManifest};
+use tempfile::TempDir;
+use wasm_pkg_client::{ContentDigest, PublishOpts};
+
+use crate::common::{component_bytes, oci_layout_client};
+
+mod common;
+
+const WIT: &str = r#"
+package example:layout@0.1.0;
+
+world the-world {
+    export base: func() -> u32;
+}
+"#;
+
+async fn read_json<T: serde::de::DeserializeOwned>(path: impl AsRef<Path>) -> T {
+    serde_json::from_slice(&tokio::fs::read(path).await.unwrap()).unwrap()
+}
+
+#[tokio::test]
+async fn oci_layout_round_trip() {
+    let tmp = TempDir::new().unwrap();
+    let client = oci_layout_client(tmp.path());
+    let bytes = component_bytes(WIT);
+
+    let opts = PublishOpts {
+        package: Some(("example:app".parse().unwrap(), "1.0.0".parse().unwrap())),
+        ..Default::default()
+    };
+    let (package, version) = client
+        .publish_release_data(Box::pin(Cursor::new(bytes.clone())), opts)
+        .await
+        .expect("publishing to an OCI layout should succeed");
+
+    // The layout follows the OCI image layout spec and the Wasm OCI artifact conventions
+    let layout_dir = tmp.path().join("example").join("app");
+    let layout: serde_json::Value = read_json(layout_dir.join("oci-layout")).await;
+    assert_eq!(layout["imageLayoutVersion"], "1.0.0");
+    let index: OciImageIndex = read_json(layout_dir.join("index.json")).await;
+    assert_eq!(index.manifests.len(), 1);
+    let entry = &index.manifests[0];
+    assert_eq!(
+        entry.annotations.as_ref().unwrap()["org.opencontainers.image.ref.name"],
+        "1.0.0"
+    );
+    let manifest_digest: ContentDigest = entry.digest.parse().unwrap();
+    let ContentDigest::Sha256 { hex } = &manifest_digest;
+    let manifest: OciImageManifest = read_json(layout_dir.join("blobs/sha256").join(hex)).await;
+    assert_eq!(
+        manifest.annotations.as_ref().unwrap()["org.opencontainers.image.version"],
+        "1.0.0"
+    );
+    assert_eq!(
+        manifest.config.media_type,
+        "application/vnd.wasm.config.v0+json"
+    );
+    assert_eq!(manifest.layers[0].media_type, "application/wasm");
+
+    let release = client.get_release(&package, &version).await.unwrap();
+    assert_eq!(
+        release.content_digest.to_string(),
+        manifest.layers[0].digest
+    );
+    let content: Vec<_> = client
+        .stream_content(&package, &release)
+        .await
+        .unwrap()
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(content.concat(), bytes);
+
+    client.yank(&package, &version, None).await.unwrap();
+    let versions = client.list_all_versions(&package).await.unwrap();
+    assert_eq!(versions.len(), 1, "the yanked tag is not a version");
+    assert!(versions[0].yanked);
+    client.unyank(&package, &version, None).await.unwrap();
+    let versions = client.list_all_versions(&package).await.unwrap();
+    assert!(!versions[0].yanked);
+
+    client
+        .get_release(&package, &"2.0.0".parse().unwrap())
+        .await
+        .expect_err("2.0.0 was never published");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn oci_layout_concurrent_publishes_keep_all_tags() {
+    let tmp = TempDir::new().unwrap();
+    let bytes = component_bytes(WIT);
+    let package: wasm_pkg_client::PackageRef = "example:app".parse().unwrap();
+
+    // Separate clients, like separate processes publishing to the same layout
+    let publishes = (0..64).map(|minor| {
+        let client = oci_layout_client(tmp.path());
+        let opts = PublishOpts {
+            package: Some((package.clone(), format!("1.{minor}.0").parse().unwrap())),
+            ..Default::default()
+        };
+        let bytes = bytes.clone();
+        tokio::spawn(async move {
+            client
+                .publish_release_data(Box::pin(Cursor::new(bytes)), opts)
+                .await
+        })
+    });
+    for publish in futures_util::future::join_all(publishes).await {
+        publish
+            .unwrap()
+            .expect("publishing to an OCI layout should succeed");
+    }
+
+    let index: OciImageIndex =
+        read_json(tmp.path().join("example").join("app").join("index.json")).await;
+    let mut tags: Vec<_> = index
+        .manifests
+        .iter()
+        .map(|entry| {
+            entry.annotations.as_ref().unwrap()["org.opencontainers.image.ref.name"].clone()
+        })
+        .collect();
+    tags.sort();
+    let mut expected: Vec<_> = (0..64).map(|minor| format!("1.{minor}.0")).collect();
+    expected.sort();
+    assert_eq!(tags, expected, "no publish should overwrite another one");
+}
+
+#[tokio::test]
+async fn oci_layout_concurrent_yanks_keep_all_versions() {
+    let tmp = TempDir::new().unwrap();
+    let bytes = component_bytes(WIT);
+    let package: wasm_pkg_client::PackageRef = "example:app".parse().unwrap();
+    let client = oci_layout_client(tmp.path());
+    for minor in 0..32 {
+        let opts = PublishOpts {
+            package: Some((package.clone(), format!("1.{minor}.0").parse().unwrap())),
+            ..Default::default()
+        };
+        client
+            .publish_release_data(Box::pin(Cursor::new(bytes.clone())), opts)
+            .await
+            .unwrap();
+    }
+
+    // Separate clients, like separate processes yanking releases of the same layout
+    let yanks = (0..32).map(|minor| {
+        let client = oci_layout_client(tmp.path());
+        let package = package.clone();
+        let version: wasm_pkg_client::Version = format!("1.{minor}.0").parse().unwrap();
+        tokio::spawn(async move { client.yank(&package, &version, None).await })
+    });
+    for yank in futures_util::future::join_all(yanks).await {
+        yank.unwrap()
+            .expect("yanking in an OCI layout should succeed");
+    }
+
+    let versions = client.list_all_versions(&package).await.unwrap();
+    assert_eq!(versions.len(), 32);
+    assert!(
+        versions.iter().all(|version| version.yanked),
+        "no yank should overwrite another one"
+    );
+}
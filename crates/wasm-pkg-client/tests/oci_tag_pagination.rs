@@ -0,0 +1,208 @@
+//! Tests that listing the versions of an OCI package follows tag pagination, using a minimal local
+//! stand-in for the tag listing API of an OCI registry.
+
+use std::{
+    net::{Ipv4Addr, SocketAddr},
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+use wasm_pkg_client::{Client, Config, Version};
+
+/// A registry stand-in that serves `GET /v2/` and `GET /v2/<repo>/tags/list`, returning at most
+/// `page_size` tags per response along with a `Link` header like the distribution registry does.
+#[derive(Clone)]
+struct TagServer {
+    tags: Arc<Vec<String>>,
+    page_size: usize,
+    /// Whether the `last` query parameter is honored. Some registries ignore it.
+    honor_last: bool,
+    /// Another server the `Link` header points at instead of this one
+    link_origin: Option<SocketAddr>,
+    tag_requests: Arc<AtomicUsize>,
+}
+
+impl TagServer {
+    fn new(tags: &[&str], page_size: usize, honor_last: bool) -> Self {
+        let mut tags: Vec<String> = tags.iter().map(ToString::to_string).collect();
+        tags.sort();
+        Self {
+            tags: Arc::new(tags),
+            page_size,
+            honor_last,
+            link_origin: None,
+            tag_requests: Default::default(),
+        }
+    }
+
+    fn with_link_origin(mut self, origin: SocketAddr) -> Self {
+        self.link_origin = Some(origin);
+        self
+    }
+
+    /// Starts serving on a random local port, returning the address
+    async fn start(&self) -> SocketAddr {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .await
+            .expect("failed to bind stand-in registry");
+        let addr = listener.local_addr().unwrap();
+        let server = self.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(server.clone().handle(stream));
+            }
+        });
+        addr
+    }
+
+    async fn handle(self, mut stream: TcpStream) {
+        let mut buf = Vec::new();
+        let mut chunk = [0; 1024];
+        while !buf.windows(4).any(|w| w == b"\r\n\r\n") {
+            match stream.read(&mut chunk).await {
+                Ok(0) | Err(_) => return,
+                Ok(n) => buf.extend_from_slice(&chunk[..n]),
+            }
+            // Anything that isn't plain HTTP (e.g. a TLS handshake for registry metadata) is dropped
+            if buf.len() >= 4 && !buf.starts_with(b"GET ") && !buf.starts_with(b"HEAD") {
+                return;
+            }
+        }
+        let request = String::from_utf8_lossy(&buf);
+        let target = request.split(' ').nth(1).unwrap_or_default();
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+        let (status, headers, body) = if path == "/v2/" {
+            ("200 OK", String::new(), "{}".to_string())
+        } else if let Some(repo) = path
+            .strip_prefix("/v2/")
+            .and_then(|p| p.strip_suffix("/tags/list"))
+        {
+            self.tag_requests.fetch_add(1, Ordering::SeqCst);
+            let param = |name: &str| {
+                query
+                    .split('&')
+                    .find_map(|pair| pair.strip_prefix(name)?.strip_prefix('='))
+            };
+            let last = param("last").filter(|_| self.honor_last);
+            let n = param("n")
+                .and_then(|n| n.parse().ok())
+                .unwrap_or(self.page_size)
+                .min(self.page_size);
+            let remaining: Vec<&String> = self
+                .tags
+                .iter()
+                .filter(|tag| last.is_none_or(|last| tag.as_str() > last))
+                .collect();
+            let page = &remaining[..n.min(remaining.len())];
+            let link = match page.last() {
+                Some(page_last) if remaining.len() > page.len() => {
+                    let origin = self
+                        .link_origin
+                        .map(|addr| format!("http://{addr}"))
+                        .unwrap_or_default();
+                    format!(
+                        "Link: <{origin}/v2/{repo}/tags/list?last={page_last}&n={n}>; rel=\"next\"\r\n"
+                    )
+                }
+                _ => String::new(),
+            };
+            let body = serde_json::json!({ "name": repo, "tags": page }).to_string();
+            ("200 OK", link, body)
+        } else {
+            ("404 Not Found", String::new(), String::new())
+        };
+
+        let response = format!(
+            "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n{headers}\r\n{body}",
+            body.len()
+        );
+        let _ = stream.write_all(response.as_bytes()).await;
+        let _ = stream.shutdown().await;
+    }
+}
+
+fn client_for(addr: SocketAddr) -> Client {
+    let config = Config::from_toml(&format!(
+        r#"
+        default_registry = "{addr}"
+
+        [registry."{addr}"]
+        type = "oci"
+        [registry."{addr}".oci]
+        protocol = "http"
+    "#
+    ))
+    .expect("failed to parse config");
+    Client::new(config)
+}
+
+const TAGS: &[&str] = &[
+    "0.1.0", "0.1.1", "0.2.0", "0.3.0", "0.3.1", "1.0.0", "1.1.0", "latest",
+];
+
+#[tokio::test]
+async fn list_all_versions_follows_pagination() {
+    let server = TagServer::new(TAGS, 3, true);
+    let client = client_for(server.start().await);
+
+    let package = "example:paged".parse().unwrap();
+    let mut versions: Vec<Version> = client
+        .list_all_versions(&package)
+        .await
+        .expect("failed to list versions")
+        .into_iter()
+        .map(|info| info.version)
+        .collect();
+    versions.sort();
+
+    let expected: Vec<Version> = TAGS.iter().filter_map(|tag| tag.parse().ok()).collect();
+    assert_eq!(versions, expected, "every page of tags should be listed");
+    assert_eq!(
+        server.tag_requests.load(Ordering::SeqCst),
+        3,
+        "every page should be requested once, stopping at the page without a Link header"
+    );
+}
+
+#[tokio::test]
+async fn list_all_versions_errors_when_registry_ignores_last() {
+    let server = TagServer::new(TAGS, 3, false);
+    let client = client_for(server.start().await);
+
+    let package = "example:paged".parse().unwrap();
+    client
+        .list_all_versions(&package)
+        .await
+        .expect_err("a page without new tags shouldn't be returned as a partial list");
+
+    assert_eq!(
+        server.tag_requests.load(Ordering::SeqCst),
+        2,
+        "listing should stop once a page returns no new tags"
+    );
+}
+
+#[tokio::test]
+async fn list_all_versions_refuses_links_to_other_origins() {
+    let other = TagServer::new(TAGS, 3, true);
+    let server = TagServer::new(TAGS, 3, true).with_link_origin(other.start().await);
+    let client = client_for(server.start().await);
+
+    let package = "example:paged".parse().unwrap();
+    client
+        .list_all_versions(&package)
+        .await
+        .expect_err("a Link header to another origin shouldn't be followed");
+    assert_eq!(
+        other.tag_requests.load(Ordering::SeqCst),
+        0,
+        "the credentials of the registry shouldn't be sent to another origin"
+    );
+}
@@ -280,6 +280,7 @@ impl Config {
 #[derive(Clone, Default)]
 pub struct RegistryConfig {
     default_backend: Option<String>,
+    mirrors: Vec<Registry>,
     backend_configs: HashMap<String, ::toml::Table>,
 }
 
@@ -299,11 +300,15 @@ impl RegistryConfig {
     pub fn merge(&mut self, other: Self) {
         let Self {
             default_backend: backend_type,
+            mirrors,
             backend_configs,
         } = other;
         if backend_type.is_some() {
             self.default_backend = backend_type;
         }
+        if !mirrors.is_empty() {
+            self.mirrors = mirrors;
+        }
         for (ty, config) in backend_configs {
             match self.backend_configs.entry(ty) {
                 Entry::Occupied(mut occupied) => occupied.get_mut().extend(config),
@@ -333,6 +338,18 @@ impl RegistryConfig {
         self.default_backend = default_backend;
     }
 
+    /// Returns the mirrors of this registry, in the order they are tried when reading packages.
+    pub fn mirrors(&self) -> &[Registry] {
+        &self.mirrors
+    }
+
+    /// Sets the mirrors of this registry.
+    ///
+    /// To remove all mirrors, pass an empty `Vec`.
+    pub fn set_mirrors(&mut self, mirrors: Vec<Registry>) {
+        self.mirrors = mirrors;
+    }
+
     /// Returns an iterator of configured backend types.
     pub fn configured_backend_types(&self) -> impl Iterator<Item = &str> {
         self.backend_configs.keys().map(|ty| ty.as_str())
@@ -369,6 +386,7 @@ impl std::fmt::Debug for RegistryConfig {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("RegistryConfig")
             .field("backend_type", &self.default_backend)
+            .field("mirrors", &self.mirrors)
             .field(
                 "backend_configs",
                 &DebugBackendConfigs(&self.backend_configs),
@@ -65,6 +65,8 @@ impl From<super::Config> for TomlConfig {
 struct TomlRegistryConfig {
     #[serde(alias = "type")]
     default: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    mirrors: Vec<Registry>,
     #[serde(flatten)]
     backend_configs: HashMap<String, toml::Table>,
 }
@@ -73,10 +75,12 @@ impl From<TomlRegistryConfig> for super::RegistryConfig {
     fn from(value: TomlRegistryConfig) -> Self {
         let TomlRegistryConfig {
             default,
+            mirrors,
             backend_configs,
         } = value;
         Self {
             default_backend: default,
+            mirrors,
             backend_configs,
         }
     }
@@ -86,10 +90,12 @@ impl From<super::RegistryConfig> for TomlRegistryConfig {
     fn from(value: super::RegistryConfig) -> Self {
         let super::RegistryConfig {
             default_backend: backend_default,
+            mirrors,
             backend_configs,
         } = value;
         Self {
             default: backend_default,
+            mirrors,
             backend_configs,
         }
     }
@@ -243,6 +249,39 @@ mod tests {
         );
     }
 
+    #[test]
+    fn mirrors_parse_correctly() {
+        let toml_config = toml::toml! {
+            [registry."ghcr.io"]
+            mirrors = ["mirror.internal", "localhost:5000"]
+            [registry."ghcr.io".oci]
+            auth = { username = "open", password = "sesame" }
+        };
+
+        let toml_cfg: TomlConfig = toml_config.try_into().unwrap();
+        let cfg = crate::config::Config::from(toml_cfg);
+        let reg_conf = cfg
+            .registry_config(&"ghcr.io".parse().unwrap())
+            .expect("Should have config for registry");
+        let mirrors: Vec<_> = reg_conf.mirrors().iter().map(|m| m.to_string()).collect();
+        assert_eq!(mirrors, ["mirror.internal", "localhost:5000"]);
+        assert_eq!(
+            reg_conf.default_backend(),
+            Some("oci"),
+            "Mirrors should not count as a backend config"
+        );
+
+        let toml_str = ::toml::to_string(&cfg).unwrap();
+        let roundtrip = crate::config::Config::from_toml(&toml_str).unwrap();
+        assert_eq!(
+            roundtrip
+                .registry_config(&"ghcr.io".parse().unwrap())
+                .unwrap()
+                .mirrors(),
+            reg_conf.mirrors()
+        );
+    }
+
     #[test]
     fn test_custom_namespace_config() {
         let toml_config = toml::toml! {
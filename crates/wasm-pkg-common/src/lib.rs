@@ -58,6 +58,8 @@ pub enum Error {
     RegistryMetadataError(#[source] anyhow::Error),
     #[error("version not found: {0}")]
     VersionNotFound(semver::Version),
+    #[error("release digest changed since it was cached: {0}")]
+    DigestChanged(String),
     #[error("{0}@{1} already exists in the registry")]
     VersionAlreadyExists(PackageRef, semver::Version),
     #[error(
@@ -36,6 +36,12 @@ pub struct RegistryMetadata {
 pub const OCI_PROTOCOL: &str = "oci";
 /// Local filesystem key
 pub const LOCAL_PROTOCOL: &str = "local";
+/// OCI image layout directory key
+pub const OCI_LAYOUT_PROTOCOL: &str = "oci-layout";
+/// Git repository key
+pub const GIT_PROTOCOL: &str = "git";
+/// Static package index
+pub const INDEX_PROTOCOL: &str = "index";
 
 impl RegistryMetadata {
     /// Returns the registry's preferred protocol.
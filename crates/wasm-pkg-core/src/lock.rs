@@ -2,7 +2,7 @@
 
 use std::{
     cmp::Ordering,
-    collections::{BTreeSet, HashMap},
+    collections::{BTreeSet, HashMap, HashSet},
     ops::{Deref, DerefMut},
     path::{Path, PathBuf},
 };
@@ -15,6 +15,7 @@ use tokio::{
     io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt},
 };
 use wasm_pkg_client::{ContentDigest, PackageRef, Version};
+use wasm_pkg_common::package::PackageSpec;
 
 use crate::resolver::{DependencyResolution, DependencyResolutionMap};
 
@@ -41,6 +42,30 @@ pub struct LockFile {
 
     #[serde(skip)]
     locker: Locker,
+
+    #[serde(skip)]
+    mode: LockMode,
+
+    /// The packages at the time the lock file was locked.
+    #[serde(skip)]
+    locked_packages: BTreeSet<LockedPackage>,
+
+    /// Packages that are never locked.
+    #[serde(skip)]
+    excluded: BTreeSet<PackageRef>,
+}
+
+/// Whether resolving dependencies may change a [`LockFile`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LockMode {
+    /// The lock file is updated with the resolved dependencies.
+    #[default]
+    Update,
+    /// Resolving dependencies fails if the lock file would change.
+    Locked,
+    /// Like [`LockMode::Locked`], but dependencies are also resolved without network access, as if
+    /// the client was in offline mode.
+    Frozen,
 }
 
 impl PartialEq for LockFile {
@@ -72,9 +97,32 @@ impl LockFile {
             version: LOCK_FILE_V1,
             packages: packages.into_iter().collect(),
             locker,
+            mode: LockMode::default(),
+            locked_packages: BTreeSet::new(),
+            excluded: BTreeSet::new(),
         })
     }
 
+    /// Sets whether resolving dependencies may change the lock file. When locked, the current
+    /// packages are the ones the lock file must keep.
+    pub fn with_mode(mut self, mode: LockMode) -> Self {
+        self.mode = mode;
+        self.locked_packages = self.packages.clone();
+        self
+    }
+
+    /// Returns whether resolving dependencies may change the lock file.
+    pub fn mode(&self) -> LockMode {
+        self.mode
+    }
+
+    /// Excludes the given packages from the lock file, e.g. the members of a workspace that are
+    /// built from source. They are left out when [updating](Self::update_dependencies) the lock
+    /// file, so they don't count as changes in a locked lock file either.
+    pub fn exclude_packages(&mut self, packages: impl IntoIterator<Item = PackageRef>) {
+        self.excluded.extend(packages);
+    }
+
     /// Loads a lock file from the given path. If readonly is set to false, then an exclusive lock
     /// will be acquired on the file. This function will block until the lock is acquired.
     pub async fn load_from_path(path: impl AsRef<Path>, readonly: bool) -> Result<Self> {
@@ -124,9 +172,52 @@ impl LockFile {
     /// map.
     ///
     /// This function will not write the data to the file unless [`write`](Self::write) is called.
-    pub fn update_dependencies(&mut self, map: &DependencyResolutionMap) {
-        self.packages.clear();
-        self.packages.extend(generate_locked_packages(map));
+    ///
+    /// Fails if the lock file is [locked](LockMode::Locked) and any of the dependencies is not
+    /// locked yet.
+    pub fn update_dependencies(&mut self, map: &DependencyResolutionMap) -> Result<()> {
+        let packages: BTreeSet<LockedPackage> = generate_locked_packages(map)
+            .filter(|pkg| !self.excluded.contains(&pkg.name))
+            .collect();
+        if self.mode != LockMode::Update {
+            let unlocked: Vec<PackageSpec> = packages
+                .iter()
+                .flat_map(|pkg| {
+                    let locked = self.locked_packages.get(pkg);
+                    pkg.versions
+                        .iter()
+                        .filter(move |version| {
+                            !locked.is_some_and(|locked| locked.versions.contains(version))
+                        })
+                        .map(move |version| PackageSpec {
+                            package: pkg.name.clone(),
+                            version: Some(version.version.clone()),
+                        })
+                })
+                .collect();
+            if !unlocked.is_empty() {
+                return Err(LockFileOutdated {
+                    path: self.locker.path.clone(),
+                    unlocked,
+                }
+                .into());
+            }
+        }
+        self.packages = packages;
+        Ok(())
+    }
+
+    /// Checks that the packages are the same as when the lock file was locked. Always succeeds if
+    /// the lock file isn't [locked](LockMode::Locked).
+    pub fn check_unchanged(&self) -> Result<()> {
+        if self.mode != LockMode::Update && !same_packages(&self.locked_packages, &self.packages) {
+            return Err(LockFileOutdated {
+                path: self.locker.path.clone(),
+                unlocked: Vec::new(),
+            }
+            .into());
+        }
+        Ok(())
     }
 
     /// Attempts to load the lock file from the current directory. Most of the time, users of this
@@ -144,8 +235,10 @@ impl LockFile {
         Self::load_from_path(lock_path, readonly).await
     }
 
-    /// Serializes and writes the lock file
+    /// Serializes and writes the lock file. Fails if the lock file is [locked](LockMode::Locked)
+    /// and its packages changed.
     pub async fn write(&mut self) -> Result<()> {
+        self.check_unchanged()?;
         let contents = toml::to_string_pretty(self)?;
         // Truncate the file before writing to it
         self.locker.rewind().await.with_context(|| {
@@ -214,6 +307,75 @@ impl LockFile {
         tracing::info!(%package_ref, ?registry, %requirement, "dependency package was not in the lock file");
         Ok(None)
     }
+
+    /// Removes all locked versions of the given packages so that they are re-resolved the next
+    /// time dependencies are resolved with this lock file. All other packages are left untouched.
+    ///
+    /// Fails if any of the given packages is not present in the lock file.
+    pub fn unlock_packages<'b>(
+        &mut self,
+        packages: impl IntoIterator<Item = &'b PackageRef>,
+    ) -> Result<()> {
+        // Check every package first so that the lock file is left untouched on error
+        let packages: HashSet<&PackageRef> = packages.into_iter().collect();
+        if let Some(package) = packages
+            .iter()
+            .find(|package| !self.packages.iter().any(|locked| &&locked.name == *package))
+        {
+            anyhow::bail!("package `{package}` is not in the lock file");
+        }
+        self.packages
+            .retain(|locked| !packages.contains(&locked.name));
+        Ok(())
+    }
+
+    /// Pins the locked requirements of the given package that the version satisfies to that exact
+    /// version and digest. Requirements the version doesn't satisfy, such as those of another
+    /// locked version of the package, are left untouched.
+    ///
+    /// Fails if the package is not present in the lock file or if the version does not satisfy any
+    /// of the requirements the package was locked with.
+    pub fn pin_package(
+        &mut self,
+        package: &PackageRef,
+        version: &Version,
+        digest: &ContentDigest,
+    ) -> Result<()> {
+        let locked: Vec<LockedPackage> = self
+            .packages
+            .iter()
+            .filter(|locked| &locked.name == package)
+            .cloned()
+            .collect();
+        if locked.is_empty() {
+            anyhow::bail!("package `{package}` is not in the lock file");
+        }
+        // Pin copies of the entries so that the lock file is left untouched on error
+        let mut pinned = false;
+        let locked: Vec<LockedPackage> = locked
+            .into_iter()
+            .map(|mut pkg| {
+                for locked_version in pkg.versions.iter_mut() {
+                    if locked_version.requirement.matches(version) {
+                        locked_version.version = version.clone();
+                        locked_version.digest = digest.clone();
+                        pinned = true;
+                    }
+                }
+                pkg
+            })
+            .collect();
+        if !pinned {
+            anyhow::bail!(
+                "version `{version}` of package `{package}` does not satisfy any of its locked requirements"
+            );
+        }
+        for pkg in locked {
+            // `LockedPackage` is ordered by name and registry only, so this replaces the entry
+            self.packages.replace(pkg);
+        }
+        Ok(())
+    }
 }
 
 fn generate_locked_packages(map: &DependencyResolutionMap) -> impl Iterator<Item = LockedPackage> {
@@ -229,17 +391,19 @@ fn generate_locked_packages(map: &DependencyResolutionMap) -> impl Iterator<Item
                     DependencyResolution::Local(_) => unreachable!(),
                 };
 
-                let prev = packages
+                let versions = packages
                     .entry((id.clone(), registry.map(str::to_string)))
-                    .or_default()
-                    .insert(
-                        pkg.requirement.to_string(),
+                    .or_default();
+                for requirement in pkg.requirements() {
+                    let prev = versions.insert(
+                        requirement.to_string(),
                         (pkg.version.clone(), pkg.digest.clone()),
                     );
 
-                if let Some((prev, _)) = prev {
-                    // The same requirements should resolve to the same version
-                    assert!(prev == pkg.version)
+                    if let Some((prev, _)) = prev {
+                        // The same requirements should resolve to the same version
+                        assert!(prev == pkg.version)
+                    }
                 }
             }
             None => continue,
@@ -266,6 +430,46 @@ fn generate_locked_packages(map: &DependencyResolutionMap) -> impl Iterator<Item
     })
 }
 
+/// The error returned when a [locked](LockMode::Locked) lock file would change.
+#[derive(Debug)]
+pub struct LockFileOutdated {
+    /// The path of the lock file.
+    pub path: PathBuf,
+    /// The package versions that would be added to the lock file, if any.
+    pub unlocked: Vec<PackageSpec>,
+}
+
+impl std::fmt::Display for LockFileOutdated {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "the lock file at {} needs to be updated",
+            self.path.display()
+        )?;
+        if !self.unlocked.is_empty() {
+            let unlocked: Vec<String> = self.unlocked.iter().map(ToString::to_string).collect();
+            write!(f, " to lock {}", unlocked.join(", "))?;
+        }
+        write!(f, ", but it is locked")
+    }
+}
+
+impl std::error::Error for LockFileOutdated {}
+
+/// Returns whether both sets of packages lock the same versions. The locked versions of a package
+/// aren't ordered, so they are compared regardless of their order.
+fn same_packages(a: &BTreeSet<LockedPackage>, b: &BTreeSet<LockedPackage>) -> bool {
+    a.len() == b.len()
+        && a.iter().zip(b).all(|(a, b)| {
+            a.name == b.name
+                && a.registry == b.registry
+                && a.versions.len() == b.versions.len()
+                && a.versions
+                    .iter()
+                    .all(|version| b.versions.contains(version))
+        })
+}
+
 /// Represents a locked package in a lock file.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct LockedPackage {
@@ -326,6 +530,9 @@ impl LockFileIntermediate {
             version: self.version,
             packages: self.packages,
             locker,
+            mode: LockMode::default(),
+            locked_packages: BTreeSet::new(),
+            excluded: BTreeSet::new(),
         }
     }
 }
@@ -882,4 +1089,85 @@ mod tests {
         );
         assert_eq!(lock.version, LOCK_FILE_V1, "Lock file version should be 1");
     }
+
+    #[tokio::test]
+    async fn test_unlock_and_pin() {
+        let tempdir = tempfile::tempdir().expect("failed to create tempdir");
+        let path = tempdir.path().join(LOCK_FILE_NAME);
+
+        let old_digest: ContentDigest = sha2::Sha256::new_with_prefix(b"old").into();
+        let new_digest: ContentDigest = sha2::Sha256::new_with_prefix(b"new").into();
+        let locked = |name: &str, requirement: &str| LockedPackage {
+            name: name.parse().unwrap(),
+            versions: vec![LockedPackageVersion {
+                version: "0.1.0".parse().unwrap(),
+                digest: old_digest.clone(),
+                requirement: VersionReq::parse(requirement).unwrap(),
+            }],
+            registry: None,
+        };
+
+        let mut lock = LockFile::new_with_path(
+            [
+                locked("enterprise:holodeck", "*"),
+                locked("ds9:holosuite", "=0.1.0"),
+            ],
+            &path,
+        )
+        .await
+        .expect("Shouldn't fail when creating a new lock file");
+
+        lock.pin_package(
+            &"enterprise:holodeck".parse().unwrap(),
+            &"0.2.0".parse().unwrap(),
+            &new_digest,
+        )
+        .expect("Should be able to pin a package with a wildcard requirement");
+        let pinned = lock
+            .resolve(
+                None,
+                &"enterprise:holodeck".parse().unwrap(),
+                &VersionReq::STAR,
+            )
+            .unwrap()
+            .expect("Pinned package should still be locked");
+        assert_eq!(pinned.version, "0.2.0".parse().unwrap());
+        assert_eq!(pinned.digest, new_digest);
+
+        lock.pin_package(
+            &"ds9:holosuite".parse().unwrap(),
+            &"0.2.0".parse().unwrap(),
+            &new_digest,
+        )
+        .expect_err("Should not be able to pin a version outside of the requirement");
+        lock.pin_package(
+            &"defiant:armor".parse().unwrap(),
+            &"0.2.0".parse().unwrap(),
+            &new_digest,
+        )
+        .expect_err("Should not be able to pin a package that isn't locked");
+
+        lock.unlock_packages([
+            &"ds9:holosuite".parse().unwrap(),
+            &"defiant:armor".parse().unwrap(),
+        ])
+        .expect_err("Should not be able to unlock a package that isn't locked");
+        assert_eq!(
+            lock.packages.len(),
+            2,
+            "A failed unlock should leave the lock file untouched"
+        );
+        lock.unlock_packages([&"ds9:holosuite".parse().unwrap()])
+            .expect("Should be able to unlock a locked package");
+        assert_eq!(
+            lock.packages
+                .iter()
+                .map(|p| p.name.to_string())
+                .collect::<Vec<_>>(),
+            ["enterprise:holodeck"],
+            "Only the unlocked package should be removed"
+        );
+        lock.unlock_packages([&"ds9:holosuite".parse().unwrap()])
+            .expect_err("Should not be able to unlock a package that isn't locked");
+    }
 }
@@ -39,6 +39,9 @@ pub struct Manifest {
     /// tools.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub metadata: Option<Metadata>,
+    /// Settings for how dependencies are resolved
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolver: Option<ResolverSettings>,
 }
 
 impl Manifest {
@@ -154,6 +157,20 @@ impl Manifest {
             .context("unable to write manifest to path")
     }
 
+    /// Returns whether semver compatible package references should be unified
+    pub fn unify_semver(&self) -> bool {
+        self.resolver
+            .as_ref()
+            .is_some_and(|resolver| resolver.unify_semver)
+    }
+
+    /// Returns the maximum number of concurrent registry requests while resolving, if configured
+    pub fn resolve_concurrency(&self) -> Option<usize> {
+        self.resolver
+            .as_ref()
+            .and_then(|resolver| resolver.concurrency)
+    }
+
     /// Returns a matching override name and value for the input path
     pub(crate) fn has_override(&self, path: impl AsRef<Path>) -> bool {
         let path = path.as_ref().canonicalize().ok();
@@ -178,6 +195,20 @@ pub struct Override {
     pub version: Option<VersionReq>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct ResolverSettings {
+    /// Treat versioned package references (e.g. `wasi:io@0.2.0`) as caret requirements
+    /// (`^0.2.0`) instead of exact ones, so that semver compatible references share the single
+    /// highest matching release.
+    #[serde(default)]
+    pub unify_semver: bool,
+    /// The maximum number of registry requests made concurrently while resolving dependencies.
+    /// Defaults to [`DEFAULT_RESOLVE_CONCURRENCY`](crate::resolver::DEFAULT_RESOLVE_CONCURRENCY).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub concurrency: Option<usize>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
 #[serde(deny_unknown_fields)]
 pub struct Metadata {
@@ -227,6 +258,10 @@ mod tests {
                 homepage: Some("https://foo.bar".to_string()),
                 revision: Some("f00ba4".to_string()),
             }),
+            resolver: Some(ResolverSettings {
+                unify_semver: true,
+                concurrency: Some(4),
+            }),
         };
 
         manifest
@@ -2,30 +2,32 @@
 // NOTE(thomastaylor312): This is copied and adapted from the `cargo-component` crate: https://github.com/bytecodealliance/cargo-component/blob/f0be1c7d9917aa97e9102e69e3b838dae38d624b/crates/core/src/registry.rs
 
 use std::{
-    collections::{BTreeSet, HashMap, HashSet, hash_map},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, hash_map},
     fmt::Debug,
     ops::{Deref, DerefMut},
     path::{Path, PathBuf},
     str::FromStr,
+    sync::Arc,
 };
 
 use anyhow::{Context, Result, bail};
-use futures_util::TryStreamExt;
+use futures_util::{StreamExt, TryStreamExt, future::BoxFuture};
 use indexmap::{IndexMap, IndexSet};
 use petgraph::{Direction, acyclic::Acyclic, graph::NodeIndex, stable_graph::StableDiGraph};
 use semver::{Comparator, Op, Version, VersionReq};
 use tokio::io::{AsyncRead, AsyncReadExt};
 use wasm_pkg_client::{
-    Client, Config, ContentDigest, Error as WasmPkgError, PackageRef, Release, VersionInfo,
-    caching::{CachingClient, FileCache},
+    Client, Config, ContentDigest, ContentStream, Error as WasmPkgError, PackageRef, Release,
+    VersionInfo,
+    caching::{Cache, CachingClient, FileCache},
 };
 use wasm_pkg_common::package::PackageSpec;
 use wit_component::DecodedWasm;
 use wit_parser::{PackageId, PackageName, Resolve, UnresolvedPackageGroup, WorldId};
 
 use crate::{
-    lock::LockFile,
-    wit::{get_local_dependencies, get_packages},
+    lock::{LockFile, LockMode},
+    wit::{get_local_dependencies, get_packages, packages_from_foreign_deps},
 };
 
 /// The name of the default registry.
@@ -117,12 +119,34 @@ pub struct RegistryResolution {
     pub registry: Option<String>,
     /// The version requirement that was used to resolve the package.
     pub requirement: VersionReq,
+    /// Any other version requirements of the dependency that are satisfied by the same version.
+    pub other_requirements: Vec<VersionReq>,
     /// The package version that was resolved.
     pub version: Version,
     /// The digest of the package contents.
     pub digest: ContentDigest,
     /// The client to use for fetching the package contents.
-    client: CachingClient<FileCache>,
+    client: Arc<dyn ContentSource>,
+}
+
+/// Fetches package contents through a [`CachingClient`], so that resolutions don't depend on the
+/// type of its cache.
+trait ContentSource: Send + Sync {
+    fn get_content<'a>(
+        &'a self,
+        package: &'a PackageRef,
+        release: &'a Release,
+    ) -> BoxFuture<'a, Result<ContentStream, WasmPkgError>>;
+}
+
+impl<T: Cache + Send + Sync> ContentSource for CachingClient<T> {
+    fn get_content<'a>(
+        &'a self,
+        package: &'a PackageRef,
+        release: &'a Release,
+    ) -> BoxFuture<'a, Result<ContentStream, WasmPkgError>> {
+        Box::pin(CachingClient::get_content(self, package, release))
+    }
 }
 
 impl Debug for RegistryResolution {
@@ -132,6 +156,7 @@ impl Debug for RegistryResolution {
             .field("package", &self.package)
             .field("registry", &self.registry)
             .field("requirement", &self.requirement)
+            .field("other_requirements", &self.other_requirements)
             .field("version", &self.version)
             .field("digest", &self.digest)
             .finish()
@@ -139,6 +164,18 @@ impl Debug for RegistryResolution {
 }
 
 impl RegistryResolution {
+    /// Returns every version requirement that resolved to this package version.
+    pub fn requirements(&self) -> impl Iterator<Item = &VersionReq> {
+        std::iter::once(&self.requirement).chain(&self.other_requirements)
+    }
+
+    /// Records another requirement that is satisfied by this resolution.
+    fn add_requirement(&mut self, requirement: VersionReq) {
+        if !self.requirements().any(|req| req == &requirement) {
+            self.other_requirements.push(requirement);
+        }
+    }
+
     /// Fetches the raw package bytes from the registry. Returns an AsyncRead that will stream the
     /// package contents
     pub async fn fetch(&self) -> Result<impl AsyncRead> {
@@ -239,7 +276,7 @@ impl DependencyResolution {
             }
         };
 
-        if &bytes[0..4] != b"\0asm" {
+        if !bytes.starts_with(b"\0asm") {
             let package = UnresolvedPackageGroup::parse(
                 // This is fake, but it's needed for the parser to work.
                 self.name().to_string(),
@@ -321,6 +358,56 @@ impl DecodedDependency<'_> {
         }
     }
 
+    /// Gets the foreign packages referenced by the decoded dependency that it doesn't include,
+    /// along with the version requirement of the reference.
+    ///
+    /// Decoded Wasm always includes all of its foreign packages, so this is only ever non-empty
+    /// for an unresolved WIT package.
+    fn unresolved_packages(&self) -> Vec<(PackageRef, VersionReq)> {
+        let Self::Wit { package, .. } = self else {
+            return Vec::new();
+        };
+        let included: HashSet<_> = std::iter::once(&package.main)
+            .chain(&package.nested)
+            .map(|pkg| &pkg.name)
+            .collect();
+        packages_from_foreign_deps(
+            std::iter::once(&package.main)
+                .chain(&package.nested)
+                .flat_map(|pkg| pkg.foreign_deps.keys())
+                .filter(|name| !included.contains(name))
+                .cloned(),
+        )
+        .collect()
+    }
+
+    /// Gets the foreign packages referenced by the decoded dependency, along with the version
+    /// requirement of the reference.
+    ///
+    /// For decoded Wasm, these are the packages it includes rather than packages that need to be
+    /// resolved.
+    pub fn referenced_packages(&self) -> Vec<(PackageRef, VersionReq)> {
+        match self {
+            Self::Wit { .. } => self.unresolved_packages(),
+            Self::Wasm { decoded, .. } => packages_from_foreign_deps(
+                decoded
+                    .resolve()
+                    .packages
+                    .iter()
+                    .filter(|(id, _)| *id != decoded.package())
+                    .map(|(_, pkg)| pkg.name.clone()),
+            )
+            .collect(),
+        }
+    }
+
+    /// Gets the resolution related to the decoded dependency.
+    pub fn resolution(&self) -> &DependencyResolution {
+        match self {
+            Self::Wit { resolution, .. } | Self::Wasm { resolution, .. } => resolution,
+        }
+    }
+
     /// Gets the package name of the decoded dependency.
     pub fn package_name(&self) -> &PackageName {
         match self {
@@ -343,74 +430,200 @@ impl DecodedDependency<'_> {
     }
 }
 
+/// Where the version requirement of a registry dependency came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RequirementOrigin {
+    /// The requirement was added directly to the resolver.
+    Direct,
+    /// The requirement was set by an override, such as one in the manifest.
+    Override,
+    /// The requirement comes from a package reference in the WIT at the given path.
+    Wit(PathBuf),
+    /// The requirement comes from a package reference in the given resolved registry package.
+    Package(PackageSpec),
+}
+
+impl std::fmt::Display for RequirementOrigin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Direct => write!(f, "a direct dependency"),
+            Self::Override => write!(f, "an override"),
+            Self::Wit(path) => write!(f, "the WIT at `{}`", path.display()),
+            Self::Package(spec) => write!(f, "package `{spec}`"),
+        }
+    }
+}
+
+/// The error returned when a registry dependency can't be resolved. This explains where the
+/// requirement came from and which releases were considered.
+#[derive(Debug, Clone)]
+pub struct ResolutionError {
+    /// The registry package that couldn't be resolved.
+    pub package: PackageRef,
+    /// The version requirement that couldn't be satisfied.
+    pub requirement: VersionReq,
+    /// Where the requirement came from. If it came from another registry package, this is followed
+    /// by where the requirement of that package came from and so on.
+    pub origins: Vec<RequirementOrigin>,
+    /// The releases of the package in the registry, sorted by version. This is `None` if the
+    /// package was not found.
+    pub available: Option<Vec<VersionInfo>>,
+    /// The version the lock file pinned the requirement to, which wasn't found in the registry.
+    pub locked: Option<Version>,
+}
+
+impl std::fmt::Display for ResolutionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Some(available) = &self.available else {
+            write!(
+                f,
+                "package `{}` was not found in component registry",
+                self.package
+            )?;
+            return self.fmt_origins(f);
+        };
+        write!(
+            f,
+            "component registry package `{}` has no release matching version requirement `{}`",
+            self.package, self.requirement
+        )?;
+        self.fmt_origins(f)?;
+        if available.is_empty() {
+            write!(f, "\n  no releases are available")?;
+        } else {
+            let versions: Vec<_> = available
+                .iter()
+                .map(|info| {
+                    if info.yanked {
+                        format!("{} (yanked)", info.version)
+                    } else {
+                        info.version.to_string()
+                    }
+                })
+                .collect();
+            write!(f, "\n  available releases: {}", versions.join(", "))?;
+        }
+        for info in available
+            .iter()
+            .filter(|info| info.yanked && self.requirement.matches(&info.version))
+        {
+            write!(f, "\n  release {} matches but is yanked", info.version)?;
+        }
+        if let Some(locked) = &self.locked {
+            write!(
+                f,
+                "\n  the lock file pins release {locked}, which is no longer available"
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl ResolutionError {
+    fn fmt_origins(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for origin in &self.origins {
+            write!(f, "\n  required by {origin}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ResolutionError {}
+
+/// The default maximum number of concurrent registry requests made by [`DependencyResolver`].
+pub const DEFAULT_RESOLVE_CONCURRENCY: usize = 16;
+
 /// Used to resolve dependencies for a WIT package.
-pub struct DependencyResolver<'a> {
-    client: CachingClient<FileCache>,
+pub struct DependencyResolver<'a, T = FileCache> {
+    client: CachingClient<T>,
+    /// The client as the content source of registry resolutions
+    content: Arc<dyn ContentSource>,
     lock_file: Option<&'a LockFile>,
     packages: HashMap<PackageRef, Vec<VersionInfo>>,
-    dependencies: HashMap<PackageRef, RegistryDependency>,
+    /// Every requirement of a registry dependency. A package may be required at several versions
+    dependencies: HashMap<PackageRef, Vec<RegistryDependency>>,
+    /// Dependencies that were overridden, which ignore any other requirements added later
+    overridden: HashSet<PackageRef>,
     resolutions: DependencyResolutionMap,
+    concurrency: usize,
+    unify_semver: bool,
+    /// The exact versions of package references that were widened to caret requirements
+    widened: HashMap<(PackageRef, VersionReq), Version>,
+    /// Where the requirement that produced each registry resolution came from
+    origins: HashMap<PackageSpec, RequirementOrigin>,
+    /// Requirements that weren't added by a resolved registry package
+    root_requirements: Vec<(PackageRef, VersionReq)>,
+    /// The package references of each resolved registry package
+    package_requirements: HashMap<PackageSpec, Vec<(PackageRef, VersionReq)>>,
 }
 
-impl<'a> DependencyResolver<'a> {
+impl<'a, T: Cache + Send + Sync + 'static> DependencyResolver<'a, T> {
     /// Creates a new dependency resolver. If [`Config`] is `None`, then the resolver will be set to
-    /// offline mode and a lock file must be given as well. Anything that will require network
-    /// access will fail in offline mode.
+    /// offline mode. In offline mode, dependencies are resolved from the lock file or from the
+    /// versions and packages in the cache, and anything that will require network access will fail.
     pub fn new(
         config: Option<Config>,
         lock_file: Option<&'a LockFile>,
-        cache: FileCache,
+        cache: T,
     ) -> anyhow::Result<Self> {
-        if config.is_none() && lock_file.is_none() {
-            anyhow::bail!("lock file must be provided when offline mode is enabled");
-        }
-        let client = CachingClient::new(config.map(Client::new), cache);
-        Ok(DependencyResolver {
-            client,
+        Self::new_with_client(
+            CachingClient::new(config.map(Client::new), cache),
             lock_file,
-            resolutions: Default::default(),
-            packages: Default::default(),
-            dependencies: Default::default(),
-        })
+        )
     }
 
     /// Creates a new dependency resolver with the given client. This is useful when you already
-    /// have a client available. If the client is set to offline mode, then a lock file must be
-    /// given or this will error
+    /// have a client available. If the lock file is [frozen](LockMode::Frozen), the client is put
+    /// into offline mode.
     pub fn new_with_client(
-        client: CachingClient<FileCache>,
+        client: CachingClient<T>,
         lock_file: Option<&'a LockFile>,
     ) -> anyhow::Result<Self> {
-        if client.is_readonly() && lock_file.is_none() {
-            anyhow::bail!("lock file must be provided when offline mode is enabled");
-        }
+        let client = if lock_file.is_some_and(|lock| lock.mode() == LockMode::Frozen) {
+            client.into_offline()
+        } else {
+            client
+        };
         Ok(DependencyResolver {
+            content: Arc::new(client.clone()),
             client,
             lock_file,
             resolutions: Default::default(),
             packages: Default::default(),
             dependencies: Default::default(),
+            overridden: Default::default(),
+            concurrency: DEFAULT_RESOLVE_CONCURRENCY,
+            unify_semver: false,
+            widened: Default::default(),
+            origins: Default::default(),
+            root_requirements: Default::default(),
+            package_requirements: Default::default(),
         })
     }
 
-    /// Add a dependency to the resolver. If the dependency already exists, then it will be ignored.
-    /// To override an existing dependency, use [`override_dependency`](Self::override_dependency).
+    /// Add a dependency to the resolver. A registry dependency may be added several times with
+    /// different version requirements, in which case every required version is resolved. It is
+    /// ignored if the same requirement was already added, if it is already satisfied by a resolved
+    /// version, or if the dependency is local or overridden. To override an existing dependency,
+    /// use [`override_dependency`](Self::override_dependency).
     pub async fn add_dependency(
         &mut self,
         name: &PackageRef,
         dependency: &Dependency,
     ) -> Result<()> {
-        self.add_dependency_internal(name, dependency, false).await
+        self.add_dependency_internal(name, dependency, false, RequirementOrigin::Direct)
+            .await
     }
 
     /// Add a dependency to the resolver. If the dependency already exists, then it will be
-    /// overridden.
+    /// overridden, replacing all of its version requirements.
     pub async fn override_dependency(
         &mut self,
         name: &PackageRef,
         dependency: &Dependency,
     ) -> Result<()> {
-        self.add_dependency_internal(name, dependency, true).await
+        self.add_dependency_internal(name, dependency, true, RequirementOrigin::Override)
+            .await
     }
 
     async fn add_dependency_internal(
@@ -418,17 +631,20 @@ impl<'a> DependencyResolver<'a> {
         name: &PackageRef,
         dependency: &Dependency,
         force_override: bool,
+        origin: RequirementOrigin,
     ) -> Result<()> {
         match dependency {
             Dependency::Package(package) => {
+                if !matches!(origin, RequirementOrigin::Package(_)) {
+                    self.root_requirements
+                        .push((name.clone(), package.version.clone()));
+                }
                 // Dependency comes from a registry, add a dependency to the resolver
                 let registry_name = package.registry.as_deref().or_else(|| {
-                    self.client.client().ok().and_then(|client| {
-                        client
-                            .config()
-                            .resolve_registry(name)
-                            .map(|reg| reg.as_ref())
-                    })
+                    self.client
+                        .config()
+                        .and_then(|config| config.resolve_registry(name))
+                        .map(|reg| reg.as_ref())
                 });
                 let package_name = package.name.clone().unwrap_or_else(|| name.clone());
 
@@ -443,22 +659,55 @@ impl<'a> DependencyResolver<'a> {
                     _ => None,
                 };
 
+                let registry_dep = RegistryDependency {
+                    package: package_name,
+                    version: package.version.clone(),
+                    locked: locked.map(|l| (l.version.clone(), l.digest.clone())),
+                    origin,
+                };
+                if force_override {
+                    self.overridden.insert(name.clone());
+                    self.dependencies
+                        .insert(name.to_owned(), vec![registry_dep]);
+                    return Ok(());
+                }
+
                 // So if it wasn't already fetched first? then we'll try and resolve it later, and the override
                 // is not present there for some reason
-                if !force_override
-                    && (self.resolutions.contains_key(name) || self.dependencies.contains_key(name))
-                {
+                let local = self
+                    .resolutions
+                    .versions_of(name)
+                    .any(|res| matches!(res, DependencyResolution::Local(_)));
+                let pending = self
+                    .dependencies
+                    .get(name)
+                    .is_some_and(|deps| deps.iter().any(|dep| dep.version == package.version));
+                if self.overridden.contains(name) || local || pending {
                     tracing::debug!(%name, %dependency, "dependency already exists and override is not set, ignoring");
                     return Ok(());
                 }
-                self.dependencies.insert(
-                    name.to_owned(),
-                    RegistryDependency {
-                        package: package_name,
-                        version: package.version.clone(),
-                        locked: locked.map(|l| (l.version.clone(), l.digest.clone())),
-                    },
-                );
+                // A requirement that is satisfied by an already resolved version shares it
+                if let Some(res) = self
+                    .resolutions
+                    .values_mut()
+                    .filter_map(|res| match res {
+                        DependencyResolution::Registry(res)
+                            if &res.name == name && package.version.matches(&res.version) =>
+                        {
+                            Some(res)
+                        }
+                        _ => None,
+                    })
+                    .max_by(|a, b| a.version.cmp(&b.version))
+                {
+                    tracing::debug!(%name, %dependency, version = %res.version, "dependency is satisfied by an already resolved version");
+                    res.add_requirement(package.version.clone());
+                    return Ok(());
+                }
+                self.dependencies
+                    .entry(name.to_owned())
+                    .or_default()
+                    .push(registry_dep);
             }
             Dependency::Local(p) => {
                 let res = DependencyResolution::Local(LocalResolution {
@@ -473,7 +722,7 @@ impl<'a> DependencyResolver<'a> {
                 // We should definitely fix this and change where we resolve these things
                 let should_insert = force_override
                     || self.dependencies.contains_key(name)
-                    || !self.resolutions.contains_key(name);
+                    || self.resolutions.versions_of(name).next().is_none();
                 if !should_insert {
                     tracing::debug!(%name, "dependency already exists and registry override is not set, ignoring");
                     return Ok(());
@@ -488,11 +737,19 @@ impl<'a> DependencyResolver<'a> {
                 // local dependency and add those to the resolver before adding the dependency
                 let (_, packages) = get_packages(p)
                     .context("Error getting dependent packages from local dependency")?;
-                Box::pin(self.add_packages(packages))
-                    .await
-                    .context("Error adding packages to resolver for local dependency")?;
+                Box::pin(
+                    self.add_packages_with_origin(packages, RequirementOrigin::Wit(p.clone())),
+                )
+                .await
+                .context("Error adding packages to resolver for local dependency")?;
 
-                let prev = self.resolutions.insert(name.clone(), res);
+                let prev = self.resolutions.insert(
+                    PackageSpec {
+                        package: name.clone(),
+                        version: None,
+                    },
+                    res,
+                );
                 assert!(prev.is_none());
             }
         }
@@ -501,136 +758,497 @@ impl<'a> DependencyResolver<'a> {
     }
 
     /// A helper function for adding an iterator of package refs and their associated version
-    /// requirements to the resolver
+    /// requirements to the resolver. If [semver unification](Self::with_semver_unification) is
+    /// enabled, exact requirements are widened to caret requirements.
     pub async fn add_packages(
         &mut self,
         packages: impl IntoIterator<Item = (PackageRef, VersionReq)>,
     ) -> Result<()> {
-        for (package, req) in packages {
-            self.add_dependency(
+        self.add_packages_with_origin(packages, RequirementOrigin::Direct)
+            .await
+    }
+
+    /// Like [`add_packages`](Self::add_packages), but records where the requirements came from so
+    /// that a requirement that can't be satisfied can be traced back to its origin.
+    pub async fn add_packages_with_origin(
+        &mut self,
+        packages: impl IntoIterator<Item = (PackageRef, VersionReq)>,
+        origin: RequirementOrigin,
+    ) -> Result<()> {
+        for (package, mut req) in packages {
+            if self.unify_semver
+                && let Some((widened, version)) = widen_exact_requirement(&req)
+            {
+                self.widened
+                    .insert((package.clone(), widened.clone()), version);
+                req = widened;
+            }
+            self.add_dependency_internal(
                 &package,
                 &Dependency::Package(RegistryPackage {
                     name: Some(package.clone()),
                     version: req,
                     registry: None,
                 }),
+                false,
+                origin.clone(),
             )
             .await?;
         }
         Ok(())
     }
 
+    /// Sets the maximum number of registry requests that are made concurrently while resolving.
+    /// Defaults to [`DEFAULT_RESOLVE_CONCURRENCY`]. A limit of 0 is treated as 1.
+    pub fn with_concurrency(mut self, limit: usize) -> Self {
+        self.concurrency = limit.max(1);
+        self
+    }
+
+    /// Sets whether exact version requirements from package references (e.g. `ns:pkg@1.2.3`) are
+    /// treated as caret requirements (`^1.2.3`). Compatible versions of a package are then unified
+    /// to the single highest version that satisfies all of their requirements. Defaults to `false`.
+    ///
+    /// The requirements that were widened are reported by
+    /// [`DependencyResolutionMap::widened_requirements`].
+    pub fn with_semver_unification(mut self, enabled: bool) -> Self {
+        self.unify_semver = enabled;
+        self
+    }
+
     /// Resolve all dependencies.
     ///
-    /// This will download all dependencies that are not already present in client storage.
+    /// This will download all dependencies that are not already present in client storage. Version
+    /// listing and release lookups are done concurrently (see
+    /// [`with_concurrency`](Self::with_concurrency)), but the result does not depend on the order
+    /// in which requests complete.
+    ///
+    /// Registry packages may reference foreign packages without including them. Each resolved
+    /// registry package is decoded and any such packages are added as dependencies, repeating until
+    /// no new packages are found.
     ///
     /// Returns the dependency resolution map.
     pub async fn resolve(mut self) -> Result<DependencyResolutionMap> {
-        let mut resolutions = self.resolutions;
-        for (name, dependency) in self.dependencies.into_iter() {
-            // We need to clone a handle to the client because we mutably borrow self below. Might
-            // be worth replacing the mutable borrow with a RwLock down the line.
-            let client = self.client.clone();
-
-            let (selected_version, digest) = if client.is_readonly() {
-                dependency
-                    .locked
-                    .as_ref()
-                    .map(|(ver, digest)| (ver, Some(digest)))
-                    .ok_or_else(|| {
-                        anyhow::anyhow!("Couldn't find locked dependency while in offline mode")
-                    })?
+        while !self.dependencies.is_empty() {
+            let dependencies = std::mem::take(&mut self.dependencies);
+            let mut transitive = Vec::new();
+            for (resolution, origin, packages) in
+                self.resolve_registry_dependencies(dependencies).await?
+            {
+                let spec = PackageSpec {
+                    package: resolution.name.clone(),
+                    version: Some(resolution.version.clone()),
+                };
+                self.package_requirements
+                    .entry(spec.clone())
+                    .or_insert_with(|| packages.clone());
+                transitive.push((RequirementOrigin::Package(spec.clone()), packages));
+                // Different requirements may resolve to the same version, in which case they share
+                // the first resolution
+                match self.resolutions.entry(spec) {
+                    hash_map::Entry::Occupied(mut entry) => {
+                        if let DependencyResolution::Registry(existing) = entry.get_mut() {
+                            existing.add_requirement(resolution.requirement);
+                        }
+                    }
+                    hash_map::Entry::Vacant(entry) => {
+                        self.origins.insert(entry.key().clone(), origin);
+                        entry.insert(DependencyResolution::Registry(resolution));
+                    }
+                }
+            }
+            // Packages that are already resolved or pending are ignored here, so this only adds
+            // dependencies that haven't been seen yet
+            for (origin, packages) in transitive {
+                self.add_packages_with_origin(packages, origin).await?;
+            }
+        }
+
+        if self.unify_semver {
+            self.unify_versions();
+        }
+        self.resolutions.widened = self.widened_requirements();
+
+        Ok(self.resolutions)
+    }
+
+    /// Builds the error for a registry dependency that couldn't be resolved, tracing its
+    /// requirement back through the packages that required it.
+    fn resolution_error(
+        &self,
+        dependency: &RegistryDependency,
+        available: Option<Vec<VersionInfo>>,
+    ) -> ResolutionError {
+        let mut origins = vec![dependency.origin.clone()];
+        let mut seen = HashSet::new();
+        while let Some(RequirementOrigin::Package(spec)) = origins.last() {
+            // Resolution of a package can't depend on itself, but be safe against cycles anyway
+            if !seen.insert(spec.clone()) {
+                break;
+            }
+            let Some(origin) = self.origins.get(spec) else {
+                break;
+            };
+            origins.push(origin.clone());
+        }
+        let mut available = available;
+        if let Some(available) = available.as_mut() {
+            available.sort_by(|a, b| a.version.cmp(&b.version));
+        }
+        ResolutionError {
+            package: dependency.package.clone(),
+            requirement: dependency.version.clone(),
+            origins,
+            available,
+            locked: dependency
+                .locked
+                .as_ref()
+                .map(|(version, _)| version.clone()),
+        }
+    }
+
+    /// Folds every resolved version of a package into the highest resolved version that satisfies
+    /// all of its requirements. Resolutions that were only required by a folded version are
+    /// removed as well.
+    fn unify_versions(&mut self) {
+        let mut unified = false;
+        let mut versions: BTreeMap<PackageRef, Vec<Version>> = BTreeMap::new();
+        for (spec, res) in self.resolutions.iter() {
+            if let (DependencyResolution::Registry(_), Some(version)) = (res, &spec.version) {
+                versions
+                    .entry(spec.package.clone())
+                    .or_default()
+                    .push(version.clone());
+            }
+        }
+
+        for (name, mut versions) in versions {
+            versions.sort_by(|a, b| b.cmp(a));
+            for (i, lower) in versions.iter().enumerate().skip(1) {
+                let spec = |version: &Version| PackageSpec {
+                    package: name.clone(),
+                    version: Some(version.clone()),
+                };
+                let Some(DependencyResolution::Registry(res)) = self.resolutions.get(&spec(lower))
+                else {
+                    continue;
+                };
+                let requirements: Vec<_> = res.requirements().cloned().collect();
+                let Some(higher) = versions[..i].iter().find(|higher| {
+                    self.resolutions.contains_key(&spec(higher))
+                        && requirements.iter().all(|req| req.matches(higher))
+                }) else {
+                    continue;
+                };
+                tracing::debug!(%name, %lower, %higher, "unifying dependency versions");
+                self.resolutions.remove(&spec(lower));
+                unified = true;
+                if let Some(DependencyResolution::Registry(res)) =
+                    self.resolutions.get_mut(&spec(higher))
+                {
+                    for req in requirements {
+                        res.add_requirement(req);
+                    }
+                }
+            }
+        }
+        if unified {
+            self.remove_unreachable();
+        }
+    }
+
+    /// Removes the registry resolutions that can no longer be reached from the root requirements,
+    /// such as the dependencies of a version that was unified away.
+    fn remove_unreachable(&mut self) {
+        let mut reachable: HashSet<PackageSpec> = self
+            .resolutions
+            .iter()
+            .filter(|(_, res)| matches!(res, DependencyResolution::Local(_)))
+            .map(|(spec, _)| spec.clone())
+            .collect();
+        let mut queue: Vec<_> = self
+            .root_requirements
+            .iter()
+            .filter_map(|(name, req)| self.satisfying_resolution(name, req))
+            .collect();
+        while let Some(spec) = queue.pop() {
+            if !reachable.insert(spec.clone()) {
+                continue;
+            }
+            if let Some(requirements) = self.package_requirements.get(&spec) {
+                queue.extend(
+                    requirements
+                        .iter()
+                        .filter_map(|(name, req)| self.satisfying_resolution(name, req)),
+                );
+            }
+        }
+        self.resolutions.retain(|spec, _| {
+            let keep = reachable.contains(spec);
+            if !keep {
+                tracing::debug!(%spec, "removing dependency that is no longer required");
+            }
+            keep
+        });
+    }
+
+    /// Returns the highest resolved version of the package that satisfies the requirement, with
+    /// exact requirements widened like [`add_packages`](Self::add_packages) does.
+    fn satisfying_resolution(&self, name: &PackageRef, req: &VersionReq) -> Option<PackageSpec> {
+        let widened = widen_exact_requirement(req).map(|(widened, _)| widened);
+        let req = widened.as_ref().unwrap_or(req);
+        self.resolutions
+            .iter()
+            .filter(|(spec, _)| &spec.package == name)
+            .filter(|(spec, _)| spec.version.as_ref().is_none_or(|v| req.matches(v)))
+            .map(|(spec, _)| spec)
+            .max_by(|a, b| a.version.cmp(&b.version))
+            .cloned()
+    }
+
+    /// Returns the widened requirements that resolved to a different version than the one that was
+    /// referenced.
+    fn widened_requirements(&self) -> Vec<WidenedRequirement> {
+        let mut widened: Vec<_> = self
+            .resolutions
+            .values()
+            .filter_map(|res| match res {
+                DependencyResolution::Registry(res) => Some(res),
+                DependencyResolution::Local(_) => None,
+            })
+            .flat_map(|res| {
+                res.requirements().filter_map(|req| {
+                    let referenced = self.widened.get(&(res.name.clone(), req.clone()))?;
+                    (referenced != &res.version).then(|| WidenedRequirement {
+                        name: res.name.clone(),
+                        referenced: referenced.clone(),
+                        requirement: req.clone(),
+                        version: res.version.clone(),
+                    })
+                })
+            })
+            .collect();
+        widened.sort_by(|a, b| (&a.name, &a.referenced).cmp(&(&b.name, &b.referenced)));
+        widened
+    }
+
+    /// Resolves the given registry dependencies, returning each resolution along with the origin
+    /// of its requirement and the foreign packages it references but does not include.
+    async fn resolve_registry_dependencies(
+        &mut self,
+        dependencies: HashMap<PackageRef, Vec<RegistryDependency>>,
+    ) -> Result<
+        Vec<(
+            RegistryResolution,
+            RequirementOrigin,
+            Vec<(PackageRef, VersionReq)>,
+        )>,
+    > {
+        let client = &self.client;
+        let content = &self.content;
+        let concurrency = self.concurrency;
+        // Sort the dependencies so that the same error is reported first no matter in which order
+        // the requests complete
+        let mut dependencies: Vec<_> = dependencies
+            .into_iter()
+            .flat_map(|(name, deps)| deps.into_iter().map(move |dep| (name.clone(), dep)))
+            .collect();
+        dependencies.sort_by_cached_key(|(name, dep)| (name.clone(), dep.version.to_string()));
+
+        // In offline mode, locked dependencies are resolved from the lock file alone and the
+        // versions of any other dependency can only come from the cache
+        let missing = dependencies
+            .iter()
+            .filter(|(_, dependency)| !client.is_readonly() || dependency.locked.is_none())
+            .map(|(_, dependency)| &dependency.package)
+            .filter(|package| !self.packages.contains_key(*package))
+            .cloned()
+            .collect::<BTreeSet<_>>();
+        let loaded: Vec<_> = futures_util::stream::iter(missing)
+            .map(|package| async move {
+                let versions = load_versions(client, &package)
+                    .await
+                    .with_context(|| format!("package: {package}"))?;
+                anyhow::Ok((package, versions))
+            })
+            .buffered(concurrency)
+            .try_collect()
+            .await?;
+        self.packages.extend(
+            loaded
+                .into_iter()
+                .filter_map(|(package, versions)| Some((package, versions?))),
+        );
+
+        let mut selected: Vec<(
+            PackageRef,
+            RegistryDependency,
+            Version,
+            Option<ContentDigest>,
+        )> = Vec::with_capacity(dependencies.len());
+        let mut shared = Vec::new();
+        for (name, dependency) in dependencies {
+            let (selected_version, digest) = if let Some((version, digest)) =
+                dependency.locked.as_ref().filter(|_| client.is_readonly())
+            {
+                (version, Some(digest))
             } else {
-                let versions =
-                    load_package(&mut self.packages, &self.client, dependency.package.clone())
-                        .await
-                        .with_context(|| format!("package: {}", dependency.package.clone()))?
-                        .with_context(|| {
-                            format!(
-                                "package `{name}` was not found in component registry",
-                                name = dependency.package
-                            )
-                        })?;
+                let Some(versions) = self.packages.get(&dependency.package) else {
+                    return Err(self.resolution_error(&dependency, None).into());
+                };
 
-                match &dependency.locked {
+                let selected_version = match &dependency.locked {
                     Some((version, digest)) => {
-                        // The dependency had a lock file entry, so attempt to do an exact match first
-                        let exact_req = VersionReq {
-                            comparators: vec![Comparator {
-                                op: Op::Exact,
-                                major: version.major,
-                                minor: Some(version.minor),
-                                patch: Some(version.patch),
-                                pre: version.pre.clone(),
-                            }],
-                        };
-
-                        // If an exact match can't be found, fallback to the latest release to satisfy
-                        // the version requirement; this can happen when packages are yanked. If we did
-                        // find an exact match, return the digest for comparison after fetching the
-                        // release
-                        find_latest_release(versions, &exact_req)
+                        // The dependency had a lock file entry, so attempt to do an exact match
+                        // first. Yanked versions are still allowed here so that existing lock files
+                        // keep working.
+                        //
+                        // If the locked version can't be found, fallback to the latest release to
+                        // satisfy the version requirement. If we did find an exact match, return
+                        // the digest for comparison after fetching the release
+                        versions
+                            .iter()
+                            .find(|info| &info.version == version)
                             .map(|v| (&v.version, Some(digest)))
-                            .or_else(|| find_latest_release(versions, &dependency.version).map(|v| (&v.version, None)))
+                            .or_else(|| {
+                                find_latest_release(versions, &dependency.version)
+                                    .map(|v| (&v.version, None))
+                            })
+                    }
+                    None if self.unify_semver => {
+                        // Prefer a version of the package that was already selected so that
+                        // requirements which can share a version don't each pull in another one
+                        let resolved = self.resolutions.values().filter_map(|res| match res {
+                            DependencyResolution::Registry(res) => {
+                                Some((&res.package, &res.version))
+                            }
+                            DependencyResolution::Local(_) => None,
+                        });
+                        let existing = selected
+                            .iter()
+                            .map(|(_, dep, version, _)| (&dep.package, version))
+                            .chain(resolved)
+                            .filter(|(package, version)| {
+                                *package == &dependency.package
+                                    && dependency.version.matches(version)
+                            })
+                            .map(|(_, version)| version)
+                            .max();
+                        match existing {
+                            Some(version) => versions.iter().find(|info| &info.version == version),
+                            None => find_latest_release(versions, &dependency.version),
                         }
-                    None => find_latest_release(versions, &dependency.version).map(|v| (&v.version, None)),
-                }.with_context(||
-                    format!(
-                        "component registry package `{name}` has no release matching version requirement `{version}`",
-                        name = dependency.package,
-                        version = dependency.version
-                    )
-                )?
+                        .map(|v| (&v.version, None))
+                    }
+                    None => find_latest_release(versions, &dependency.version)
+                        .map(|v| (&v.version, None)),
+                };
+                match selected_version {
+                    Some(selected) => selected,
+                    None => {
+                        return Err(self
+                            .resolution_error(&dependency, Some(versions.clone()))
+                            .into());
+                    }
+                }
             };
+            let selected_version = selected_version.clone();
+            let digest = digest.cloned();
+            // Only fetch each release once, requirements that selected the same release share it
+            if selected.iter().any(|(_, dep, version, _)| {
+                dep.package == dependency.package && version == &selected_version
+            }) {
+                shared.push((name, dependency, selected_version, digest));
+            } else {
+                selected.push((name, dependency, selected_version, digest));
+            }
+        }
+
+        let mut resolved: Vec<_> = futures_util::stream::iter(selected)
+            .map(|(name, dependency, selected_version, digest)| {
+                async move {
+                    let release = client
+                        .get_release(&dependency.package, &selected_version)
+                        .await?;
+                    if let Some(digest) = digest
+                        && release.content_digest != digest
+                    {
+                        bail!(
+                            "component registry package `{name}` (v`{version}`) has digest `{content}` but the lock file specifies digest `{digest}`",
+                            name = dependency.package,
+                            version = release.version,
+                            content = release.content_digest,
+                        );
+                    }
+                    let registry = client
+                        .config()
+                        .and_then(|config| config.resolve_registry(&name))
+                        .map(ToString::to_string);
+                    let resolution = RegistryResolution {
+                        name,
+                        package: dependency.package,
+                        registry,
+                        requirement: dependency.version,
+                        other_requirements: Vec::new(),
+                        version: release.version,
+                        digest: release.content_digest,
+                        client: content.clone(),
+                    };
+                    let packages = DependencyResolution::Registry(resolution.clone())
+                        .decode()
+                        .await
+                        .with_context(|| {
+                            format!(
+                                "failed to decode component registry package `{name}`",
+                                name = resolution.package
+                            )
+                        })?
+                        .unresolved_packages();
+                    anyhow::Ok((resolution, dependency.origin, packages))
+                }
+            })
+            .buffered(concurrency)
+            .try_collect()
+            .await?;
 
-            // We need to clone a handle to the client because we mutably borrow self above. Might
-            // be worth replacing the mutable borrow with a RwLock down the line.
-            let release = client
-                .get_release(&dependency.package, selected_version)
-                .await?;
+        for (name, dependency, version, digest) in shared {
+            let mut resolution = resolved
+                .iter()
+                .map(|(res, _, _)| res)
+                .find(|res| res.package == dependency.package && res.version == version)
+                .expect("shared release should have been fetched")
+                .clone();
             if let Some(digest) = digest
-                && &release.content_digest != digest
+                && resolution.digest != digest
             {
                 bail!(
                     "component registry package `{name}` (v`{version}`) has digest `{content}` but the lock file specifies digest `{digest}`",
                     name = dependency.package,
-                    version = release.version,
-                    content = release.content_digest,
+                    content = resolution.digest,
                 );
             }
-            let resolution = RegistryResolution {
-                name: name.clone(),
-                package: dependency.package.clone(),
-                registry: self.client.client().ok().and_then(|client| {
-                    client
-                        .config()
-                        .resolve_registry(&name)
-                        .map(ToString::to_string)
-                }),
-                requirement: dependency.version.clone(),
-                version: release.version.clone(),
-                digest: release.content_digest.clone(),
-                client: self.client.clone(),
-            };
-            resolutions.insert(name, DependencyResolution::Registry(resolution));
+            resolution.registry = client
+                .config()
+                .and_then(|config| config.resolve_registry(&name))
+                .map(ToString::to_string);
+            resolution.name = name;
+            resolution.requirement = dependency.version;
+            resolution.other_requirements = Vec::new();
+            // The packages referenced by the release are already reported by its first resolution
+            resolved.push((resolution, dependency.origin, Vec::new()));
         }
-
-        Ok(resolutions)
+        Ok(resolved)
     }
 }
 
-async fn load_package<'b>(
-    packages: &'b mut HashMap<PackageRef, Vec<VersionInfo>>,
-    client: &CachingClient<FileCache>,
-    package: PackageRef,
-) -> Result<Option<&'b Vec<VersionInfo>>> {
-    match packages.entry(package) {
-        hash_map::Entry::Occupied(e) => Ok(Some(e.into_mut())),
-        hash_map::Entry::Vacant(e) => match client.list_all_versions(e.key()).await {
-            Ok(p) => Ok(Some(e.insert(p))),
-            Err(WasmPkgError::PackageNotFound) => Ok(None),
-            Err(err) => Err(err.into()),
-        },
+async fn load_versions<T: Cache>(
+    client: &CachingClient<T>,
+    package: &PackageRef,
+) -> Result<Option<Vec<VersionInfo>>> {
+    match client.list_all_versions(package).await {
+        Ok(versions) => Ok(Some(versions)),
+        Err(WasmPkgError::PackageNotFound) => Ok(None),
+        Err(err) => Err(err.into()),
     }
 }
 
@@ -641,6 +1259,33 @@ struct RegistryDependency {
     package: PackageRef,
     version: VersionReq,
     locked: Option<(Version, ContentDigest)>,
+    origin: RequirementOrigin,
+}
+
+/// Widens an exact requirement (as used for versioned package references) to the caret requirement
+/// of the same version, returning the widened requirement and the referenced version.
+fn widen_exact_requirement(req: &VersionReq) -> Option<(VersionReq, Version)> {
+    let [comparator] = req.comparators.as_slice() else {
+        return None;
+    };
+    let (Op::Exact, Some(minor), Some(patch)) = (comparator.op, comparator.minor, comparator.patch)
+    else {
+        return None;
+    };
+    let version = Version {
+        major: comparator.major,
+        minor,
+        patch,
+        pre: comparator.pre.clone(),
+        build: Default::default(),
+    };
+    let widened = VersionReq {
+        comparators: vec![Comparator {
+            op: Op::Caret,
+            ..comparator.clone()
+        }],
+    };
+    Some((widened, version))
 }
 
 fn find_latest_release<'a>(
@@ -660,31 +1305,68 @@ fn find_latest_release<'a>(
 
 /// Represents a map of dependency resolutions.
 ///
-/// The key to the map is the package name of the dependency.
+/// The key to the map is the package name of the dependency along with the resolved version, so
+/// the same package can be resolved at several versions. Local dependencies don't have a resolved
+/// version.
 #[derive(Debug, Clone, Default)]
-pub struct DependencyResolutionMap(HashMap<PackageRef, DependencyResolution>);
+pub struct DependencyResolutionMap {
+    resolutions: HashMap<PackageSpec, DependencyResolution>,
+    /// Widened requirements that resolved to a different version than the one referenced
+    widened: Vec<WidenedRequirement>,
+}
 
-impl AsRef<HashMap<PackageRef, DependencyResolution>> for DependencyResolutionMap {
-    fn as_ref(&self) -> &HashMap<PackageRef, DependencyResolution> {
-        &self.0
+/// A package reference whose exact version requirement was widened to a caret requirement and
+/// resolved to a different version. See [`DependencyResolver::with_semver_unification`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WidenedRequirement {
+    /// The name of the dependency.
+    pub name: PackageRef,
+    /// The version of the package reference.
+    pub referenced: Version,
+    /// The widened version requirement.
+    pub requirement: VersionReq,
+    /// The version the requirement resolved to.
+    pub version: Version,
+}
+
+impl AsRef<HashMap<PackageSpec, DependencyResolution>> for DependencyResolutionMap {
+    fn as_ref(&self) -> &HashMap<PackageSpec, DependencyResolution> {
+        &self.resolutions
     }
 }
 
 impl Deref for DependencyResolutionMap {
-    type Target = HashMap<PackageRef, DependencyResolution>;
+    type Target = HashMap<PackageSpec, DependencyResolution>;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.resolutions
     }
 }
 
 impl DerefMut for DependencyResolutionMap {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+        &mut self.resolutions
     }
 }
 
 impl DependencyResolutionMap {
+    /// Returns the package references whose version requirement was widened and that resolved to a
+    /// different version than the one referenced, sorted by name.
+    pub fn widened_requirements(&self) -> &[WidenedRequirement] {
+        &self.widened
+    }
+
+    /// Returns the resolutions of every version of the given dependency.
+    pub fn versions_of<'b>(
+        &'b self,
+        name: &'b PackageRef,
+    ) -> impl Iterator<Item = &'b DependencyResolution> + 'b {
+        self.resolutions
+            .iter()
+            .filter(move |(spec, _)| &spec.package == name)
+            .map(|(_, resolution)| resolution)
+    }
+
     /// Fetch all dependencies and ensure there are no circular dependencies. Returns the decoded
     /// dependencies (sorted topologically), ready to use for output or adding to a [`Resolve`].
     pub async fn decode_dependencies(
@@ -692,7 +1374,7 @@ impl DependencyResolutionMap {
     ) -> Result<IndexMap<PackageName, DecodedDependency<'_>>> {
         // Start by decoding all of the dependencies
         let mut deps = IndexMap::new();
-        for (name, resolution) in self.0.iter() {
+        for (name, resolution) in self.resolutions.iter() {
             let decoded = resolution.decode().await?;
             if let Some(prev) = deps.insert(decoded.package_name().clone(), decoded) {
                 anyhow::bail!(
@@ -702,6 +1384,13 @@ impl DependencyResolutionMap {
             }
         }
 
+        let available = available_packages(&deps);
+        for dep in deps.values_mut() {
+            if let DecodedDependency::Wit { package, .. } = dep {
+                remap_compatible_references(package, &available);
+            }
+        }
+
         // Do a topological sort of the dependencies
         let mut order = IndexSet::new();
         let mut visiting = HashSet::new();
@@ -731,12 +1420,13 @@ impl DependencyResolutionMap {
         let deps = self.decode_dependencies().await?;
 
         // Parse the root package itself
-        let root = UnresolvedPackageGroup::parse_dir(&dir).with_context(|| {
+        let mut root = UnresolvedPackageGroup::parse_dir(&dir).with_context(|| {
             format!(
                 "failed to parse package from directory `{dir}`",
                 dir = dir.as_ref().display()
             )
         })?;
+        remap_compatible_references(&mut root, &available_packages(&deps));
 
         let mut source_files: Vec<_> = root
             .source_map
@@ -789,6 +1479,70 @@ impl DependencyResolutionMap {
     }
 }
 
+/// Returns the names of all packages that are defined by the given dependencies, including any
+/// packages they include.
+fn available_packages(deps: &IndexMap<PackageName, DecodedDependency>) -> HashSet<PackageName> {
+    deps.values()
+        .flat_map(|dep| -> Vec<PackageName> {
+            match dep {
+                DecodedDependency::Wit { package, .. } => std::iter::once(&package.main)
+                    .chain(&package.nested)
+                    .map(|pkg| pkg.name.clone())
+                    .collect(),
+                DecodedDependency::Wasm { decoded, .. } => decoded
+                    .resolve()
+                    .packages
+                    .iter()
+                    .map(|(_, pkg)| pkg.name.clone())
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+/// WIT requires a package reference to match the version of the package exactly. When semver
+/// compatible versions are unified, a reference may be to a version that isn't available, so point
+/// it at the highest available semver compatible version instead.
+fn remap_compatible_references(
+    group: &mut UnresolvedPackageGroup,
+    available: &HashSet<PackageName>,
+) {
+    for pkg in std::iter::once(&mut group.main).chain(&mut group.nested) {
+        let mut renames = HashMap::new();
+        for name in pkg.foreign_deps.keys() {
+            let Some(version) = name.version.as_ref() else {
+                continue;
+            };
+            if available.contains(name) {
+                continue;
+            }
+            let Ok(req) = VersionReq::parse(&format!("^{version}")) else {
+                continue;
+            };
+            let compatible = available
+                .iter()
+                .filter(|other| other.namespace == name.namespace && other.name == name.name)
+                .filter(|other| other.version.as_ref().is_some_and(|v| req.matches(v)))
+                .max_by(|a, b| a.version.cmp(&b.version));
+            // Two references that point at the same package can't be merged, so leave those alone
+            // and let resolving the package report the missing version
+            if let Some(compatible) = compatible
+                && !pkg.foreign_deps.contains_key(compatible)
+                && !renames.values().any(|renamed| renamed == compatible)
+            {
+                renames.insert(name.clone(), compatible.clone());
+            }
+        }
+        if renames.is_empty() {
+            continue;
+        }
+        pkg.foreign_deps = std::mem::take(&mut pkg.foreign_deps)
+            .into_iter()
+            .map(|(name, items)| (renames.remove(&name).unwrap_or(name), items))
+            .collect();
+    }
+}
+
 fn visit<'a>(
     dep: &'a DecodedDependency<'a>,
     deps: &'a IndexMap<PackageName, DecodedDependency>,
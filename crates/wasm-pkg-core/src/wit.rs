@@ -13,7 +13,7 @@ use semver::{Version, VersionReq};
 use wasm_metadata::{AddMetadata, AddMetadataField};
 use wasm_pkg_client::{
     PackageRef,
-    caching::{CachingClient, FileCache},
+    caching::{Cache, CachingClient},
 };
 use wasm_pkg_common::package::PackageSpec;
 use wit_component::WitPrinter;
@@ -25,7 +25,7 @@ use crate::{
     resolver::{
         DecodedDependency, Dependency, DependencyGraph, DependencyResolution,
         DependencyResolutionMap, DependencyResolver, LocalPackageIndex, LocalResolution,
-        RegistryPackage,
+        RegistryPackage, RequirementOrigin,
     },
 };
 
@@ -57,17 +57,17 @@ impl FromStr for OutputType {
 
 /// Builds a WIT package given the manifest and directory to parse. Will update the given lock
 /// file with the resolved dependencies but will not write it to disk.
-pub async fn build_package(
+pub async fn build_package<T: Cache + Send + Sync + 'static>(
     manifest: &Manifest,
     wit_dir: impl AsRef<Path>,
     lock_file: &mut LockFile,
-    client: CachingClient<FileCache>,
+    client: CachingClient<T>,
 ) -> Result<(PackageRef, Option<Version>, Vec<u8>)> {
     let dependencies = resolve_dependencies(manifest, &wit_dir, Some(lock_file), client)
         .await
         .context("Unable to resolve dependencies")?;
 
-    lock_file.update_dependencies(&dependencies);
+    lock_file.update_dependencies(&dependencies)?;
 
     let (resolve, pkg_id) = dependencies
         .generate_resolve(wit_dir.as_ref())
@@ -130,20 +130,21 @@ pub async fn build_package(
 
 /// Fetches and optionally updates all dependencies for the given path and writes them in the
 /// specified format. The lock file will be updated with the resolved dependencies but will not be
-/// written to disk.
+/// written to disk. Returns the resolved dependencies.
 ///
 /// This is mostly a convenience wrapper around [`resolve_dependencies`] and [`populate_dependencies`].
-pub async fn fetch_dependencies(
+pub async fn fetch_dependencies<T: Cache + Send + Sync + 'static>(
     manifest: &Manifest,
     wit_dir: impl AsRef<Path>,
     lock_file: &mut LockFile,
-    client: CachingClient<FileCache>,
+    client: CachingClient<T>,
     output: OutputType,
-) -> Result<()> {
+) -> Result<DependencyResolutionMap> {
     // Don't pass lock file if update is true
     let dependencies = resolve_dependencies(manifest, &wit_dir, Some(lock_file), client).await?;
-    lock_file.update_dependencies(&dependencies);
-    populate_dependencies(wit_dir, &dependencies, output).await
+    lock_file.update_dependencies(&dependencies)?;
+    populate_dependencies(wit_dir, &dependencies, output).await?;
+    Ok(dependencies)
 }
 
 /// Generate the list of all packages and their version requirement from the given path (a directory
@@ -273,13 +274,17 @@ pub(crate) fn get_local_dependencies(
 /// This will configure the resolver, override any dependencies from manifest and resolve the
 /// dependency map. This map can then be used in various other functions for fetching the
 /// dependencies and/or building a final resolved package.
-pub async fn resolve_dependencies(
+pub async fn resolve_dependencies<T: Cache + Send + Sync + 'static>(
     manifest: &Manifest,
     path: impl AsRef<Path>,
     lock_file: Option<&LockFile>,
-    client: CachingClient<FileCache>,
+    client: CachingClient<T>,
 ) -> Result<DependencyResolutionMap> {
-    let mut resolver = DependencyResolver::new_with_client(client, lock_file)?;
+    let mut resolver = DependencyResolver::new_with_client(client, lock_file)?
+        .with_semver_unification(manifest.unify_semver());
+    if let Some(limit) = manifest.resolve_concurrency() {
+        resolver = resolver.with_concurrency(limit);
+    }
     // add deps from manifest first in case they're local deps and then add deps from the directory
     if let Some(overrides) = manifest.overrides.as_ref() {
         tracing::debug!("detected manifest overrides");
@@ -307,14 +312,20 @@ pub async fn resolve_dependencies(
             };
 
             tracing::debug!(dependency = %dep);
+            // Overrides apply to every reference to the package, no matter which version it requires
             resolver
-                .add_dependency(&pkg, &dep)
+                .override_dependency(&pkg, &dep)
                 .await
                 .with_context(|| format!("unable to add dependency {dep}"))?;
         }
     }
-    let (_spec, packages) = get_packages(path)?;
-    resolver.add_packages(packages).await?;
+    let (_spec, packages) = get_packages(&path)?;
+    resolver
+        .add_packages_with_origin(
+            packages,
+            RequirementOrigin::Wit(path.as_ref().to_path_buf()),
+        )
+        .await?;
     resolver.resolve().await
 }
 
@@ -374,18 +385,22 @@ async fn write_wasm_deps(
                 tokio::fs::create_dir_all(&output_path).await?;
                 write_local_dep(local, output_path).await?;
             }
-            // This case shouldn't happen because registries only support wit packages. We can't get
-            // a resolve from the unresolved group, so error out here. Ideally we could print the
-            // unresolved group, but WitPrinter doesn't support that yet
+            // A registry package that is plain WIT can't be encoded on its own since its foreign
+            // dependencies are separate packages, so write out the WIT as is. The resolver has
+            // already added those foreign dependencies, so they are written alongside it
             DecodedDependency::Wit {
-                resolution: DependencyResolution::Registry(_),
+                resolution: DependencyResolution::Registry(registry),
                 ..
             } => {
-                anyhow::bail!("Unable to resolve dependency, this is a programmer error");
+                tokio::fs::create_dir_all(&output_path).await?;
+                let mut reader = registry.fetch().await?;
+                let mut output_file =
+                    tokio::fs::File::create(output_path.join("package.wit")).await?;
+                tokio::io::copy(&mut reader, &mut output_file).await?;
+                output_file.sync_all().await?;
             }
-            // Right now WIT packages include all of their dependencies, so we don't need to fetch
-            // those too. In the future, we'll need to look for unsatisfied dependencies and fetch
-            // them
+            // Wasm encoded packages include all of their dependencies, so any packages a registry
+            // dependency references without including are resolved as their own dependencies
             DecodedDependency::Wasm { resolution, .. } => {
                 // This is going to be written to a single file, so we don't create a directory here
                 // NOTE(thomastaylor312): This janky looking thing is to avoid chopping off the
@@ -417,7 +432,7 @@ async fn write_wasm_deps(
     Ok(())
 }
 
-fn packages_from_foreign_deps(
+pub(crate) fn packages_from_foreign_deps(
     deps: impl IntoIterator<Item = PackageName>,
 ) -> impl Iterator<Item = (PackageRef, VersionReq)> {
     deps.into_iter().filter_map(|dep| {
@@ -1,5 +1,7 @@
 // NOTE: test "lib" code needs to be under ./tests/common/mod.rs otherwise there will
 // be false clippy positives since ./tests/common.rs will be treated as a runnable test target
+// Not every test target uses every helper
+#![allow(dead_code)]
 use std::path::{Path, PathBuf};
 
 use tempfile::TempDir;
@@ -24,6 +26,54 @@ pub(crate) async fn get_client() -> anyhow::Result<(TempDir, CachingClient<FileC
     Ok((cache_temp_dir, CachingClient::new(Some(client), cache)))
 }
 
+/// Returns a config that uses a `local` registry rooted at the given directory as the default
+/// registry
+pub(crate) fn local_registry_config(root: &Path) -> anyhow::Result<Config> {
+    Ok(Config::from_toml(&format!(
+        r#"
+default_registry = "local"
+
+[registry."local"]
+type = "local"
+
+[registry."local".local]
+root = '{}'
+"#,
+        root.display(),
+    ))?)
+}
+
+/// Returns a caching client that uses a `local` registry rooted at the given directory as the
+/// default registry
+pub(crate) async fn get_local_client(
+    root: &Path,
+) -> anyhow::Result<(TempDir, CachingClient<FileCache>)> {
+    let config = local_registry_config(root)?;
+    let cache_temp_dir = tempfile::tempdir()?;
+    let cache = FileCache::new(cache_temp_dir.path()).await?;
+
+    Ok((
+        cache_temp_dir,
+        CachingClient::new(Some(Client::new(config)), cache),
+    ))
+}
+
+/// Writes a release with the given contents directly into a `local` registry rooted at `root`
+pub(crate) async fn write_local_release(
+    root: &Path,
+    package: &str,
+    version: &str,
+    contents: &[u8],
+) -> anyhow::Result<()> {
+    let (namespace, name) = package
+        .split_once(':')
+        .ok_or_else(|| anyhow::anyhow!("invalid package name {package}"))?;
+    let dir = root.join(namespace).join(name);
+    tokio::fs::create_dir_all(&dir).await?;
+    tokio::fs::write(dir.join(format!("{version}.wasm")), contents).await?;
+    Ok(())
+}
+
 /// Loads the fixture with the given name into a temporary directory. This will copy the fixture from the tests/fixtures directory into a temporary directory and return the tempdir containing that directory (and its path)
 pub(crate) async fn load_fixture(fixture: &str) -> anyhow::Result<(TempDir, PathBuf)> {
     let temp_dir = tempfile::tempdir()?;
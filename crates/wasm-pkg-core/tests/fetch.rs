@@ -1,10 +1,18 @@
-use std::{collections::HashMap, path::Path};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
 
 use rstest::rstest;
 use tokio::process::Command;
+use wasm_pkg_client::{
+    Client,
+    caching::{CachingClient, InMemoryCache},
+};
 use wasm_pkg_core::{
     lock::LockFile,
-    manifest::{Manifest, Override},
+    manifest::{Manifest, Override, ResolverSettings},
+    resolver::{DependencyResolutionMap, RequirementOrigin, ResolutionError},
     wit::{self, OutputType},
 };
 
@@ -158,6 +166,329 @@ async fn test_transitive_local(#[values(OutputType::Wasm, OutputType::Wit)] outp
     );
 }
 
+#[rstest]
+#[tokio::test]
+async fn test_transitive_registry(#[values(OutputType::Wasm, OutputType::Wit)] output: OutputType) {
+    // None of these packages include their dependencies, so example:b and example:c can only be
+    // found by decoding the packages that reference them
+    let registry = tempfile::tempdir().unwrap();
+    write_wit_releases(
+        registry.path(),
+        [
+            (
+                "example:a",
+                "1.0.0",
+                "interface api {\n  use example:b/types@1.0.0.{id};\n  get: func() -> id;\n}",
+            ),
+            (
+                "example:b",
+                "1.0.0",
+                "interface types {\n  use example:c/base@1.0.0.{raw};\n  type id = raw;\n}",
+            ),
+            (
+                "example:c",
+                "1.0.0",
+                "interface base {\n  type raw = u64;\n}",
+            ),
+        ],
+    )
+    .await;
+
+    let (_project, lock, _) = fetch_registry_project(
+        registry.path(),
+        &Manifest::default(),
+        "import example:a/api@1.0.0;",
+        output,
+    )
+    .await;
+
+    let locked: Vec<_> = lock.packages.iter().map(|p| p.name.to_string()).collect();
+    assert_eq!(locked, ["example:a", "example:b", "example:c"]);
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_multiple_versions_registry(
+    #[values(OutputType::Wasm, OutputType::Wit)] output: OutputType,
+) {
+    let registry = tempfile::tempdir().unwrap();
+    write_wit_releases(
+        registry.path(),
+        [
+            (
+                "example:a",
+                "1.0.0",
+                "interface api {\n  use example:io/streams@0.2.0.{handle};\n  get: func() -> handle;\n}",
+            ),
+            (
+                "example:b",
+                "1.0.0",
+                "interface api {\n  use example:io/streams@0.2.3.{handle};\n  get: func() -> handle;\n}",
+            ),
+            ("example:io", "0.2.0", "interface streams {\n  type handle = u32;\n}"),
+            ("example:io", "0.2.3", "interface streams {\n  type handle = u64;\n}"),
+        ],
+    )
+    .await;
+
+    let (project, lock, _) = fetch_registry_project(
+        registry.path(),
+        &Manifest::default(),
+        "import example:a/api@1.0.0;\n  import example:b/api@1.0.0;",
+        output,
+    )
+    .await;
+
+    let io = lock
+        .packages
+        .iter()
+        .find(|p| p.name.to_string() == "example:io")
+        .expect("example:io should be in the lock file");
+    let mut versions: Vec<_> = io.versions.iter().map(|v| v.version.to_string()).collect();
+    versions.sort();
+    assert_eq!(versions, ["0.2.0", "0.2.3"]);
+
+    for dep in ["example-io-0.2.0", "example-io-0.2.3"] {
+        assert!(
+            project.path().join("wit/deps").join(dep).exists(),
+            "{dep} should be written to the deps directory"
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_fetch_with_in_memory_cache() {
+    let registry = tempfile::tempdir().unwrap();
+    write_wit_releases(
+        registry.path(),
+        [(
+            "example:a",
+            "1.0.0",
+            "interface api {\n  get: func() -> u32;\n}",
+        )],
+    )
+    .await;
+    let config = common::local_registry_config(registry.path()).unwrap();
+    let cache = InMemoryCache::new();
+    let client = CachingClient::new(Some(Client::new(config)), cache.clone());
+
+    let (project, wit_dir) = write_project("import example:a/api@1.0.0;").await;
+    let mut lock = LockFile::new_with_path([], project.path().join("wkg.lock"))
+        .await
+        .unwrap();
+    wit::fetch_dependencies(
+        &Manifest::default(),
+        &wit_dir,
+        &mut lock,
+        client,
+        OutputType::Wit,
+    )
+    .await
+    .expect("Should be able to fetch the dependencies");
+
+    assert!(
+        wit_dir.join("deps/example-a-1.0.0").exists(),
+        "example:a should be written to the deps directory"
+    );
+    assert!(cache.size() > 0, "the package should be in the cache");
+}
+
+#[tokio::test]
+async fn test_resolution_error_explains_requirement() {
+    let registry = tempfile::tempdir().unwrap();
+    write_wit_releases(
+        registry.path(),
+        [
+            (
+                "example:a",
+                "1.0.0",
+                "interface api {\n  use example:b/types@1.0.0.{id};\n}",
+            ),
+            (
+                "example:b",
+                "1.0.0",
+                "interface types {\n  use example:c/base@1.1.0.{raw};\n  type id = raw;\n}",
+            ),
+            (
+                "example:c",
+                "1.0.0",
+                "interface base {\n  type raw = u64;\n}",
+            ),
+            (
+                "example:c",
+                "1.1.0",
+                "interface base {\n  type raw = u64;\n}",
+            ),
+        ],
+    )
+    .await;
+    tokio::fs::write(registry.path().join("example/c/1.1.0.yanked"), "")
+        .await
+        .unwrap();
+    let (_temp_cache, client) = common::get_local_client(registry.path()).await.unwrap();
+    let (_project, wit_dir) = write_project("import example:a/api@1.0.0;").await;
+
+    let err = wit::resolve_dependencies(&Manifest::default(), &wit_dir, None, client)
+        .await
+        .expect_err("example:c@1.1.0 is yanked, so resolving should fail");
+    let err = err
+        .downcast_ref::<ResolutionError>()
+        .unwrap_or_else(|| panic!("Should be a resolution error: {err:#}"));
+
+    assert_eq!(err.package.to_string(), "example:c");
+    assert_eq!(err.requirement.to_string(), "=1.1.0");
+    assert_eq!(
+        err.origins,
+        [
+            RequirementOrigin::Package("example:b@1.0.0".parse().unwrap()),
+            RequirementOrigin::Package("example:a@1.0.0".parse().unwrap()),
+            RequirementOrigin::Wit(wit_dir.clone()),
+        ]
+    );
+    let available: Vec<_> = err
+        .available
+        .as_ref()
+        .expect("example:c should be found")
+        .iter()
+        .map(|info| (info.version.to_string(), info.yanked))
+        .collect();
+    assert_eq!(
+        available,
+        [("1.0.0".to_string(), false), ("1.1.0".to_string(), true)]
+    );
+    let message = err.to_string();
+    assert!(
+        message.contains("release 1.1.0 matches but is yanked"),
+        "{message}"
+    );
+}
+
+#[tokio::test]
+async fn test_unify_semver_registry() {
+    let registry = tempfile::tempdir().unwrap();
+    write_wit_releases(
+        registry.path(),
+        [
+            (
+                "example:a",
+                "1.0.0",
+                "interface api {\n  use example:io/streams@0.2.0.{handle};\n  get: func() -> handle;\n}",
+            ),
+            (
+                "example:b",
+                "1.0.0",
+                "interface api {\n  use example:io/streams@0.2.3.{handle};\n  get: func() -> handle;\n}",
+            ),
+            ("example:io", "0.2.0", "interface streams {\n  type handle = u32;\n}"),
+            ("example:io", "0.2.3", "interface streams {\n  type handle = u32;\n}"),
+            ("example:io", "0.2.5", "interface streams {\n  type handle = u32;\n}"),
+            ("example:io", "0.3.0", "interface streams {\n  type handle = u64;\n}"),
+        ],
+    )
+    .await;
+    let manifest = Manifest {
+        resolver: Some(ResolverSettings {
+            unify_semver: true,
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+
+    // References to other packages are pointed at the unified version when printing WIT
+    let (project, lock, resolved) = fetch_registry_project(
+        registry.path(),
+        &manifest,
+        "import example:a/api@1.0.0;\n  import example:b/api@1.0.0;",
+        OutputType::Wit,
+    )
+    .await;
+
+    let io = lock
+        .packages
+        .iter()
+        .find(|p| p.name.to_string() == "example:io")
+        .expect("example:io should be in the lock file");
+    let mut locked: Vec<_> = io
+        .versions
+        .iter()
+        .map(|v| (v.requirement.to_string(), v.version.to_string()))
+        .collect();
+    locked.sort();
+    assert_eq!(
+        locked,
+        [
+            ("^0.2.0".to_string(), "0.2.5".to_string()),
+            ("^0.2.3".to_string(), "0.2.5".to_string()),
+        ]
+    );
+    assert!(project.path().join("wit/deps/example-io-0.2.5").exists());
+    assert!(!project.path().join("wit/deps/example-io-0.2.0").exists());
+
+    let widened: Vec<_> = resolved
+        .widened_requirements()
+        .iter()
+        .map(|w| format!("{}@{} -> {}", w.name, w.referenced, w.version))
+        .collect();
+    assert_eq!(
+        widened,
+        ["example:io@0.2.0 -> 0.2.5", "example:io@0.2.3 -> 0.2.5"]
+    );
+}
+
+/// Writes a project with a world containing the given items, returning the project and WIT dirs
+async fn write_project(world: &str) -> (tempfile::TempDir, PathBuf) {
+    let project = tempfile::tempdir().unwrap();
+    let wit_dir = project.path().join("wit");
+    tokio::fs::create_dir_all(&wit_dir).await.unwrap();
+    tokio::fs::write(
+        wit_dir.join("world.wit"),
+        format!("package example:app@0.1.0;\nworld app {{\n  {world}\n}}\n"),
+    )
+    .await
+    .unwrap();
+    (project, wit_dir)
+}
+
+/// Writes WIT packages with the given interfaces directly into a `local` registry
+async fn write_wit_releases<'a>(
+    root: &Path,
+    releases: impl IntoIterator<Item = (&'a str, &'a str, &'a str)>,
+) {
+    for (package, version, interfaces) in releases {
+        let contents = format!("package {package}@{version};\n{interfaces}\n");
+        common::write_local_release(root, package, version, contents.as_bytes())
+            .await
+            .unwrap();
+    }
+}
+
+/// Fetches the dependencies of a project with a world containing the given items from the `local`
+/// registry and ensures the fetched dependencies are enough to resolve the project
+async fn fetch_registry_project(
+    registry: &Path,
+    manifest: &Manifest,
+    world: &str,
+    output: OutputType,
+) -> (tempfile::TempDir, LockFile, DependencyResolutionMap) {
+    let (_temp_cache, client) = common::get_local_client(registry).await.unwrap();
+
+    let (project, wit_dir) = write_project(world).await;
+    let mut lock = LockFile::new_with_path([], project.path().join("wkg.lock"))
+        .await
+        .unwrap();
+
+    let resolved = wit::fetch_dependencies(manifest, &wit_dir, &mut lock, client, output)
+        .await
+        .unwrap_or_else(|e| panic!("Should be able to fetch the dependencies: {e:#}"));
+
+    let mut resolve = wit_parser::Resolve::default();
+    resolve
+        .push_dir(&wit_dir)
+        .unwrap_or_else(|e| panic!("Should be able to resolve the package: {e:#}"));
+
+    (project, lock, resolved)
+}
+
 async fn build_component(fixture_path: &Path) {
     let output = Command::new(env!("CARGO"))
         .current_dir(fixture_path)
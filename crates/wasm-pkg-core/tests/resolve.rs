@@ -0,0 +1,372 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use rstest::rstest;
+use sha2::Digest;
+use wasm_pkg_client::{
+    Client, Config, ContentDigest, ContentStream, Error, LoaderPublisher, PackageLoader,
+    PackagePublisher, PackageRef, PublishingSource, Registry, RegistryConfig, RegistryMetadata,
+    Release, Version, VersionInfo, VersionReq,
+    caching::{CachingClient, FileCache},
+};
+use wasm_pkg_core::{
+    lock::{LockFile, LockedPackage, LockedPackageVersion},
+    resolver::{DependencyResolution, DependencyResolver},
+};
+
+mod common;
+
+const PACKAGE_COUNT: usize = 20;
+
+fn package_name(i: usize) -> String {
+    format!("example:pkg{i:02}")
+}
+
+// Resolved packages are decoded, so every release needs to be a valid WIT package
+fn contents(package: &str, version: &str) -> Vec<u8> {
+    format!("package {package}@{version};\n").into_bytes()
+}
+
+async fn populate_registry(root: &std::path::Path) {
+    for i in 0..PACKAGE_COUNT {
+        let package = package_name(i);
+        for version in ["1.0.0", "1.1.0", "2.0.0"] {
+            common::write_local_release(root, &package, version, &contents(&package, version))
+                .await
+                .unwrap();
+        }
+    }
+}
+
+#[rstest]
+#[case(1)]
+#[case(8)]
+#[tokio::test]
+async fn resolve_with_concurrency_limit(#[case] concurrency: usize) {
+    let registry = tempfile::tempdir().unwrap();
+    populate_registry(registry.path()).await;
+    let (_cache, client) = common::get_local_client(registry.path()).await.unwrap();
+
+    let mut resolver = DependencyResolver::new_with_client(client, None)
+        .unwrap()
+        .with_concurrency(concurrency);
+    let req: VersionReq = "^1.0".parse().unwrap();
+    resolver
+        .add_packages((0..PACKAGE_COUNT).map(|i| (package_name(i).parse().unwrap(), req.clone())))
+        .await
+        .unwrap();
+    let resolutions = resolver.resolve().await.expect("resolve should succeed");
+
+    assert_eq!(resolutions.len(), PACKAGE_COUNT);
+    for i in 0..PACKAGE_COUNT {
+        let package: PackageRef = package_name(i).parse().unwrap();
+        let [DependencyResolution::Registry(resolution)] =
+            resolutions.versions_of(&package).collect::<Vec<_>>()[..]
+        else {
+            panic!("{package} should be resolved once from the registry");
+        };
+        assert_eq!(resolution.version.to_string(), "1.1.0");
+        let expected: ContentDigest =
+            sha2::Sha256::new_with_prefix(contents(&package.to_string(), "1.1.0")).into();
+        assert_eq!(resolution.digest, expected);
+    }
+}
+
+#[tokio::test]
+async fn resolve_reports_missing_packages_deterministically() {
+    let registry = tempfile::tempdir().unwrap();
+    populate_registry(registry.path()).await;
+    let (_cache, client) = common::get_local_client(registry.path()).await.unwrap();
+
+    let mut resolver = DependencyResolver::new_with_client(client, None)
+        .unwrap()
+        .with_concurrency(8);
+    let packages = ["example:missing-b", "example:pkg00", "example:missing-a"];
+    resolver
+        .add_packages(
+            packages
+                .iter()
+                .map(|name| (name.parse().unwrap(), VersionReq::STAR)),
+        )
+        .await
+        .unwrap();
+    let err = resolver
+        .resolve()
+        .await
+        .expect_err("resolving unknown packages should fail");
+    assert!(
+        err.to_string().contains("example:missing-a"),
+        "the first missing package should be reported: {err:#}"
+    );
+}
+
+#[tokio::test]
+async fn resolve_unifies_locked_compatible_versions() {
+    let registry = tempfile::tempdir().unwrap();
+    for version in ["0.2.0", "0.2.3", "0.2.5", "0.3.0"] {
+        common::write_local_release(
+            registry.path(),
+            "example:io",
+            version,
+            &contents("example:io", version),
+        )
+        .await
+        .unwrap();
+    }
+    let (_cache, client) = common::get_local_client(registry.path()).await.unwrap();
+    let package: PackageRef = "example:io".parse().unwrap();
+
+    // An older resolution of a compatible requirement is still locked
+    let lock_dir = tempfile::tempdir().unwrap();
+    let lock = LockFile::new_with_path(
+        [LockedPackage {
+            name: package.clone(),
+            registry: None,
+            versions: vec![LockedPackageVersion {
+                requirement: "^0.2.0".parse().unwrap(),
+                version: "0.2.3".parse().unwrap(),
+                digest: sha2::Sha256::new_with_prefix(contents("example:io", "0.2.3")).into(),
+            }],
+        }],
+        lock_dir.path().join("wkg.lock"),
+    )
+    .await
+    .unwrap();
+
+    let mut resolver = DependencyResolver::new_with_client(client, Some(&lock))
+        .unwrap()
+        .with_semver_unification(true);
+    resolver
+        .add_packages(
+            ["=0.2.0", "=0.2.4", "=0.3.0"]
+                .into_iter()
+                .map(|req| (package.clone(), req.parse().unwrap())),
+        )
+        .await
+        .unwrap();
+    let resolutions = resolver.resolve().await.expect("resolve should succeed");
+
+    let mut resolved: Vec<_> = resolutions
+        .versions_of(&package)
+        .map(|res| match res {
+            DependencyResolution::Registry(res) => {
+                let mut requirements: Vec<_> =
+                    res.requirements().map(ToString::to_string).collect();
+                requirements.sort();
+                (res.version.to_string(), requirements)
+            }
+            DependencyResolution::Local(_) => {
+                panic!("{package} should be resolved from the registry")
+            }
+        })
+        .collect();
+    resolved.sort();
+    assert_eq!(
+        resolved,
+        [
+            (
+                "0.2.5".to_string(),
+                vec!["^0.2.0".to_string(), "^0.2.4".to_string()]
+            ),
+            ("0.3.0".to_string(), vec!["^0.3.0".to_string()]),
+        ]
+    );
+    let widened: Vec<_> = resolutions
+        .widened_requirements()
+        .iter()
+        .map(|w| w.referenced.to_string())
+        .collect();
+    assert_eq!(widened, ["0.2.0", "0.2.4"]);
+}
+
+#[tokio::test]
+async fn resolve_removes_dependencies_of_unified_versions() {
+    let registry = tempfile::tempdir().unwrap();
+    // Only the older release depends on another package
+    let old_io = "package example:io@0.2.0;\n\ninterface streams {\n    use example:old/types@1.0.0.{t};\n}\n";
+    common::write_local_release(registry.path(), "example:io", "0.2.0", old_io.as_bytes())
+        .await
+        .unwrap();
+    common::write_local_release(
+        registry.path(),
+        "example:io",
+        "0.2.5",
+        &contents("example:io", "0.2.5"),
+    )
+    .await
+    .unwrap();
+    let old_types = "package example:old@1.0.0;\n\ninterface types {\n    type t = u32;\n}\n";
+    common::write_local_release(
+        registry.path(),
+        "example:old",
+        "1.0.0",
+        old_types.as_bytes(),
+    )
+    .await
+    .unwrap();
+    let (_cache, client) = common::get_local_client(registry.path()).await.unwrap();
+    let package: PackageRef = "example:io".parse().unwrap();
+
+    // The older release is locked for one of the requirements, so it is resolved before unifying
+    let lock_dir = tempfile::tempdir().unwrap();
+    let lock = LockFile::new_with_path(
+        [LockedPackage {
+            name: package.clone(),
+            registry: Some("local".to_string()),
+            versions: vec![LockedPackageVersion {
+                requirement: "^0.2.0".parse().unwrap(),
+                version: "0.2.0".parse().unwrap(),
+                digest: sha2::Sha256::new_with_prefix(old_io).into(),
+            }],
+        }],
+        lock_dir.path().join("wkg.lock"),
+    )
+    .await
+    .unwrap();
+
+    let mut resolver = DependencyResolver::new_with_client(client, Some(&lock))
+        .unwrap()
+        .with_semver_unification(true);
+    resolver
+        .add_packages(
+            ["=0.2.0", "=0.2.5"]
+                .into_iter()
+                .map(|req| (package.clone(), req.parse().unwrap())),
+        )
+        .await
+        .unwrap();
+    let resolutions = resolver.resolve().await.expect("resolve should succeed");
+
+    let mut resolved: Vec<_> = resolutions.keys().map(ToString::to_string).collect();
+    resolved.sort();
+    assert_eq!(
+        resolved,
+        ["example:io@0.2.5"],
+        "the dependency of the unified version shouldn't be kept"
+    );
+}
+
+/// A backend that serves the same releases as [`populate_registry`] and records the highest number
+/// of requests that were in flight at once
+#[derive(Clone, Default)]
+struct CountingBackend {
+    in_flight: Arc<AtomicUsize>,
+    max_in_flight: Arc<AtomicUsize>,
+}
+
+impl CountingBackend {
+    async fn request(&self) {
+        let current = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max_in_flight.fetch_max(current, Ordering::SeqCst);
+        // Give the other requests a chance to start while this one is in flight
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[async_trait]
+impl PackageLoader for CountingBackend {
+    async fn list_all_versions(&self, _package: &PackageRef) -> Result<Vec<VersionInfo>, Error> {
+        self.request().await;
+        Ok(["1.0.0", "1.1.0", "2.0.0"]
+            .into_iter()
+            .map(|version| VersionInfo {
+                version: version.parse().unwrap(),
+                yanked: false,
+            })
+            .collect())
+    }
+
+    async fn get_release(&self, package: &PackageRef, version: &Version) -> Result<Release, Error> {
+        self.request().await;
+        Ok(Release {
+            version: version.clone(),
+            content_digest: sha2::Sha256::new_with_prefix(contents(
+                &package.to_string(),
+                &version.to_string(),
+            ))
+            .into(),
+        })
+    }
+
+    async fn stream_content_unvalidated(
+        &self,
+        package: &PackageRef,
+        release: &Release,
+    ) -> Result<ContentStream, Error> {
+        self.request().await;
+        let data = contents(&package.to_string(), &release.version.to_string());
+        Ok(futures_util::stream::iter([Ok(data.into())]).boxed())
+    }
+}
+
+#[async_trait]
+impl PackagePublisher for CountingBackend {
+    async fn publish(
+        &self,
+        _package: &PackageRef,
+        _version: &Version,
+        _data: PublishingSource,
+        _dry_run: bool,
+    ) -> Result<(), Error> {
+        Err(Error::RegistryError(anyhow::anyhow!(
+            "the counting backend is read-only"
+        )))
+    }
+}
+
+#[rstest]
+#[case(1)]
+#[case(4)]
+#[tokio::test]
+async fn resolve_never_exceeds_concurrency_limit(#[case] concurrency: usize) {
+    let config = Config::from_toml(
+        r#"
+default_registry = "counting"
+
+[registry."counting"]
+type = "counting"
+"#,
+    )
+    .unwrap();
+    let backend = CountingBackend::default();
+    let factory = backend.clone();
+    let client = Client::builder(config)
+        .with_backend(
+            "counting",
+            move |_: &Registry, _: &RegistryConfig, _: &RegistryMetadata| {
+                Ok(Box::new(factory.clone()) as Box<dyn LoaderPublisher>)
+            },
+        )
+        .build();
+    let cache_dir = tempfile::tempdir().unwrap();
+    let cache = FileCache::new(cache_dir.path()).await.unwrap();
+
+    let mut resolver =
+        DependencyResolver::new_with_client(CachingClient::new(Some(client), cache), None)
+            .unwrap()
+            .with_concurrency(concurrency);
+    let req: VersionReq = "^1.0".parse().unwrap();
+    resolver
+        .add_packages((0..PACKAGE_COUNT).map(|i| (package_name(i).parse().unwrap(), req.clone())))
+        .await
+        .unwrap();
+    let resolutions = resolver.resolve().await.expect("resolve should succeed");
+
+    assert_eq!(resolutions.len(), PACKAGE_COUNT);
+    let max_in_flight = backend.max_in_flight.load(Ordering::SeqCst);
+    assert!(
+        max_in_flight <= concurrency,
+        "{max_in_flight} requests were in flight with a limit of {concurrency}"
+    );
+    if concurrency > 1 {
+        assert!(max_in_flight > 1, "requests should be made concurrently");
+    }
+}
@@ -0,0 +1,269 @@
+//! Args and commands for managing the package cache. Read-only cache directories are never
+//! modified, so these only manage the cache directory.
+use std::time::{Duration, SystemTime};
+
+use anstream::{print, println};
+use anyhow::Context;
+use clap::{Args, Subcommand};
+use serde_json::json;
+use wasm_pkg_client::caching::{CachedData, RevalidationPolicy};
+
+use crate::{Common, OutputFormat};
+
+/// Commands for managing the package cache
+#[derive(Debug, Subcommand)]
+pub enum CacheCommands {
+    /// List the cached packages, versions and their sizes.
+    List(ListArgs),
+    /// Check the digest of all cached data and remove data that doesn't match.
+    Verify(VerifyArgs),
+    /// Remove the least recently used data from the cache.
+    Prune(PruneArgs),
+    /// Remove everything from the cache.
+    Clean(CleanArgs),
+}
+
+impl CacheCommands {
+    pub async fn run(self) -> anyhow::Result<()> {
+        match self {
+            CacheCommands::List(args) => args.run().await,
+            CacheCommands::Verify(args) => args.run().await,
+            CacheCommands::Prune(args) => args.run().await,
+            CacheCommands::Clean(args) => args.run().await,
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct ListArgs {
+    /// The output format. Valid options are "text" or "json".
+    #[clap(long, value_enum, default_value_t)]
+    pub format: OutputFormat,
+
+    #[clap(flatten)]
+    pub common: Common,
+}
+
+impl ListArgs {
+    pub async fn run(self) -> anyhow::Result<()> {
+        let cache = self.common.load_cache().await?.top().clone();
+        let index = cache.index().await?;
+        match self.format {
+            OutputFormat::Text => {
+                let mut rows: Vec<[String; 4]> = Vec::new();
+                for data in index.iter() {
+                    let size = format_size(data.size);
+                    if data.releases.is_empty() {
+                        rows.push([
+                            "-".into(),
+                            "-".into(),
+                            size.clone(),
+                            data.digest.to_string(),
+                        ]);
+                    }
+                    for release in data.releases.iter() {
+                        rows.push([
+                            release.package.to_string(),
+                            release.version.to_string(),
+                            size.clone(),
+                            data.digest.to_string(),
+                        ]);
+                    }
+                }
+                rows.sort();
+                rows.insert(
+                    0,
+                    ["Package", "Version", "Size", "Digest"].map(String::from),
+                );
+                print!("{}", crate::render_table(&rows));
+                crate::statusln!(
+                    "Total",
+                    "{} in {}",
+                    format_size(index.iter().map(|data| data.size).sum()),
+                    cache.root().display()
+                );
+            }
+            OutputFormat::Json => {
+                let entries: Vec<_> = index
+                    .iter()
+                    .map(|data| {
+                        json!({
+                            "digest": data.digest.to_string(),
+                            "size": data.size,
+                            "accessed": unix_timestamp(data.accessed),
+                            "releases": data.releases.iter().map(|release| json!({
+                                "package": release.package.to_string(),
+                                "version": release.version.to_string(),
+                            })).collect::<Vec<_>>(),
+                        })
+                    })
+                    .collect();
+                println!("{}", serde_json::to_string_pretty(&entries)?);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct VerifyArgs {
+    #[clap(flatten)]
+    pub common: Common,
+}
+
+impl VerifyArgs {
+    pub async fn run(self) -> anyhow::Result<()> {
+        let cache = self.common.load_cache().await?.top().clone();
+        let corrupted = cache.verify().await?;
+        for data in corrupted.iter() {
+            crate::warnln!("removed corrupted data {}", describe(data));
+        }
+        crate::statusln!(
+            "Verified",
+            "{}, removed {} corrupted entries",
+            cache.root().display(),
+            corrupted.len()
+        );
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[clap(group = clap::ArgGroup::new("limit").required(true).multiple(true))]
+pub struct PruneArgs {
+    /// Remove the least recently used data until the cache is at most this size, e.g. `500M` or
+    /// `2GiB`.
+    #[clap(long, group = "limit", value_parser = parse_size)]
+    pub max_size: Option<u64>,
+
+    /// Remove data that wasn't used for longer than this, e.g. `30d` or `12h`.
+    #[clap(long, group = "limit", value_parser = parse_duration)]
+    pub older_than: Option<Duration>,
+
+    #[clap(flatten)]
+    pub common: Common,
+}
+
+impl PruneArgs {
+    pub async fn run(self) -> anyhow::Result<()> {
+        let cache = self.common.load_cache().await?.top().clone();
+        let removed = cache.prune(self.max_size, self.older_than).await?;
+        for data in removed.iter() {
+            crate::statusln!("Removed", "{}", describe(data));
+        }
+        crate::statusln!(
+            "Pruned",
+            "{} entries ({}) from {}",
+            removed.len(),
+            format_size(removed.iter().map(|data| data.size).sum()),
+            cache.root().display()
+        );
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct CleanArgs {
+    #[clap(flatten)]
+    pub common: Common,
+}
+
+impl CleanArgs {
+    pub async fn run(self) -> anyhow::Result<()> {
+        let cache = self.common.load_cache().await?.top().clone();
+        cache.clean().await?;
+        crate::statusln!("Cleaned", "{}", cache.root().display());
+        Ok(())
+    }
+}
+
+/// Describes the data by the releases that refer to it, or by its digest.
+fn describe(data: &CachedData) -> String {
+    if data.releases.is_empty() {
+        return data.digest.to_string();
+    }
+    let releases: Vec<String> = data
+        .releases
+        .iter()
+        .map(|release| format!("{}@{}", release.package, release.version))
+        .collect();
+    format!("{} ({})", releases.join(", "), format_size(data.size))
+}
+
+fn unix_timestamp(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+const SIZE_UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+
+fn format_size(size: u64) -> String {
+    let mut value = size as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < SIZE_UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{size} B")
+    } else {
+        format!("{value:.1} {}", SIZE_UNITS[unit])
+    }
+}
+
+/// Parses a size in bytes with an optional binary unit, e.g. `1024`, `500K`, `500M` or `2GiB`.
+fn parse_size(input: &str) -> anyhow::Result<u64> {
+    let split = input
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(input.len());
+    let (number, unit) = input.split_at(split);
+    let number: u64 = number
+        .parse()
+        .with_context(|| format!("invalid size `{input}`"))?;
+    let shift = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 0,
+        "K" | "KB" | "KIB" => 10,
+        "M" | "MB" | "MIB" => 20,
+        "G" | "GB" | "GIB" => 30,
+        "T" | "TB" | "TIB" => 40,
+        _ => anyhow::bail!("invalid size unit `{unit}`, expected one of B, K, M, G or T"),
+    };
+    number
+        .checked_mul(1 << shift)
+        .with_context(|| format!("size `{input}` is too large"))
+}
+
+/// Parses a duration with a unit, e.g. `90s`, `30m`, `12h`, `30d` or `2w`.
+pub(crate) fn parse_duration(input: &str) -> anyhow::Result<Duration> {
+    let split = input
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(input.len());
+    let (number, unit) = input.split_at(split);
+    let number: u64 = number
+        .parse()
+        .with_context(|| format!("invalid duration `{input}`"))?;
+    let seconds = match unit.trim() {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => anyhow::bail!("invalid duration unit `{unit}`, expected one of s, m, h, d or w"),
+    };
+    number
+        .checked_mul(seconds)
+        .map(Duration::from_secs)
+        .with_context(|| format!("duration `{input}` is too large"))
+}
+
+/// Parses a revalidation policy, which is either `never`, `always` or a duration.
+pub(crate) fn parse_revalidation(input: &str) -> anyhow::Result<RevalidationPolicy> {
+    match input {
+        "never" => Ok(RevalidationPolicy::Never),
+        "always" => Ok(RevalidationPolicy::Always),
+        duration => parse_duration(duration)
+            .map(RevalidationPolicy::After)
+            .context("expected `never`, `always` or a duration"),
+    }
+}
@@ -3,6 +3,7 @@
 use std::{
     io::{Cursor, Seek},
     path::PathBuf,
+    time::Duration,
 };
 
 use anstream::eprintln;
@@ -13,7 +14,7 @@ use tokio::io::AsyncWriteExt;
 use tracing::level_filters::LevelFilter;
 use wasm_pkg_client::{
     Client, PackageRef, PublishOpts, Version,
-    caching::{CachingClient, FileCache},
+    caching::{CachingClient, DigestMismatchAction, FileCache, LayeredCache, RevalidationPolicy},
 };
 use wasm_pkg_common::{
     self,
@@ -22,16 +23,24 @@ use wasm_pkg_common::{
     registry::Registry,
 };
 use wasm_pkg_core::{
-    lock::LockFile,
+    lock::{LockFile, LockFileOutdated, LockMode},
     manifest::{Manifest, workspace::WorkspaceRootConfig},
 };
 use wit_component::DecodedWasm;
 
+pub mod cache;
 pub mod oci;
+pub mod outdated;
 mod overlay;
+pub mod tree;
+pub mod vendor;
 pub mod wit;
 
+use cache::CacheCommands;
 use oci::OciCommands;
+use outdated::OutdatedArgs;
+use tree::TreeArgs;
+use vendor::VendorArgs;
 use wit::{BuildArgs, FetchArgs, UpdateArgs, WitCommands};
 
 use crate::{overlay::PublishVerifier, wit::temp_wit_file};
@@ -89,6 +98,41 @@ pub struct Common {
     /// The path to the cache directory. Defaults to the system cache directory.
     #[arg(long = "cache", value_name = "CACHE", env = "WKG_CACHE_DIR")]
     cache: Option<PathBuf>,
+    /// Additional cache directories that are only read from, in the order they are checked after
+    /// the cache directory, e.g. a pre-populated cache that is shared between CI jobs. Can be given
+    /// multiple times, and the environment variable takes a list separated like `PATH`.
+    #[arg(
+        long = "cache-readonly-dir",
+        value_name = "DIR",
+        env = "WKG_CACHE_READONLY_DIRS",
+        value_delimiter = if cfg!(windows) { ';' } else { ':' }
+    )]
+    cache_readonly_dirs: Vec<PathBuf>,
+    /// Run without accessing the network. Dependencies are only resolved from the lock file and
+    /// the cached versions of packages, and packages are only loaded from the cache.
+    #[arg(long, env = "WKG_OFFLINE")]
+    offline: bool,
+    /// How long the listed versions of a package are cached before they are listed again, e.g.
+    /// `10m`. By default, versions are listed every time.
+    #[arg(
+        long,
+        value_name = "DURATION",
+        env = "WKG_VERSIONS_TTL",
+        value_parser = cache::parse_duration
+    )]
+    versions_ttl: Option<Duration>,
+    /// When cached releases are checked against the registry in case they were republished with
+    /// different content: `never`, `always` or how long after caching them, e.g. `1d`.
+    #[arg(
+        long,
+        value_name = "POLICY",
+        env = "WKG_REVALIDATE",
+        value_parser = cache::parse_revalidation
+    )]
+    revalidate: Option<RevalidationPolicy>,
+    /// Fail instead of warning when a revalidated release was republished with different content.
+    #[arg(long, env = "WKG_DENY_DIGEST_CHANGES")]
+    deny_digest_changes: bool,
 }
 
 impl Common {
@@ -104,27 +148,83 @@ impl Common {
         }
     }
 
-    /// Helper for loading the [`FileCache`]
-    pub async fn load_cache(&self) -> anyhow::Result<FileCache> {
+    /// Helper for loading the cache, which writes to the cache directory and also reads from any
+    /// read-only cache directories
+    pub async fn load_cache(&self) -> anyhow::Result<LayeredCache<FileCache>> {
         let dir = if let Some(dir) = self.cache.as_ref() {
             dir.clone()
         } else {
             FileCache::global_cache_path().context("unable to find cache directory")?
         };
 
-        FileCache::new(dir).await
+        let top = FileCache::new(dir).await?;
+        let mut readonly = Vec::with_capacity(self.cache_readonly_dirs.len());
+        for dir in self.cache_readonly_dirs.iter() {
+            readonly.push(FileCache::open(dir).await.with_context(|| {
+                format!("unable to open read-only cache directory {}", dir.display())
+            })?);
+        }
+        Ok(LayeredCache::new(top, readonly))
     }
 
     /// Helper for loading a caching client. This should be the most commonly used method for
     /// loading a client, but if you need to modify the config or use your own cache, you can use
     /// the [`Common::load_config`] and [`Common::load_cache`] methods.
-    pub async fn get_client(&self) -> anyhow::Result<CachingClient<FileCache>> {
+    pub async fn get_client(&self) -> anyhow::Result<CachingClient<LayeredCache<FileCache>>> {
         let config = self.load_config().await?;
         let cache = self.load_cache().await?;
         let client = Client::new(config);
 
-        tracing::debug!(filecache_dir = %cache);
-        Ok(CachingClient::new(Some(client), cache))
+        tracing::debug!(filecache_dir = %cache.top());
+        Ok(self.caching_client(client, cache))
+    }
+
+    /// Helper for wrapping the given client and cache in a caching client, which is in offline mode
+    /// if `--offline` was given.
+    pub fn caching_client(
+        &self,
+        client: Client,
+        cache: LayeredCache<FileCache>,
+    ) -> CachingClient<LayeredCache<FileCache>> {
+        let client = if self.offline {
+            CachingClient::new_offline(client, cache)
+        } else {
+            CachingClient::new(Some(client), cache)
+        };
+        let client = match self.versions_ttl {
+            Some(ttl) => client.with_versions_ttl(ttl),
+            None => client,
+        };
+        let on_mismatch = if self.deny_digest_changes {
+            DigestMismatchAction::Error
+        } else {
+            DigestMismatchAction::Warn
+        };
+        client.with_revalidation(self.revalidate.unwrap_or_default(), on_mismatch)
+    }
+}
+
+/// Options for whether `wkg.lock` may be changed.
+#[derive(Args, Debug, Default, Clone, Copy)]
+pub struct LockArgs {
+    /// Fail instead of updating `wkg.lock` if it is missing or out of date.
+    #[arg(long)]
+    locked: bool,
+    /// Same as `--locked`, but also don't access the network, like `--offline`.
+    #[arg(long)]
+    frozen: bool,
+}
+
+impl LockArgs {
+    /// Returns the [`LockMode`] to load `wkg.lock` with.
+    pub fn mode(&self) -> LockMode {
+        if self.frozen {
+            LockMode::Frozen
+        } else if self.locked {
+            LockMode::Locked
+        } else {
+            LockMode::Update
+        }
     }
 }
 
@@ -137,12 +237,20 @@ pub enum Commands {
     Get(GetArgs),
     /// Publish a package to a registry
     Publish(PublishArgs),
+    /// Yank a published package release so it is no longer selected when resolving dependencies
+    Yank(YankArgs),
     /// Commands for interacting with OCI registries
     #[clap(subcommand)]
     Oci(OciCommands),
     Build(BuildArgs),
     Fetch(FetchArgs),
     Update(UpdateArgs),
+    Tree(TreeArgs),
+    Outdated(OutdatedArgs),
+    Vendor(VendorArgs),
+    /// Commands for managing the package cache
+    #[clap(subcommand)]
+    Cache(CacheCommands),
     /// Commands for interacting with WIT files and dependencies
     #[clap(subcommand)]
     Wit(WitCommands),
@@ -303,6 +411,9 @@ pub struct PublishArgs {
     #[arg(long)]
     skip_dupes: bool,
 
+    #[command(flatten)]
+    lock: LockArgs,
+
     #[command(flatten)]
     common: Common,
 }
@@ -320,7 +431,7 @@ impl PublishArgs {
             }
             [path] => path,
             paths => {
-                let mut lock_file = LockFile::load(false).await?;
+                let mut lock_file = LockFile::load(false).await?.with_mode(self.lock.mode());
                 let verifier = PublishVerifier::try_new(
                     paths,
                     "tmp_local_publish",
@@ -328,6 +439,7 @@ impl PublishArgs {
                     self.common.load_cache().await?,
                     &mut lock_file,
                     true,
+                    self.common.offline,
                 )
                 .await?;
                 let mut plan = verifier.plan;
@@ -369,23 +481,27 @@ impl PublishArgs {
         // file first. _tmp is held until the publish completes so the file
         // isn't deleted out from under us.
         let (publish_path, _tmp) = if path.is_dir() {
-            let mut lock_file = LockFile::load(true).await?;
-            let prev_lock_ref = (lock_file.version, lock_file.packages.clone());
-            let (pkg_ref, _, bytes) =
-                wit::build_wit_dir(&path.clone(), client.clone(), &mut lock_file).await?;
             // There is no way to check if we are in a git repository unlike `cargo publish --allow-dirty` so
-            // check against previous values.
-            if lock_file != prev_lock_ref && !self.dry_run {
-                return Err(anyhow!(
-                    "wkg.lock would be updated during publish, aborting"
-                ))
-                .with_context(|| {
-                    format!(
-                        "Run `wkg wit build {}` before attempting to publish",
-                        path.display()
-                    )
-                });
-            }
+            // require the lock file to be up to date instead, unless this is a dry run.
+            let mode = match self.lock.mode() {
+                LockMode::Update if !self.dry_run => LockMode::Locked,
+                mode => mode,
+            };
+            let mut lock_file = LockFile::load(true).await?.with_mode(mode);
+            let (pkg_ref, _, bytes) =
+                wit::build_wit_dir(&path.clone(), client.clone(), &mut lock_file)
+                    .await
+                    .and_then(|built| lock_file.check_unchanged().map(|_| built))
+                    .map_err(|e| {
+                        if e.downcast_ref::<LockFileOutdated>().is_some() {
+                            e.context(format!(
+                                "Run `wkg build --wit-dir {}` before attempting to publish",
+                                path.display()
+                            ))
+                        } else {
+                            e
+                        }
+                    })?;
 
             let tmp = temp_wit_file(&pkg_ref, &bytes).await?;
 
@@ -470,6 +586,72 @@ impl PublishArgs {
     }
 }
 
+#[derive(Args, Debug)]
+pub struct YankArgs {
+    /// The package release to yank, specified as `<namespace>:<name>@<version>`, e.g.
+    /// `wasi:http@0.2.0`.
+    package_spec: PackageSpec,
+
+    /// Un-yank a previously yanked release instead.
+    #[arg(long)]
+    undo: bool,
+
+    #[command(flatten)]
+    registry_args: RegistryArgs,
+
+    #[command(flatten)]
+    common: Common,
+}
+
+impl YankArgs {
+    pub async fn run(self) -> anyhow::Result<()> {
+        let PackageSpec { package, version } = self.package_spec;
+        let version = version.context("a version is required to yank a package release")?;
+        let client = Client::new(self.common.load_config().await?);
+        let registry = self.registry_args.registry;
+        if self.undo {
+            client.unyank(&package, &version, registry).await?;
+            eprintln!("Unyanked {package}@{version}");
+        } else {
+            client.yank(&package, &version, registry).await?;
+            eprintln!("Yanked {package}@{version}");
+        }
+        Ok(())
+    }
+}
+
+/// The output format of commands that print reports.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum OutputFormat {
+    /// Human readable text
+    #[default]
+    Text,
+    /// JSON for use by other tooling
+    Json,
+}
+
+/// Renders the rows as a table with aligned columns, the first row being the header.
+pub(crate) fn render_table<const N: usize>(rows: &[[String; N]]) -> String {
+    let mut widths = [0; N];
+    for row in rows.iter() {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+    let mut out = String::new();
+    for row in rows.iter() {
+        let line = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:width$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
 #[derive(ValueEnum, Clone, Debug, PartialEq)]
 enum Format {
     Auto,
@@ -490,7 +672,7 @@ impl GetArgs {
         }
         let client = Client::new(config);
         let cache = self.common.load_cache().await?;
-        let client = CachingClient::new(Some(client), cache);
+        let client = self.common.caching_client(client, cache);
 
         let version = match version {
             Some(ver) => ver,
@@ -655,10 +837,15 @@ async fn main() -> anyhow::Result<()> {
         Commands::Config(args) => args.run().await,
         Commands::Get(args) => args.run().await,
         Commands::Publish(args) => args.run().await,
+        Commands::Yank(args) => args.run().await,
         Commands::Oci(args) => args.run().await,
         Commands::Build(args) => args.run().await,
         Commands::Fetch(args) => args.run().await,
         Commands::Update(args) => args.run().await,
+        Commands::Tree(args) => args.run().await,
+        Commands::Outdated(args) => args.run().await,
+        Commands::Vendor(args) => args.run().await,
+        Commands::Cache(args) => args.run().await,
         Commands::Wit(args) => {
             warnln!("`wkg wit <command>` is deprecated");
             helpln!("use `wkg <command>` instead");
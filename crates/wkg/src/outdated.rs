@@ -0,0 +1,172 @@
+//! Args and command for reporting locked dependencies that have newer releases
+use std::path::PathBuf;
+
+use anstream::{print, println};
+use anyhow::Context;
+use clap::Args;
+use serde_json::json;
+use wasm_pkg_client::{PackageRef, Registry, Version, VersionReq};
+use wasm_pkg_core::lock::LockFile;
+
+use crate::wit::FetchContext;
+use crate::{Common, OutputFormat};
+
+/// Report the locked dependencies that have newer releases. For every locked version this shows
+/// the latest release that is compatible with the version requirement and the latest release
+/// overall. Releases are listed from the registry each package was locked to. Yanked releases are
+/// never reported, and prereleases only for locked prereleases.
+#[derive(Debug, Args)]
+pub struct OutdatedArgs {
+    /// The directory containing the WIT files to check the lock file of.
+    /// Falls back to workspace manifest if empty.
+    pub dir: Option<PathBuf>,
+
+    /// The output format. Valid options are "text" or "json".
+    #[clap(long, value_enum, default_value_t)]
+    pub format: OutputFormat,
+
+    /// Exit with an error if any dependency is outdated, e.g. for use in CI.
+    #[clap(long)]
+    pub exit_code: bool,
+
+    #[clap(flatten)]
+    pub common: Common,
+}
+
+/// A locked version of a package that has a newer release.
+struct Outdated {
+    package: PackageRef,
+    registry: Option<String>,
+    requirement: VersionReq,
+    current: Version,
+    compatible: Option<Version>,
+    latest: Option<Version>,
+}
+
+impl OutdatedArgs {
+    pub async fn run(self) -> anyhow::Result<()> {
+        let context = FetchContext::discover(self.dir).await?;
+        if !tokio::fs::try_exists(&context.lock_path).await? {
+            anyhow::bail!(
+                "no lock file found at {}, run `wkg fetch` to create one",
+                context.lock_path.display()
+            );
+        }
+        let lock_file = LockFile::load_from_path(&context.lock_path, true)
+            .await
+            .with_context(|| {
+                format!(
+                    "failed to load lock file at {}",
+                    context.lock_path.display()
+                )
+            })?;
+
+        let client = self.common.get_client().await?;
+
+        let mut outdated = Vec::new();
+        for pkg in lock_file.packages.iter() {
+            crate::statusln!("Checking", "{}", pkg.name);
+            let resolved = client
+                .config()
+                .and_then(|config| config.resolve_registry(&pkg.name))
+                .map(ToString::to_string);
+            let versions = match &pkg.registry {
+                // The cache holds the versions of the registry the package resolves to, so other
+                // registries are listed directly
+                Some(registry) if Some(registry) != resolved.as_ref() => {
+                    let registry: Registry = registry
+                        .parse()
+                        .with_context(|| format!("invalid locked registry {registry}"))?;
+                    client
+                        .client()?
+                        .list_all_versions_from(&pkg.name, Some(registry))
+                        .await
+                }
+                _ => client.list_all_versions(&pkg.name).await,
+            };
+            let versions: Vec<Version> = versions
+                .with_context(|| format!("failed to list versions of {}", pkg.name))?
+                .into_iter()
+                .filter_map(|info| (!info.yanked).then_some(info.version))
+                .collect();
+            for locked in pkg.versions.iter() {
+                let compatible = versions
+                    .iter()
+                    .filter(|version| locked.requirement.matches(version))
+                    .max();
+                let latest = versions
+                    .iter()
+                    .filter(|version| version.pre.is_empty() || !locked.version.pre.is_empty())
+                    .max();
+                if compatible.is_none_or(|v| v <= &locked.version)
+                    && latest.is_none_or(|v| v <= &locked.version)
+                {
+                    continue;
+                }
+                outdated.push(Outdated {
+                    package: pkg.name.clone(),
+                    registry: pkg.registry.clone(),
+                    requirement: locked.requirement.clone(),
+                    current: locked.version.clone(),
+                    compatible: compatible.cloned(),
+                    latest: latest.cloned(),
+                });
+            }
+        }
+
+        match self.format {
+            OutputFormat::Text if outdated.is_empty() => {
+                crate::statusln!("Finished", "all locked dependencies are up to date");
+            }
+            OutputFormat::Text => print!("{}", render_table(&outdated)),
+            OutputFormat::Json => {
+                let entries: Vec<_> = outdated
+                    .iter()
+                    .map(|entry| {
+                        json!({
+                            "package": entry.package.to_string(),
+                            "registry": entry.registry,
+                            "requirement": entry.requirement.to_string(),
+                            "current": entry.current.to_string(),
+                            "compatible": entry.compatible.as_ref().map(ToString::to_string),
+                            "latest": entry.latest.as_ref().map(ToString::to_string),
+                        })
+                    })
+                    .collect();
+                println!("{}", serde_json::to_string_pretty(&entries)?);
+            }
+        }
+
+        if self.exit_code && !outdated.is_empty() {
+            anyhow::bail!(
+                "{} locked dependency version(s) are outdated",
+                outdated.len()
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Renders the outdated versions as a table with aligned columns.
+fn render_table(outdated: &[Outdated]) -> String {
+    let display = |version: &Option<Version>| {
+        version
+            .as_ref()
+            .map(ToString::to_string)
+            .unwrap_or_else(|| "-".to_string())
+    };
+    let rows: Vec<[String; 5]> = std::iter::once(
+        ["Package", "Requirement", "Current", "Compatible", "Latest"].map(String::from),
+    )
+    .chain(outdated.iter().map(|entry| {
+        [
+            entry.package.to_string(),
+            entry.requirement.to_string(),
+            entry.current.to_string(),
+            display(&entry.compatible),
+            display(&entry.latest),
+        ]
+    }))
+    .collect();
+    crate::render_table(&rows)
+}
@@ -3,24 +3,32 @@ use std::io::Cursor;
 use std::path::PathBuf;
 
 use anyhow::Context;
+use futures_util::StreamExt;
 use wasm_pkg_client::{
     Client, PublishOpts,
-    caching::{CachingClient, FileCache},
+    caching::{Cache, CachedVersions, CachingClient, FileCache, LayeredCache},
     local::LocalConfig,
 };
 use wasm_pkg_common::{
     config::{Config, RegistryConfig, RegistryMapping},
     metadata::LOCAL_PROTOCOL,
-    package::PackageRef,
+    package::{PackageRef, Version},
     registry::Registry,
 };
-use wasm_pkg_core::{lock::LockFile, resolver::PublishPlan};
+use wasm_pkg_core::{
+    lock::{LockFile, LockMode},
+    resolver::PublishPlan,
+};
 
 use crate::wit::build_wit_dir;
 
 /// A [`CachingClient`] and [`PublishPlan`] wired to a temporary local backend
+///
+/// In offline mode, the returned client only reads from the cache. The members are then added to
+/// the cache as they are published to the temporary backend, which is the only registry that is
+/// accessed.
 pub(crate) struct PublishVerifier {
-    pub(crate) client: CachingClient<FileCache>,
+    pub(crate) client: CachingClient<LayeredCache<FileCache>>,
     pub(crate) plan: PublishPlan,
     pub(crate) packages: BTreeSet<PackageRef>,
     pub(crate) data: HashMap<PackageRef, Vec<u8>>,
@@ -33,9 +41,10 @@ impl PublishVerifier {
         paths: &[PathBuf],
         registry_name: &str,
         mut base_config: Config,
-        cache: FileCache,
+        cache: LayeredCache<FileCache>,
         lock_file: &mut LockFile,
         capture_bytes: bool,
+        offline: bool,
     ) -> anyhow::Result<PublishVerifier> {
         let local_config = LocalConfig::temp_dir()?;
         let reg_config =
@@ -60,7 +69,19 @@ impl PublishVerifier {
             );
         }
 
-        let client = CachingClient::new(Some(Client::new(base_config)), cache);
+        // Frozen lock files resolve offline as well
+        let offline = offline || lock_file.mode() == LockMode::Frozen;
+        let member_cache = cache.clone();
+        let publisher = Client::new(base_config);
+        let client = CachingClient::new(Some(publisher.clone()), cache);
+        let client = if offline {
+            client.into_offline()
+        } else {
+            client
+        };
+
+        // Members depend on each other but are built from source, so they are never locked
+        lock_file.exclude_packages(packages.iter().cloned());
 
         let mut bytes_by_package = HashMap::new();
         for spec in plan.iter() {
@@ -76,8 +97,7 @@ impl PublishVerifier {
                     format!("failed to read workspace member at {}", path.display())
                 })?
             };
-            client
-                .client()?
+            let (package, version) = publisher
                 .publish_release_data(
                     Box::pin(Cursor::new(bytes.clone())),
                     PublishOpts {
@@ -89,6 +109,11 @@ impl PublishVerifier {
                 )
                 .await
                 .with_context(|| format!("verifier failed to publish: {}", spec.package))?;
+            if offline {
+                cache_member(&publisher, &member_cache, &package, &version, &bytes)
+                    .await
+                    .with_context(|| format!("verifier failed to cache: {}", spec.package))?;
+            }
             if capture_bytes {
                 bytes_by_package.insert(spec.package.clone(), bytes);
             }
@@ -103,3 +128,27 @@ impl PublishVerifier {
         })
     }
 }
+
+/// Caches the listed versions, release and content of a member that was published to the
+/// temporary backend, replacing anything cached for it before.
+async fn cache_member(
+    publisher: &Client,
+    cache: &LayeredCache<FileCache>,
+    package: &PackageRef,
+    version: &Version,
+    bytes: &[u8],
+) -> anyhow::Result<()> {
+    let versions = publisher.list_all_versions(package).await?;
+    cache
+        .put_versions(package, &CachedVersions::new(versions))
+        .await?;
+    let release = publisher.get_release(package, version).await?;
+    cache
+        .put_data(
+            release.content_digest.clone(),
+            futures_util::stream::iter([Ok(bytes.to_vec().into())]).boxed(),
+        )
+        .await?;
+    cache.put_release(package, &release).await?;
+    Ok(())
+}
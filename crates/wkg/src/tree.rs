@@ -0,0 +1,376 @@
+//! Args and command for printing the resolved dependency graph
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use anstream::{print, println};
+use anyhow::Context;
+use clap::Args;
+use serde_json::{Value, json};
+use wasm_pkg_client::{
+    ContentDigest, PackageRef, VersionReq,
+    caching::{CachingClient, FileCache, LayeredCache},
+};
+use wasm_pkg_common::package::PackageSpec;
+use wasm_pkg_core::{
+    lock::{LOCK_FILE_NAME, LockFile},
+    manifest::Manifest,
+    resolver::{DependencyResolution, DependencyResolutionMap},
+    wit,
+};
+
+use crate::overlay::PublishVerifier;
+use crate::wit::FetchContext;
+use crate::{Common, OutputFormat};
+
+/// Print the resolved dependency graph of the package(s) as a tree. This resolves the
+/// dependencies the same way as `fetch`, using the lock file if one exists, but doesn't write
+/// anything to the `deps` directory.
+#[derive(Debug, Args)]
+pub struct TreeArgs {
+    /// The directory containing the WIT files to print the dependency tree for.
+    /// Falls back to workspace manifest if empty.
+    pub dir: Option<PathBuf>,
+
+    /// Print the packages that depend on the given package instead of the dependencies, e.g.
+    /// `wasi:io`.
+    #[clap(short = 'i', long, value_name = "PACKAGE")]
+    pub invert: Option<PackageRef>,
+
+    /// The output format. Valid options are "text" or "json".
+    #[clap(long, value_enum, default_value_t)]
+    pub format: OutputFormat,
+
+    #[clap(flatten)]
+    pub common: Common,
+}
+
+impl TreeArgs {
+    pub async fn run(self) -> anyhow::Result<()> {
+        let context = FetchContext::discover(self.dir).await?;
+        // The tree is only printed, so the lock file is read-only and an empty one is used if it
+        // doesn't exist yet, without creating it in the project
+        let scratch_dir = tempfile::tempdir()?;
+        let mut lock_file = if tokio::fs::try_exists(&context.lock_path).await? {
+            LockFile::load_from_path(&context.lock_path, true)
+                .await
+                .with_context(|| {
+                    format!(
+                        "failed to load lock file at {}",
+                        context.lock_path.display()
+                    )
+                })?
+        } else {
+            LockFile::new_with_path([], scratch_dir.path().join(LOCK_FILE_NAME)).await?
+        };
+
+        let mut graph = DependencyGraph::default();
+        match context.root.as_ref() {
+            Some(root) => {
+                // Workspace members are resolved through the publish verifier, just like `fetch`
+                let verifier = PublishVerifier::try_new(
+                    root.members.as_ref(),
+                    "tmp_local_tree",
+                    self.common.load_config().await?,
+                    self.common.load_cache().await?,
+                    &mut lock_file,
+                    false,
+                    self.common.offline,
+                )
+                .await?;
+                for dir in &context.dirs {
+                    graph
+                        .add_dir(&context.manifest, dir, &lock_file, verifier.client.clone())
+                        .await?;
+                }
+                graph.mark_workspace(&verifier.packages);
+            }
+            None => {
+                let client = self.common.get_client().await?;
+                for dir in &context.dirs {
+                    graph
+                        .add_dir(&context.manifest, dir, &lock_file, client.clone())
+                        .await?;
+                }
+            }
+        }
+
+        let (roots, inverted) = match &self.invert {
+            Some(package) => {
+                let roots = graph.sorted(
+                    (0..graph.nodes.len()).filter(|idx| &graph.nodes[*idx].spec.package == package),
+                );
+                if roots.is_empty() {
+                    anyhow::bail!("package `{package}` is not in the dependency graph");
+                }
+                (roots, true)
+            }
+            None => (graph.roots.clone(), false),
+        };
+
+        match self.format {
+            OutputFormat::Text => {
+                let trees: Vec<String> = roots
+                    .iter()
+                    .map(|root| graph.render(*root, inverted))
+                    .collect();
+                print!("{}", trees.join("\n"));
+            }
+            OutputFormat::Json => {
+                let trees: Vec<Value> = roots
+                    .iter()
+                    .map(|root| graph.to_json(*root, inverted))
+                    .collect();
+                println!("{}", serde_json::to_string_pretty(&trees)?);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Where a package in the dependency graph comes from.
+enum Source {
+    /// The package of a WIT directory the graph was resolved for
+    Root(PathBuf),
+    /// A package of another member of the workspace
+    Workspace,
+    /// A package resolved from a registry
+    Registry {
+        registry: Option<String>,
+        digest: ContentDigest,
+    },
+    /// A package resolved from a local path
+    Local(PathBuf),
+}
+
+struct Node {
+    spec: PackageSpec,
+    source: Source,
+}
+
+impl std::fmt::Display for Node {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.spec)?;
+        match &self.source {
+            Source::Root(path) => write!(f, " ({})", path.display()),
+            Source::Workspace => write!(f, " (workspace)"),
+            Source::Registry {
+                registry: Some(registry),
+                digest,
+            } => write!(f, " (registry: {registry}, {digest})"),
+            Source::Registry {
+                registry: None,
+                digest,
+            } => write!(f, " (registry, {digest})"),
+            Source::Local(path) => write!(f, " (local: {})", path.display()),
+        }
+    }
+}
+
+/// The resolved packages of every WIT directory along with the packages each of them references.
+#[derive(Default)]
+struct DependencyGraph {
+    nodes: Vec<Node>,
+    index: HashMap<PackageSpec, usize>,
+    dependencies: Vec<BTreeSet<usize>>,
+    roots: Vec<usize>,
+}
+
+impl DependencyGraph {
+    /// Resolves the dependencies of the given WIT directory and adds them to the graph.
+    async fn add_dir(
+        &mut self,
+        manifest: &Manifest,
+        dir: &Path,
+        lock_file: &LockFile,
+        client: CachingClient<LayeredCache<FileCache>>,
+    ) -> anyhow::Result<()> {
+        let (root, references) = wit::get_packages(dir)?;
+        let resolved = wit::resolve_dependencies(manifest, dir, Some(lock_file), client)
+            .await
+            .with_context(|| format!("failed to resolve dependencies for {}", dir.display()))?;
+        let decoded = resolved.decode_dependencies().await?;
+
+        let root = self.add_node(root, Source::Root(dir.to_path_buf()));
+        if !self.roots.contains(&root) {
+            self.roots.push(root);
+        }
+        for (package, requirement) in references {
+            if let Some(resolution) = find_resolution(&resolved, &package, &requirement) {
+                let dependency = self.add_resolution(resolution);
+                self.dependencies[root].insert(dependency);
+            }
+        }
+        for dep in decoded.values() {
+            let dependent = self.add_resolution(dep.resolution());
+            for (package, requirement) in dep.referenced_packages() {
+                if let Some(resolution) = find_resolution(&resolved, &package, &requirement) {
+                    let dependency = self.add_resolution(resolution);
+                    self.dependencies[dependent].insert(dependency);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn add_resolution(&mut self, resolution: &DependencyResolution) -> usize {
+        let spec = PackageSpec {
+            package: resolution.name().clone(),
+            version: resolution.version().cloned(),
+        };
+        let source = match resolution {
+            DependencyResolution::Registry(res) => Source::Registry {
+                registry: res.registry.clone(),
+                digest: res.digest.clone(),
+            },
+            DependencyResolution::Local(res) => Source::Local(res.path.clone()),
+        };
+        self.add_node(spec, source)
+    }
+
+    /// Adds the node if it isn't in the graph yet. A root always replaces the source of an
+    /// existing node, as another workspace member may have already resolved it as a dependency.
+    fn add_node(&mut self, spec: PackageSpec, source: Source) -> usize {
+        if let Some(idx) = self.index.get(&spec).copied() {
+            if matches!(source, Source::Root(_)) {
+                self.nodes[idx].source = source;
+            }
+            return idx;
+        }
+        let idx = self.nodes.len();
+        self.index.insert(spec.clone(), idx);
+        self.nodes.push(Node { spec, source });
+        self.dependencies.push(BTreeSet::new());
+        idx
+    }
+
+    /// Marks the packages of workspace members that aren't roots of the graph.
+    fn mark_workspace(&mut self, packages: &BTreeSet<PackageRef>) {
+        for node in self.nodes.iter_mut() {
+            if packages.contains(&node.spec.package) && !matches!(node.source, Source::Root(_)) {
+                node.source = Source::Workspace;
+            }
+        }
+    }
+
+    /// Returns the dependencies of the node, or the packages that depend on it when inverted.
+    fn children(&self, node: usize, inverted: bool) -> Vec<usize> {
+        if inverted {
+            self.sorted((0..self.nodes.len()).filter(|idx| self.dependencies[*idx].contains(&node)))
+        } else {
+            self.sorted(self.dependencies[node].iter().copied())
+        }
+    }
+
+    fn sorted(&self, nodes: impl IntoIterator<Item = usize>) -> Vec<usize> {
+        let mut nodes: Vec<usize> = nodes.into_iter().collect();
+        nodes.sort_by(|a, b| self.nodes[*a].spec.cmp(&self.nodes[*b].spec));
+        nodes
+    }
+
+    /// Renders the tree starting at the given node. Nodes that were already expanded are marked
+    /// with `(*)` instead of being expanded again.
+    fn render(&self, root: usize, inverted: bool) -> String {
+        let mut out = self.nodes[root].to_string();
+        self.render_children(
+            &mut out,
+            root,
+            inverted,
+            &mut String::new(),
+            &mut HashSet::new(),
+        );
+        out
+    }
+
+    fn render_children(
+        &self,
+        out: &mut String,
+        node: usize,
+        inverted: bool,
+        prefix: &mut String,
+        expanded: &mut HashSet<usize>,
+    ) {
+        let children = self.children(node, inverted);
+        if !children.is_empty() && !expanded.insert(node) {
+            out.push_str(" (*)\n");
+            return;
+        }
+        out.push('\n');
+        for (i, child) in children.iter().enumerate() {
+            let last = i + 1 == children.len();
+            out.push_str(prefix);
+            out.push_str(if last { "└── " } else { "├── " });
+            out.push_str(&self.nodes[*child].to_string());
+            let len = prefix.len();
+            prefix.push_str(if last { "    " } else { "│   " });
+            self.render_children(out, *child, inverted, prefix, expanded);
+            prefix.truncate(len);
+        }
+    }
+
+    /// Returns the tree starting at the given node as JSON. Nodes that were already expanded are
+    /// marked with `"deduplicated": true` instead of being expanded again.
+    fn to_json(&self, root: usize, inverted: bool) -> Value {
+        self.node_json(root, inverted, &mut HashSet::new())
+    }
+
+    fn node_json(&self, node: usize, inverted: bool, expanded: &mut HashSet<usize>) -> Value {
+        let Node { spec, source } = &self.nodes[node];
+        let mut value = json!({
+            "package": spec.package.to_string(),
+            "version": spec.version.as_ref().map(ToString::to_string),
+        });
+        match source {
+            Source::Root(path) => {
+                value["source"] = "root".into();
+                value["path"] = path.display().to_string().into();
+            }
+            Source::Workspace => value["source"] = "workspace".into(),
+            Source::Registry { registry, digest } => {
+                value["source"] = "registry".into();
+                value["registry"] = registry.clone().into();
+                value["digest"] = digest.to_string().into();
+            }
+            Source::Local(path) => {
+                value["source"] = "local".into();
+                value["path"] = path.display().to_string().into();
+            }
+        }
+        let children = self.children(node, inverted);
+        if !children.is_empty() && !expanded.insert(node) {
+            value["deduplicated"] = true.into();
+            return value;
+        }
+        let key = if inverted {
+            "dependents"
+        } else {
+            "dependencies"
+        };
+        value[key] = children
+            .into_iter()
+            .map(|child| self.node_json(child, inverted, expanded))
+            .collect();
+        value
+    }
+}
+
+/// Finds the resolution a package reference resolved to.
+fn find_resolution<'a>(
+    resolved: &'a DependencyResolutionMap,
+    package: &'a PackageRef,
+    requirement: &VersionReq,
+) -> Option<&'a DependencyResolution> {
+    resolved
+        .versions_of(package)
+        .filter(|res| res.version().is_none_or(|v| requirement.matches(v)))
+        .max_by(|a, b| a.version().cmp(&b.version()))
+        .or_else(|| {
+            // The reference may have been widened and unified with another version
+            let widened = resolved
+                .widened_requirements()
+                .iter()
+                .find(|w| &w.name == package && requirement.matches(&w.referenced))?;
+            resolved
+                .versions_of(package)
+                .find(|res| res.version() == Some(&widened.version))
+        })
+}
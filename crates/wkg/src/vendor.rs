@@ -0,0 +1,162 @@
+//! Args and command for vendoring the locked dependencies into a local registry directory
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+
+use anstream::print;
+use anyhow::Context;
+use clap::Args;
+use futures_util::TryStreamExt;
+use wasm_pkg_client::{Client, Release};
+use wasm_pkg_common::{
+    config::{Config, RegistryConfig, RegistryMapping},
+    metadata::LOCAL_PROTOCOL,
+    registry::Registry,
+};
+use wasm_pkg_core::lock::{LOCK_FILE_NAME, LockFile};
+
+use crate::Common;
+
+/// Copy the content of every locked registry dependency into a directory that can be used as the
+/// root of a `local` registry, e.g. to build without access to a registry. Packages are loaded
+/// from the cache (and fetched into it if needed) and checked against the digests in the lock
+/// file. This prints the config that maps the vendored namespaces to their registries, using the
+/// `local` backend for those registries, unless `--config-out` is given. The registries keep the
+/// names they have in the lock file so that the lock file stays valid with this config. Packages
+/// that are locked without a registry are mapped to the registry the current config resolves for
+/// them, e.g. the default registry.
+#[derive(Debug, Args)]
+pub struct VendorArgs {
+    /// The directory to vendor the dependencies into. Packages are written to
+    /// `<namespace>/<name>/<version>.wasm`.
+    pub dir: PathBuf,
+
+    /// The lock file to vendor the dependencies of.
+    #[clap(long, default_value = LOCK_FILE_NAME)]
+    pub lock_file: PathBuf,
+
+    /// Write the generated config to this file instead of printing it.
+    #[clap(long, value_name = "PATH")]
+    pub config_out: Option<PathBuf>,
+
+    #[clap(flatten)]
+    pub common: Common,
+}
+
+impl VendorArgs {
+    pub async fn run(self) -> anyhow::Result<()> {
+        if !tokio::fs::try_exists(&self.lock_file).await? {
+            anyhow::bail!(
+                "no lock file found at {}, run `wkg fetch` to create one",
+                self.lock_file.display()
+            );
+        }
+        let lock_file = LockFile::load_from_path(&self.lock_file, true)
+            .await
+            .with_context(|| format!("failed to load lock file at {}", self.lock_file.display()))?;
+        let base_config = self.common.load_config().await?;
+        let client = self.common.caching_client(
+            Client::new(base_config.clone()),
+            self.common.load_cache().await?,
+        );
+        // The generated config is used from other directories as well
+        tokio::fs::create_dir_all(&self.dir)
+            .await
+            .with_context(|| format!("failed to create {}", self.dir.display()))?;
+        let dir = tokio::fs::canonicalize(&self.dir)
+            .await
+            .with_context(|| format!("failed to resolve {}", self.dir.display()))?;
+
+        let mut registries: BTreeMap<_, BTreeMap<_, Registry>> = BTreeMap::new();
+        for pkg in lock_file.packages.iter() {
+            let package_dir = dir
+                .join(pkg.name.namespace().as_ref())
+                .join(pkg.name.name().as_ref());
+            tokio::fs::create_dir_all(&package_dir)
+                .await
+                .with_context(|| format!("failed to create {}", package_dir.display()))?;
+            for locked in pkg.versions.iter() {
+                crate::statusln!("Vendoring", "{}@{}", pkg.name, locked.version);
+                let release = Release {
+                    version: locked.version.clone(),
+                    content_digest: locked.digest.clone(),
+                };
+                let stream = client.get_content(&pkg.name, &release).await?;
+                let content: Vec<u8> = locked
+                    .digest
+                    .validating_stream(stream)
+                    .try_fold(Vec::new(), |mut content, bytes| async move {
+                        content.extend_from_slice(&bytes);
+                        Ok(content)
+                    })
+                    .await
+                    .with_context(|| {
+                        format!(
+                            "content of {}@{} doesn't match the lock file",
+                            pkg.name, locked.version
+                        )
+                    })?;
+                let path = package_dir.join(format!("{}.wasm", locked.version));
+                tokio::fs::write(&path, content)
+                    .await
+                    .with_context(|| format!("failed to write {}", path.display()))?;
+            }
+            // Packages that are not locked to a registry are resolved through the config, just
+            // like when they were locked
+            let registry = match pkg.registry.as_deref() {
+                Some(registry) => Some(registry.parse()?),
+                None => base_config.resolve_registry(&pkg.name).cloned(),
+            };
+            match registry {
+                Some(registry) => {
+                    registries
+                        .entry(pkg.name.namespace().clone())
+                        .or_default()
+                        .insert(pkg.name.clone(), registry);
+                }
+                None => crate::warnln!(
+                    "{} is not locked to a registry and there is no default registry, so it can't be mapped to the vendored packages",
+                    pkg.name
+                ),
+            }
+        }
+
+        let mut config = Config::empty();
+        // The same as a `LocalConfig`, which can only be created for a temporary directory
+        let registry_config = RegistryConfig::default()
+            .with_default_backend(LOCAL_PROTOCOL, HashMap::from([("root", &dir)]))?;
+        for (namespace, packages) in registries {
+            let mut namespace_registries = packages.values();
+            let first = namespace_registries.next().cloned();
+            if let Some(registry) = first.filter(|r| namespace_registries.all(|other| other == r)) {
+                config.set_namespace_registry(namespace, RegistryMapping::Registry(registry));
+            } else {
+                // The packages of the namespace come from different registries
+                for (package, registry) in packages.iter() {
+                    config.set_package_registry_override(
+                        package.clone(),
+                        RegistryMapping::Registry(registry.clone()),
+                    );
+                }
+            }
+            for registry in packages.into_values() {
+                *config.get_or_insert_registry_config_mut(&registry) = registry_config.clone();
+            }
+        }
+        match self.config_out {
+            Some(path) => {
+                config.to_file(&path).await?;
+                crate::statusln!(
+                    "Finished",
+                    "vendored dependencies into {}, config written to {}",
+                    dir.display(),
+                    path.display()
+                );
+            }
+            None => {
+                print!("{}", toml::to_string(&config)?);
+                crate::statusln!("Finished", "vendored dependencies into {}", dir.display());
+            }
+        }
+        Ok(())
+    }
+}
@@ -6,7 +6,7 @@ use anstream::eprintln;
 use anyhow::Context;
 use clap::{Args, Subcommand};
 use tempfile::NamedTempFile;
-use wasm_pkg_client::caching::{CachingClient, FileCache};
+use wasm_pkg_client::caching::{CachingClient, FileCache, LayeredCache};
 use wasm_pkg_common::package::{PackageRef, Version};
 use wasm_pkg_core::wit::WIT_DEPS_DIR;
 use wasm_pkg_core::{
@@ -18,8 +18,8 @@ use wasm_pkg_core::{
     wit::{self, OutputType},
 };
 
-use crate::Common;
 use crate::overlay::PublishVerifier;
+use crate::{Common, LockArgs};
 
 /// Commands for interacting with wit
 #[derive(Debug, Subcommand)]
@@ -53,6 +53,9 @@ pub struct BuildArgs {
     #[clap(short = 'o', long = "output")]
     pub output: Option<PathBuf>,
 
+    #[clap(flatten)]
+    pub lock: LockArgs,
+
     #[clap(flatten)]
     pub common: Common,
 }
@@ -74,17 +77,30 @@ pub struct FetchArgs {
     #[clap(short = 't', long = "type")]
     pub output_type: Option<OutputType>,
 
+    #[clap(flatten)]
+    pub lock: LockArgs,
+
     #[clap(flatten)]
     pub common: Common,
 }
 
-/// Update the lock file with the latest dependencies. This will update all dependencies and
-/// generate a new lock file.
+/// Update the lock file with the latest dependencies. By default this will update all
+/// dependencies and generate a new lock file. If any packages are given, only those packages are
+/// re-resolved and all other locked dependencies are kept as they are.
 #[derive(Debug, Args)]
 pub struct UpdateArgs {
+    /// The packages to update, e.g. `wasi:http`. Updates all dependencies if none are given.
+    pub packages: Vec<PackageRef>,
+
+    /// Update the given package to exactly this version instead of the latest matching version.
+    /// Requires exactly one package to be given.
+    #[clap(long, value_name = "VERSION", requires = "packages")]
+    pub precise: Option<Version>,
+
     /// The directory containing the WIT files to update dependencies for.
-    #[clap(short = 'd', long = "wit-dir", default_value = "wit")]
-    pub dir: PathBuf,
+    /// Falls back to workspace manifest if empty.
+    #[clap(short = 'd', long = "wit-dir")]
+    pub dir: Option<PathBuf>,
 
     /// The desired output type of the dependencies. Valid options are "wit" or "wasm" (wasm is the
     /// WIT package binary format).
@@ -98,8 +114,10 @@ pub struct UpdateArgs {
 impl BuildArgs {
     pub async fn run(self) -> anyhow::Result<()> {
         let client = self.common.get_client().await?;
-        let mut lock_file = LockFile::load(false).await?;
+        let mut lock_file = LockFile::load(false).await?.with_mode(self.lock.mode());
         let (pkg_ref, version, bytes) = build_wit_dir(&self.dir, client, &mut lock_file).await?;
+        // Don't write any output if the lock file is locked but would change
+        lock_file.check_unchanged()?;
         let output_path = if let Some(path) = self.output {
             path
         } else {
@@ -123,7 +141,7 @@ impl BuildArgs {
 /// version, and the encoded component bytes.
 pub async fn build_wit_dir(
     dir: impl AsRef<Path>,
-    client: CachingClient<FileCache>,
+    client: CachingClient<LayeredCache<FileCache>>,
     lock_file: &mut LockFile,
 ) -> anyhow::Result<(PackageRef, Option<Version>, Vec<u8>)> {
     check_dir(&dir).await?;
@@ -156,6 +174,32 @@ pub async fn temp_wit_file(package: &PackageRef, bytes: &[u8]) -> anyhow::Result
 
 impl FetchArgs {
     pub async fn run(self) -> anyhow::Result<()> {
+        let context = FetchContext::discover(self.dir).await?;
+        let lock_file = load_or_create_lock(&context.lock_path)
+            .await?
+            .with_mode(self.lock.mode());
+        context
+            .fetch(
+                &self.common,
+                self.output_type.unwrap_or_default(),
+                lock_file,
+            )
+            .await
+    }
+}
+
+/// The WIT directories, manifest and lock file that a `fetch` or `update` operates on.
+pub(crate) struct FetchContext {
+    pub(crate) dirs: Vec<PathBuf>,
+    pub(crate) manifest: Manifest,
+    pub(crate) root: Option<WorkspaceRootConfig>,
+    pub(crate) lock_path: PathBuf,
+}
+
+impl FetchContext {
+    /// Discovers the context for the current directory. If no directory is given, this falls back
+    /// to the members of the workspace root (if any) and then to `wit`.
+    pub(crate) async fn discover(dir: Option<PathBuf>) -> anyhow::Result<Self> {
         let cwd = std::env::current_dir()?;
         let mut root = Manifest::load_root_workspace(&cwd)?;
         let manifest_path = find_root_manifest_for_wd(&cwd);
@@ -168,7 +212,7 @@ impl FetchArgs {
             root = None;
         }
 
-        let dirs = if let Some(dir) = self.dir.clone() {
+        let dirs = if let Some(dir) = dir {
             vec![dir]
         } else {
             root.as_ref()
@@ -185,72 +229,92 @@ impl FetchArgs {
                 None => Manifest::default(),
             },
         };
-        let output = self.output_type.unwrap_or_default();
 
         for dir in &dirs {
             check_dir(dir).await?;
         }
 
-        match root {
+        let lock_path = match root.as_ref() {
+            Some(root) => root.root_dir().join(LOCK_FILE_NAME),
+            None => manifest_path
+                .as_deref()
+                .and_then(Path::parent)
+                .unwrap_or(&cwd)
+                .join(LOCK_FILE_NAME),
+        };
+
+        Ok(Self {
+            dirs,
+            manifest,
+            root,
+            lock_path,
+        })
+    }
+
+    /// Fetches the dependencies of every directory using the given lock file and writes the
+    /// updated lock file back to disk.
+    async fn fetch(
+        &self,
+        common: &Common,
+        output: OutputType,
+        lock_file: LockFile,
+    ) -> anyhow::Result<()> {
+        match self.root.as_ref() {
             Some(root) => {
-                self.run_workspace_fetch(&dirs, output, &manifest, root)
-                    .await
-            }
-            None => {
-                let lock_dir = manifest_path
-                    .as_deref()
-                    .and_then(Path::parent)
-                    .unwrap_or(&cwd);
-                self.fetch_into_lock(&dirs, &manifest, output, lock_dir.join(LOCK_FILE_NAME))
+                self.run_workspace_fetch(common, output, root, lock_file)
                     .await
             }
+            None => self.fetch_into_lock(common, output, lock_file).await,
         }
     }
 
     // fetch dependneces for a given workspace root, merging dependencies trees for included packages
     async fn run_workspace_fetch(
         &self,
-        dirs: &[PathBuf],
+        common: &Common,
         output: OutputType,
-        manifest: &Manifest,
-        root: WorkspaceRootConfig,
+        root: &WorkspaceRootConfig,
+        mut lock_file: LockFile,
     ) -> anyhow::Result<()> {
-        // Load/create the root lock file. This is the file that will be committed back to disk
-        let lock_path = root.root_dir().join(LOCK_FILE_NAME);
-        let mut lock_file = load_or_create_lock(&lock_path).await?;
-
+        // Building the members through the verifier replaces the locked packages, so restore them
+        // before resolving the dependencies of the members
+        let locked = lock_file.packages.clone();
         let verifier = PublishVerifier::try_new(
             root.members.as_ref(),
             "tmp_local_fetch",
-            self.common.load_config().await?,
-            self.common.load_cache().await?,
+            common.load_config().await?,
+            common.load_cache().await?,
             &mut lock_file,
             false,
+            common.offline,
         )
         .await?;
+        lock_file.packages = locked;
 
         // Resolve dependencies for every requested member through the publish verifier
         let mut merged = DependencyResolutionMap::default();
-        for dir in dirs {
-            let resolved =
-                wit::resolve_dependencies(manifest, dir, Some(&lock_file), verifier.client.clone())
-                    .await
-                    .with_context(|| {
-                        format!("failed to resolve dependencies for {}", dir.display())
-                    })?;
+        for dir in &self.dirs {
+            let resolved = wit::resolve_dependencies(
+                &self.manifest,
+                dir,
+                Some(&lock_file),
+                verifier.client.clone(),
+            )
+            .await
+            .with_context(|| format!("failed to resolve dependencies for {}", dir.display()))?;
+            report_widened(&resolved);
             for (pkg, resolution) in resolved.as_ref() {
-                if verifier.packages.contains(pkg) {
+                if verifier.packages.contains(&pkg.package) {
                     continue;
                 }
                 merged.insert(pkg.clone(), resolution.clone());
             }
         }
 
-        lock_file.update_dependencies(&merged);
-        lock_file
-            .write()
-            .await
-            .with_context(|| format!("failed to commit lock file at {}", lock_path.display()))?;
+        lock_file.update_dependencies(&merged)?;
+        lock_file.write().await.with_context(|| {
+            format!("failed to commit lock file at {}", self.lock_path.display())
+        })?;
 
         // Ensure `<root-dir>/wkg/` exists and drop the aggregated deps into `<root-dir>/wkg/deps`.
         // `populate_dependencies` canonicalizes its argument, so the parent must exist.
@@ -270,24 +334,30 @@ impl FetchArgs {
 
     /// Iterate `dirs` and run [`wit::fetch_dependencies`] unioning each call's resolved lock entries into a
     /// single set.
-    /// `fetch_dependencies` replaces [`LockFile`] packages on every call so we snapshot
-    /// between calls to avoid losing earlier entries.
+    /// `fetch_dependencies` replaces [`LockFile`] packages on every call so we restore the union
+    /// before each call to avoid losing (or re-resolving) earlier entries.
     async fn fetch_into_lock(
         &self,
-        dirs: &[PathBuf],
-        manifest: &Manifest,
+        common: &Common,
         output: OutputType,
-        lock_path: PathBuf,
+        mut lock_file: LockFile,
     ) -> anyhow::Result<()> {
-        let client = self.common.get_client().await?;
-        let mut lock_file = load_or_create_lock(&lock_path).await?;
+        let client = common.get_client().await?;
 
         let mut union: BTreeSet<LockedPackage> = BTreeSet::new();
         merge_locked_packages(&mut union, std::mem::take(&mut lock_file.packages));
-        for dir in dirs {
-            wit::fetch_dependencies(manifest, dir, &mut lock_file, client.clone(), output)
-                .await
-                .with_context(|| format!("failed to fetch dependencies for {}", dir.display()))?;
+        for dir in &self.dirs {
+            lock_file.packages = union.clone();
+            let resolved = wit::fetch_dependencies(
+                &self.manifest,
+                dir,
+                &mut lock_file,
+                client.clone(),
+                output,
+            )
+            .await
+            .with_context(|| format!("failed to fetch dependencies for {}", dir.display()))?;
+            report_widened(&resolved);
             merge_locked_packages(&mut union, std::mem::take(&mut lock_file.packages));
         }
         lock_file.packages = union;
@@ -297,9 +367,23 @@ impl FetchArgs {
     }
 }
 
+/// Reports the package references that resolved to another version because of semver unification.
+fn report_widened(resolved: &DependencyResolutionMap) {
+    for widened in resolved.widened_requirements() {
+        crate::statusln!(
+            "Widened",
+            "{}@{} to {} (`{}`)",
+            widened.name,
+            widened.referenced,
+            widened.version,
+            widened.requirement
+        );
+    }
+}
+
 /// Open `<root-dir>/wkg.lock` for read-write, creating it as an empty lockfile if missing.
 /// Mirrors `LockFile::load`'s create-if-absent semantics but at an explicit path.
-async fn load_or_create_lock(path: &Path) -> anyhow::Result<LockFile> {
+pub(crate) async fn load_or_create_lock(path: &Path) -> anyhow::Result<LockFile> {
     if !tokio::fs::try_exists(path).await? {
         let mut empty = LockFile::new_with_path([], path).await?;
         empty.write().await?;
@@ -337,23 +421,31 @@ fn merge_locked_packages(acc: &mut BTreeSet<LockedPackage>, incoming: BTreeSet<L
 
 impl UpdateArgs {
     pub async fn run(self) -> anyhow::Result<()> {
-        check_dir(&self.dir).await?;
-        let client = self.common.get_client().await?;
-        let manifest = Manifest::load().await?;
-        let mut lock_file = LockFile::load(false).await?;
-        // Clear the lock file since we're updating it
-        lock_file.packages.clear();
-        wit::fetch_dependencies(
-            &manifest,
-            self.dir,
-            &mut lock_file,
-            client,
-            self.output_type.unwrap_or_default(),
-        )
-        .await?;
-        // Now write out the lock file since everything else succeeded
-        lock_file.write().await?;
-        todo!()
+        if self.precise.is_some() && self.packages.len() != 1 {
+            anyhow::bail!("`--precise` requires exactly one package to update");
+        }
+        let context = FetchContext::discover(self.dir).await?;
+        let mut lock_file = load_or_create_lock(&context.lock_path).await?;
+        match (&self.packages[..], self.precise) {
+            // Clear the lock file since we're updating everything
+            ([], _) => lock_file.packages.clear(),
+            ([package], Some(version)) => {
+                let client = self.common.get_client().await?;
+                let release = client
+                    .get_release(package, &version)
+                    .await
+                    .with_context(|| format!("unable to find release {package}@{version}"))?;
+                lock_file.pin_package(package, &release.version, &release.content_digest)?;
+            }
+            (packages, _) => lock_file.unlock_packages(packages)?,
+        }
+        context
+            .fetch(
+                &self.common,
+                self.output_type.unwrap_or_default(),
+                lock_file,
+            )
+            .await
     }
 }
 
@@ -16,7 +16,10 @@ use testcontainers::{
 };
 use tokio::{net::TcpListener, process::Command};
 use wasm_pkg_client::{Config, CustomConfig, Registry, RegistryMetadata, oci::OciRegistryConfig};
-use wasm_pkg_core::wit::WIT_DEPS_DIR;
+use wasm_pkg_core::{
+    lock::{LOCK_FILE_NAME, LockFile},
+    wit::WIT_DEPS_DIR,
+};
 
 /// Returns an open port on localhost
 pub(crate) async fn find_open_port() -> u16 {
@@ -109,6 +112,86 @@ pub(crate) fn map_namespace(config: &Config, namespace: &str, registry: &Registr
     config
 }
 
+/// Returns a [`Config`] that uses a `local` registry rooted at the given directory as the default
+/// registry
+pub(crate) fn local_registry_config(root: &Path) -> Config {
+    let toml = format!(
+        r#"
+default_registry = "local"
+
+[registry."local"]
+type = "local"
+
+[registry."local".local]
+root = '{}'
+"#,
+        root.display(),
+    );
+    Config::from_toml(&toml).expect("local-backend config should parse")
+}
+
+/// Publishes an empty WIT package with a single `types` interface to the registry configured in
+/// `config` using `wkg publish`
+pub(crate) async fn publish_empty_package(
+    fixture: &Fixture,
+    config: &Config,
+    package: &str,
+    version: &str,
+) {
+    publish_package(fixture, config, package, version, "interface types {}").await
+}
+
+/// Publishes a WIT package with the given items to the registry configured in `config` using
+/// `wkg publish`
+pub(crate) async fn publish_package(
+    fixture: &Fixture,
+    config: &Config,
+    package: &str,
+    version: &str,
+    items: &str,
+) {
+    let dir = fixture
+        .temp_dir
+        .path()
+        .join("packages")
+        .join(format!("{}-{version}", package.replace(':', "-")));
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .expect("failed to create package dir");
+    tokio::fs::write(
+        dir.join("package.wit"),
+        format!("package {package}@{version};\n\n{items}\n"),
+    )
+    .await
+    .expect("failed to write package WIT");
+    // Build first so that the lock file of a package with dependencies is up to date
+    let status = fixture
+        .command_with_config(config)
+        .await
+        // Don't pick up the manifest of the fixture
+        .current_dir(&dir)
+        .args(["build", "--wit-dir", "."])
+        .status()
+        .await
+        .expect("spawn wkg build");
+    assert!(
+        status.success(),
+        "`wkg build` of {package}@{version} should succeed"
+    );
+    let status = fixture
+        .command_with_config(config)
+        .await
+        .current_dir(&dir)
+        .args(["publish", "."])
+        .status()
+        .await
+        .expect("spawn wkg publish");
+    assert!(
+        status.success(),
+        "`wkg publish` of {package}@{version} should succeed"
+    );
+}
+
 /// A loaded fixture with helpers for running wkg tests
 pub(crate) struct Fixture {
     pub temp_dir: tempfile::TempDir,
@@ -137,6 +220,35 @@ impl Fixture {
         cmd.env("WKG_CONFIG_FILE", config_path);
         cmd
     }
+
+    /// Runs `wkg` with the given arguments using [`Fixture::command_with_config`], returning whether
+    /// the command succeeded
+    pub(crate) async fn run_with_config(&self, config: &Config, args: &[&str]) -> bool {
+        self.command_with_config(config)
+            .await
+            .args(args)
+            .status()
+            .await
+            .expect("spawn wkg")
+            .success()
+    }
+
+    /// Returns the locked version of each of the given packages from the `wkg.lock` in the fixture
+    /// directory
+    pub(crate) async fn locked_versions<const N: usize>(&self, packages: [&str; N]) -> [String; N] {
+        let lock_path = self.fixture_path.join(LOCK_FILE_NAME);
+        let lock = LockFile::load_from_path(&lock_path, true)
+            .await
+            .expect("failed to load wkg.lock");
+        packages.map(|name| {
+            let pkg = lock
+                .packages
+                .iter()
+                .find(|p| p.name.to_string() == name)
+                .unwrap_or_else(|| panic!("wkg.lock should contain {name}"));
+            pkg.versions[0].version.to_string()
+        })
+    }
 }
 
 /// Gets the path to the fixture
@@ -1,5 +1,5 @@
 #[cfg(feature = "docker-tests")]
-use wasm_pkg_client::{Version, VersionInfo};
+use wasm_pkg_client::{Config, Registry, Version, VersionInfo};
 
 #[cfg(feature = "docker-tests")]
 use crate::common::{map_transitive_local_namespaces, publish_transitive_local};
@@ -270,6 +270,749 @@ async fn fetch_from_member_subdirectory_uses_member_context() {
     );
 }
 
+#[tokio::test]
+async fn update_selected_packages() {
+    const PACKAGES: [&str; 2] = ["example-a:foo", "example-b:bar"];
+
+    let fixture = common::load_fixture("update").await;
+    let config = common::local_registry_config(&fixture.temp_dir.path().join("registry"));
+    for package in PACKAGES {
+        common::publish_empty_package(&fixture, &config, package, "0.1.0").await;
+    }
+
+    assert!(fixture.run_with_config(&config, &["fetch"]).await);
+    assert_eq!(fixture.locked_versions(PACKAGES).await, ["0.1.0", "0.1.0"]);
+
+    for package in PACKAGES {
+        common::publish_empty_package(&fixture, &config, package, "0.1.1").await;
+    }
+
+    // Fetching again should keep the locked versions
+    assert!(fixture.run_with_config(&config, &["fetch"]).await);
+    assert_eq!(fixture.locked_versions(PACKAGES).await, ["0.1.0", "0.1.0"]);
+
+    assert!(
+        fixture
+            .run_with_config(&config, &["update", "example-a:foo"])
+            .await
+    );
+    assert_eq!(
+        fixture.locked_versions(PACKAGES).await,
+        ["0.1.1", "0.1.0"],
+        "only the selected package should be updated"
+    );
+
+    assert!(
+        fixture
+            .run_with_config(&config, &["update", "example-a:foo", "--precise", "0.1.0"])
+            .await
+    );
+    assert_eq!(fixture.locked_versions(PACKAGES).await, ["0.1.0", "0.1.0"]);
+
+    assert!(fixture.run_with_config(&config, &["update"]).await);
+    assert_eq!(fixture.locked_versions(PACKAGES).await, ["0.1.1", "0.1.1"]);
+
+    common::publish_empty_package(&fixture, &config, "example-b:bar", "0.2.0").await;
+    assert!(
+        !fixture
+            .run_with_config(&config, &["update", "example-b:bar", "--precise", "0.2.0"])
+            .await,
+        "updating to a version that does not satisfy the requirement should fail"
+    );
+    assert_eq!(fixture.locked_versions(PACKAGES).await, ["0.1.1", "0.1.1"]);
+}
+
+#[tokio::test]
+async fn update_selected_packages_in_workspace() {
+    const PACKAGES: [&str; 2] = ["example-a:foo", "example-b:bar"];
+
+    let fixture = common::load_fixture("update-workspace").await;
+    let config = common::local_registry_config(&fixture.temp_dir.path().join("registry"));
+    for package in PACKAGES {
+        common::publish_empty_package(&fixture, &config, package, "0.1.0").await;
+    }
+
+    assert!(fixture.run_with_config(&config, &["fetch"]).await);
+    assert_eq!(fixture.locked_versions(PACKAGES).await, ["0.1.0", "0.1.0"]);
+
+    for package in PACKAGES {
+        common::publish_empty_package(&fixture, &config, package, "0.1.1").await;
+    }
+
+    assert!(
+        fixture
+            .run_with_config(&config, &["update", "example-a:foo"])
+            .await
+    );
+    assert_eq!(
+        fixture.locked_versions(PACKAGES).await,
+        ["0.1.1", "0.1.0"],
+        "only the selected package should be updated"
+    );
+}
+
+#[tokio::test]
+async fn yanked_releases_are_skipped_unless_locked() {
+    const PACKAGES: [&str; 2] = ["example-a:foo", "example-b:bar"];
+
+    let fixture = common::load_fixture("update").await;
+    let config = common::local_registry_config(&fixture.temp_dir.path().join("registry"));
+    for (package, version) in [
+        ("example-a:foo", "0.1.0"),
+        ("example-a:foo", "0.1.1"),
+        ("example-b:bar", "0.1.0"),
+    ] {
+        common::publish_empty_package(&fixture, &config, package, version).await;
+    }
+
+    assert!(fixture.run_with_config(&config, &["fetch"]).await);
+    assert_eq!(fixture.locked_versions(PACKAGES).await, ["0.1.1", "0.1.0"]);
+
+    assert!(
+        fixture
+            .run_with_config(&config, &["yank", "example-a:foo@0.1.1"])
+            .await
+    );
+    assert!(
+        fixture.run_with_config(&config, &["fetch"]).await,
+        "a lock file pinned to a yanked release should keep working"
+    );
+    assert_eq!(fixture.locked_versions(PACKAGES).await, ["0.1.1", "0.1.0"]);
+
+    assert!(fixture.run_with_config(&config, &["update"]).await);
+    assert_eq!(
+        fixture.locked_versions(PACKAGES).await,
+        ["0.1.0", "0.1.0"],
+        "yanked releases should not be selected"
+    );
+
+    assert!(
+        fixture
+            .run_with_config(&config, &["yank", "--undo", "example-a:foo@0.1.1"])
+            .await
+    );
+    assert!(fixture.run_with_config(&config, &["update"]).await);
+    assert_eq!(fixture.locked_versions(PACKAGES).await, ["0.1.1", "0.1.0"]);
+
+    assert!(
+        !fixture
+            .run_with_config(&config, &["yank", "example-a:foo@0.2.0"])
+            .await,
+        "yanking a release that doesn't exist should fail"
+    );
+}
+
+#[tokio::test]
+async fn offline_uses_lock_file_and_cache() {
+    const PACKAGES: [&str; 2] = ["example-a:foo", "example-b:bar"];
+
+    let fixture = common::load_fixture("update").await;
+    let registry = fixture.temp_dir.path().join("registry");
+    let config = common::local_registry_config(&registry);
+    for package in PACKAGES {
+        common::publish_empty_package(&fixture, &config, package, "0.1.0").await;
+    }
+    assert!(fixture.run_with_config(&config, &["fetch"]).await);
+    let lock = tokio::fs::read_to_string(fixture.fixture_path.join("wkg.lock"))
+        .await
+        .unwrap();
+
+    // Nothing can be loaded from the registry anymore
+    tokio::fs::remove_dir_all(&registry).await.unwrap();
+
+    assert!(
+        fixture
+            .run_with_config(&config, &["fetch", "--offline"])
+            .await
+    );
+    assert_eq!(
+        tokio::fs::read_to_string(fixture.fixture_path.join("wkg.lock"))
+            .await
+            .unwrap(),
+        lock,
+        "fetching offline should keep the lock file as is"
+    );
+    let status = fixture
+        .command_with_config(&config)
+        .await
+        .env("WKG_OFFLINE", "true")
+        .arg("build")
+        .status()
+        .await
+        .expect("spawn wkg build");
+    assert!(status.success(), "`WKG_OFFLINE` should enable offline mode");
+    assert!(
+        fixture
+            .run_with_config(
+                &config,
+                &[
+                    "get",
+                    "--offline",
+                    "example-a:foo@0.1.0",
+                    "-o",
+                    "./foo.wasm"
+                ],
+            )
+            .await,
+        "getting a cached release offline should succeed"
+    );
+
+    assert!(
+        !fixture
+            .run_with_config(&config, &["get", "--offline", "example-a:foo@0.1.1"])
+            .await,
+        "getting a release that isn't cached should fail"
+    );
+    let empty_cache = fixture.temp_dir.path().join("empty-cache");
+    assert!(
+        !fixture
+            .run_with_config(
+                &config,
+                &[
+                    "fetch",
+                    "--offline",
+                    "--cache",
+                    empty_cache.to_str().unwrap()
+                ],
+            )
+            .await,
+        "fetching offline without a cache should fail"
+    );
+    assert!(
+        !fixture
+            .run_with_config(
+                &config,
+                &[
+                    "update",
+                    "--offline",
+                    "--cache",
+                    empty_cache.to_str().unwrap()
+                ],
+            )
+            .await,
+        "resolving dependencies that aren't locked offline without cached versions should fail"
+    );
+    assert!(
+        fixture
+            .run_with_config(&config, &["update", "--offline"])
+            .await,
+        "dependencies that aren't locked should be resolved from the cached versions offline"
+    );
+    assert_eq!(
+        tokio::fs::read_to_string(fixture.fixture_path.join("wkg.lock"))
+            .await
+            .unwrap(),
+        lock,
+        "the cached versions should resolve to the same versions"
+    );
+}
+
+#[tokio::test]
+async fn offline_and_frozen_fetch_workspace_members() {
+    const PACKAGES: [&str; 2] = ["example-a:foo", "example-b:bar"];
+
+    let fixture = common::load_fixture("offline-workspace").await;
+    let registry = fixture.temp_dir.path().join("registry");
+    let config = common::local_registry_config(&registry);
+    for package in PACKAGES {
+        common::publish_empty_package(&fixture, &config, package, "0.1.0").await;
+    }
+    assert!(fixture.run_with_config(&config, &["fetch"]).await);
+    let lock_path = fixture.fixture_path.join("wkg.lock");
+    let lock = tokio::fs::read_to_string(&lock_path).await.unwrap();
+
+    // Nothing can be loaded from the registry anymore, and the member `app` depends on `base`
+    tokio::fs::remove_dir_all(&registry).await.unwrap();
+
+    for args in [
+        &["fetch", "--offline"][..],
+        &["fetch", "--frozen"],
+        &["tree", "--offline"],
+    ] {
+        assert!(
+            fixture.run_with_config(&config, args).await,
+            "`wkg {args:?}` should succeed for workspace members"
+        );
+        assert_eq!(
+            tokio::fs::read_to_string(&lock_path).await.unwrap(),
+            lock,
+            "`wkg {args:?}` should keep the lock file as is"
+        );
+    }
+}
+
+#[tokio::test]
+async fn locked_and_frozen_keep_lock_file() {
+    const PACKAGES: [&str; 2] = ["example-a:foo", "example-b:bar"];
+
+    let fixture = common::load_fixture("update").await;
+    let registry = fixture.temp_dir.path().join("registry");
+    let config = common::local_registry_config(&registry);
+    for package in PACKAGES {
+        common::publish_empty_package(&fixture, &config, package, "0.1.0").await;
+    }
+    let lock_path = fixture.fixture_path.join("wkg.lock");
+    assert!(
+        !fixture
+            .run_with_config(&config, &["fetch", "--locked"])
+            .await,
+        "fetching with `--locked` should fail if the dependencies aren't locked"
+    );
+    assert!(fixture.run_with_config(&config, &["fetch"]).await);
+    let lock = tokio::fs::read_to_string(&lock_path).await.unwrap();
+    assert!(
+        fixture
+            .run_with_config(&config, &["fetch", "--locked"])
+            .await,
+        "fetching with `--locked` should succeed if the lock file is up to date"
+    );
+    assert!(
+        fixture
+            .run_with_config(&config, &["build", "--locked"])
+            .await,
+        "building with `--locked` should succeed if the lock file is up to date"
+    );
+
+    // Requiring a version that isn't locked yet needs the lock file to change
+    common::publish_empty_package(&fixture, &config, "example-b:bar", "0.1.1").await;
+    let manifest_path = fixture.fixture_path.join("wkg.toml");
+    let manifest = tokio::fs::read_to_string(&manifest_path).await.unwrap();
+    tokio::fs::write(
+        &manifest_path,
+        manifest.replace(
+            "[overrides.\"example-b:bar\"]\nversion = \"^0.1.0\"",
+            "[overrides.\"example-b:bar\"]\nversion = \"=0.1.1\"",
+        ),
+    )
+    .await
+    .unwrap();
+    for args in [["fetch", "--locked"], ["build", "--locked"]] {
+        assert!(
+            !fixture.run_with_config(&config, &args).await,
+            "`{}` should fail if the lock file needs to change",
+            args.join(" ")
+        );
+    }
+    assert_eq!(
+        tokio::fs::read_to_string(&lock_path).await.unwrap(),
+        lock,
+        "a failed locked run should keep the lock file as is"
+    );
+
+    // Dropping a dependency changes the lock file as well, which is only noticed after building
+    tokio::fs::write(
+        &manifest_path,
+        manifest.replace("[overrides.\"example-b:bar\"]\nversion = \"^0.1.0\"\n", ""),
+    )
+    .await
+    .unwrap();
+    let output = fixture.fixture_path.join("locked.wasm");
+    assert!(
+        !fixture
+            .run_with_config(
+                &config,
+                &["build", "--locked", "-o", output.to_str().unwrap()]
+            )
+            .await,
+        "building with `--locked` should fail if a locked dependency is no longer needed"
+    );
+    assert!(
+        !output.exists(),
+        "nothing should be built if the lock file needs to change"
+    );
+    tokio::fs::write(&manifest_path, manifest).await.unwrap();
+
+    // Nothing can be loaded from the registry anymore
+    tokio::fs::remove_dir_all(&registry).await.unwrap();
+    assert!(
+        fixture
+            .run_with_config(&config, &["fetch", "--frozen"])
+            .await,
+        "fetching with `--frozen` should only use the lock file and the cache"
+    );
+    assert_eq!(tokio::fs::read_to_string(&lock_path).await.unwrap(), lock);
+}
+
+#[tokio::test]
+async fn vendor_copies_locked_dependencies() {
+    const PACKAGES: [&str; 2] = ["example-a:foo", "example-b:bar"];
+
+    let fixture = common::load_fixture("update").await;
+    let registry = fixture.temp_dir.path().join("registry");
+    let config = common::local_registry_config(&registry);
+    for package in PACKAGES {
+        common::publish_empty_package(&fixture, &config, package, "0.1.0").await;
+    }
+    assert!(fixture.run_with_config(&config, &["fetch"]).await);
+
+    // Packages that are locked without a registry are mapped to the default registry
+    let lock = tokio::fs::read_to_string(fixture.fixture_path.join("wkg.lock"))
+        .await
+        .unwrap();
+    let unregistered_lock = fixture.temp_dir.path().join("unregistered.lock");
+    tokio::fs::write(
+        &unregistered_lock,
+        lock.replace(
+            "name = \"example-b:bar\"\nregistry = \"local\"\n",
+            "name = \"example-b:bar\"\n",
+        ),
+    )
+    .await
+    .unwrap();
+
+    // The directory is relative to the fixture, but the generated config has to work anywhere
+    let vendor_dir = fixture.fixture_path.join("vendor");
+    let vendor_config = fixture.temp_dir.path().join("vendor.toml");
+    assert!(
+        fixture
+            .run_with_config(
+                &config,
+                &[
+                    "vendor",
+                    "vendor",
+                    "--lock-file",
+                    unregistered_lock.to_str().unwrap(),
+                    "--config-out",
+                    vendor_config.to_str().unwrap(),
+                ],
+            )
+            .await
+    );
+    for path in ["example-a/foo/0.1.0.wasm", "example-b/bar/0.1.0.wasm"] {
+        assert!(
+            vendor_dir.join(path).is_file(),
+            "{path} should have been vendored"
+        );
+    }
+
+    let vendor_config = Config::from_file(&vendor_config).await.unwrap();
+    let local: Registry = "local".parse().unwrap();
+    for package in PACKAGES {
+        assert_eq!(
+            vendor_config.resolve_registry(&package.parse().unwrap()),
+            Some(&local),
+            "{package} should be mapped to the vendored packages"
+        );
+    }
+    let root: std::collections::HashMap<String, std::path::PathBuf> = vendor_config
+        .registry_config(&local)
+        .unwrap()
+        .backend_config("local")
+        .unwrap()
+        .unwrap();
+    assert!(root["root"].is_absolute(), "{}", root["root"].display());
+
+    // Building only needs the vendored dependencies now, even with an empty cache
+    tokio::fs::remove_dir_all(&registry).await.unwrap();
+    let empty_cache = fixture.temp_dir.path().join("empty-cache");
+    assert!(
+        fixture
+            .run_with_config(
+                &vendor_config,
+                &[
+                    "build",
+                    "--locked",
+                    "--cache",
+                    empty_cache.to_str().unwrap()
+                ],
+            )
+            .await,
+        "building with the vendored dependencies should succeed"
+    );
+}
+
+#[tokio::test]
+async fn cache_commands_manage_cached_packages() {
+    let fixture = common::load_fixture("update").await;
+    let config = common::local_registry_config(&fixture.temp_dir.path().join("registry"));
+    for package in ["example-a:foo", "example-b:bar"] {
+        common::publish_empty_package(&fixture, &config, package, "0.1.0").await;
+    }
+    assert!(fixture.run_with_config(&config, &["fetch"]).await);
+
+    let list_cache = || async {
+        let output = fixture
+            .command_with_config(&config)
+            .await
+            .args(["cache", "list", "--format", "json"])
+            .output()
+            .await
+            .expect("spawn wkg cache list");
+        assert!(output.status.success());
+        serde_json::from_slice::<Vec<serde_json::Value>>(&output.stdout).unwrap()
+    };
+    let entries = list_cache().await;
+    let packages: Vec<&str> = entries
+        .iter()
+        .flat_map(|entry| entry["releases"].as_array().unwrap())
+        .map(|release| release["package"].as_str().unwrap())
+        .collect();
+    assert_eq!(packages.len(), 2);
+    assert!(packages.contains(&"example-a:foo") && packages.contains(&"example-b:bar"));
+
+    // Corrupt one of the cached packages
+    let cache_dir = fixture.temp_dir.path().join("cache");
+    let corrupted = entries[0]["digest"].as_str().unwrap();
+    tokio::fs::write(cache_dir.join(corrupted), b"corrupted")
+        .await
+        .unwrap();
+    assert!(fixture.run_with_config(&config, &["cache", "verify"]).await);
+    let entries = list_cache().await;
+    assert_eq!(entries.len(), 1, "corrupted data should be removed");
+    assert_ne!(entries[0]["digest"].as_str().unwrap(), corrupted);
+
+    assert!(
+        !fixture.run_with_config(&config, &["cache", "prune"]).await,
+        "pruning requires a limit"
+    );
+    assert!(
+        fixture
+            .run_with_config(&config, &["cache", "prune", "--older-than", "1d"])
+            .await
+    );
+    assert_eq!(
+        list_cache().await.len(),
+        1,
+        "recently used data should be kept"
+    );
+    assert!(
+        fixture
+            .run_with_config(&config, &["cache", "prune", "--max-size", "0"])
+            .await
+    );
+    assert!(list_cache().await.is_empty());
+
+    // Fill the cache again before cleaning it
+    assert!(fixture.run_with_config(&config, &["fetch"]).await);
+    assert!(fixture.run_with_config(&config, &["cache", "clean"]).await);
+    assert!(list_cache().await.is_empty());
+}
+
+#[tokio::test]
+async fn outdated_reports_newer_releases() {
+    let fixture = common::load_fixture("update").await;
+    let config = common::local_registry_config(&fixture.temp_dir.path().join("registry"));
+    for package in ["example-a:foo", "example-b:bar"] {
+        common::publish_empty_package(&fixture, &config, package, "0.1.0").await;
+    }
+    assert!(fixture.run_with_config(&config, &["fetch"]).await);
+    assert!(
+        fixture
+            .run_with_config(&config, &["outdated", "--exit-code"])
+            .await,
+        "freshly fetched dependencies should be up to date"
+    );
+
+    for (package, version) in [("example-a:foo", "0.1.1"), ("example-b:bar", "0.2.0")] {
+        common::publish_empty_package(&fixture, &config, package, version).await;
+    }
+    let output = fixture
+        .command_with_config(&config)
+        .await
+        .args(["outdated", "--format", "json"])
+        .output()
+        .await
+        .expect("spawn wkg outdated");
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        report,
+        serde_json::json!([
+            {
+                "package": "example-a:foo",
+                "registry": "local",
+                "requirement": "^0.1.0",
+                "current": "0.1.0",
+                "compatible": "0.1.1",
+                "latest": "0.1.1",
+            },
+            {
+                "package": "example-b:bar",
+                "registry": "local",
+                "requirement": "^0.1.0",
+                "current": "0.1.0",
+                "compatible": "0.1.0",
+                "latest": "0.2.0",
+            },
+        ])
+    );
+    assert!(
+        !fixture
+            .run_with_config(&config, &["outdated", "--exit-code"])
+            .await,
+        "outdated dependencies should fail with `--exit-code`"
+    );
+
+    // Yanked releases aren't reported
+    assert!(fixture.run_with_config(&config, &["update"]).await);
+    assert!(
+        fixture
+            .run_with_config(&config, &["yank", "example-b:bar@0.2.0"])
+            .await
+    );
+    assert!(
+        fixture
+            .run_with_config(&config, &["outdated", "--exit-code"])
+            .await
+    );
+
+    // Neither are prereleases of locked releases
+    common::publish_empty_package(&fixture, &config, "example-a:foo", "0.2.0-rc.1").await;
+    assert!(
+        fixture
+            .run_with_config(&config, &["outdated", "--exit-code"])
+            .await,
+        "prereleases shouldn't be reported"
+    );
+
+    // Releases are listed from the locked registry, even if the packages now resolve to another one
+    common::publish_empty_package(&fixture, &config, "example-a:foo", "0.1.2").await;
+    let moved = wasm_pkg_client::Config::from_toml(&format!(
+        r#"
+default_registry = "moved"
+
+[registry."moved".local]
+root = '{}'
+
+[registry."local".local]
+root = '{}'
+"#,
+        fixture.temp_dir.path().join("moved").display(),
+        fixture.temp_dir.path().join("registry").display(),
+    ))
+    .unwrap();
+    let output = fixture
+        .command_with_config(&moved)
+        .await
+        .args(["outdated", "--format", "json"])
+        .output()
+        .await
+        .expect("spawn wkg outdated");
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report[0]["package"], "example-a:foo");
+    assert_eq!(report[0]["compatible"], "0.1.2");
+}
+
+#[tokio::test]
+async fn tree_prints_resolved_dependencies() {
+    let fixture = common::load_fixture("tree").await;
+    let config = common::local_registry_config(&fixture.temp_dir.path().join("registry"));
+    common::publish_package(
+        &fixture,
+        &config,
+        "example-c:baz",
+        "0.1.0",
+        "interface types {\n  type id = u32;\n}",
+    )
+    .await;
+    common::publish_package(
+        &fixture,
+        &config,
+        "example-b:bar",
+        "0.1.0",
+        "interface types {\n  use example-c:baz/types@0.1.0.{id};\n}",
+    )
+    .await;
+    common::publish_package(
+        &fixture,
+        &config,
+        "example-a:foo",
+        "0.1.0",
+        "interface types {\n  use example-b:bar/types@0.1.0.{id};\n}",
+    )
+    .await;
+
+    let tree = |args: &'static [&'static str]| {
+        let fixture = &fixture;
+        let config = &config;
+        async move {
+            let output = fixture
+                .command_with_config(config)
+                .await
+                .arg("tree")
+                .args(args)
+                .output()
+                .await
+                .expect("spawn wkg tree");
+            assert!(
+                output.status.success(),
+                "`wkg tree {args:?}` should succeed"
+            );
+            String::from_utf8(output.stdout).expect("output should be UTF-8")
+        }
+    };
+
+    let text = tree(&[]).await;
+    let lines: Vec<_> = text.lines().collect();
+    assert_eq!(lines[0], "tree:app@0.1.0 (wit)");
+    assert!(
+        lines[1].starts_with("├── example-a:foo@0.1.0 (registry: local, sha256:"),
+        "{text}"
+    );
+    assert!(
+        lines[2].starts_with("│   ├── example-b:bar@0.1.0 (registry: local, sha256:"),
+        "{text}"
+    );
+    assert!(
+        lines[3].starts_with("│   │   └── example-c:baz@0.1.0 (registry: local, sha256:"),
+        "{text}"
+    );
+    assert!(
+        lines[4].starts_with("│   └── example-c:baz@0.1.0 (registry: local, sha256:"),
+        "{text}"
+    );
+    // Subtrees that were already expanded aren't repeated
+    assert!(
+        lines[5].starts_with("├── example-b:bar@0.1.0 (registry: local, sha256:")
+            && lines[5].ends_with(" (*)"),
+        "{text}"
+    );
+    assert!(
+        lines[6].starts_with("└── example-c:baz@0.1.0 (registry: local, sha256:"),
+        "{text}"
+    );
+
+    let json: serde_json::Value = serde_json::from_str(&tree(&["--format", "json"]).await).unwrap();
+    let root = &json[0];
+    assert_eq!(root["package"], "tree:app");
+    assert_eq!(root["source"], "root");
+    let foo = &root["dependencies"][0];
+    assert_eq!(foo["package"], "example-a:foo");
+    assert_eq!(foo["version"], "0.1.0");
+    assert_eq!(foo["source"], "registry");
+    assert_eq!(foo["registry"], "local");
+    assert!(foo["digest"].as_str().unwrap().starts_with("sha256:"));
+    assert_eq!(foo["dependencies"][0]["package"], "example-b:bar");
+    assert_eq!(
+        foo["dependencies"][0]["dependencies"][0]["package"],
+        "example-c:baz"
+    );
+    let bar = &root["dependencies"][1];
+    assert_eq!(bar["package"], "example-b:bar");
+    assert_eq!(bar["deduplicated"], true);
+    assert!(bar.get("dependencies").is_none());
+
+    let json: serde_json::Value =
+        serde_json::from_str(&tree(&["--invert", "example-b:bar", "--format", "json"]).await)
+            .unwrap();
+    let bar = &json[0];
+    assert_eq!(bar["package"], "example-b:bar");
+    let dependents: Vec<_> = bar["dependents"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|dep| dep["package"].as_str().unwrap())
+        .collect();
+    assert_eq!(dependents, ["example-a:foo", "tree:app"]);
+    assert_eq!(bar["dependents"][0]["dependents"][0]["package"], "tree:app");
+
+    assert!(
+        !fixture.fixture_path.join("wkg.lock").exists(),
+        "`wkg tree` should not create a lock file"
+    );
+}
+
 #[tokio::test]
 pub async fn check() {
     // Use an explicit config that maps `wasi` to `wasi.dev`.